@@ -0,0 +1,82 @@
+//! Runs the fan curves of `[[fan_curve]]` against a fake sysfs tree, prints
+//! the duty for a rising and falling temperature and the restored
+//! `pwm1_enable`.
+//!
+//! ```sh
+//! cargo run --example fake_hwmon
+//! ```
+
+#[allow(dead_code)]
+#[path = "../src/core/fancurve.rs"]
+mod fancurve;
+
+use fancurve::FanControl;
+use fancurve::FanCurveConfig;
+use fancurve::SysfsPwmWriter;
+use fancurve::pwm_to_duty;
+use fancurve::read_sysfs_value;
+use fancurve::restore_auto_fan_control;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+#[derive(Deserialize)]
+struct Config {
+	fan_curve: Vec<FanCurveConfig>,
+}
+
+const CONFIG: &str = r#"
+[[fan_curve]]
+chip = "nct6798"
+pwm = 1
+temp_chip = "k10temp"
+temp = 1
+points = [[40, 25], [60, 45], [80, 100]]
+hysteresis = 3.0
+min_duty = 30
+"#;
+
+fn write(path: impl AsRef<Path>, data: &str) {
+	let path = path.as_ref();
+	fs::create_dir_all(path.parent().unwrap()).unwrap();
+	fs::write(path, data).unwrap();
+}
+
+fn main() {
+	let sys = std::env::temp_dir().join(format!("fake-sys-{}", std::process::id()));
+	let pwm_chip = sys.join("class/hwmon/hwmon2");
+	let temp_chip = sys.join("class/hwmon/hwmon1");
+	write(pwm_chip.join("name"), "nct6798\n");
+	write(pwm_chip.join("pwm1"), "128\n");
+	write(pwm_chip.join("pwm1_enable"), "5\n");
+	write(pwm_chip.join("fan1_input"), "900\n");
+	write(temp_chip.join("name"), "k10temp\n");
+	write(temp_chip.join("temp1_input"), "35000\n");
+
+	let config: Config = toml::from_str(CONFIG).unwrap();
+	let mut fan_control = FanControl::new(&sys, &config.fan_curve, SysfsPwmWriter);
+	for temp in [35, 50, 62, 70, 68, 66, 64, 50, 20] {
+		write(temp_chip.join("temp1_input"), &format!("{}000\n", temp));
+		fan_control.tick();
+
+		let pwm = read_sysfs_value::<u8>(pwm_chip.join("pwm1")).unwrap();
+		let enable = read_sysfs_value::<u8>(pwm_chip.join("pwm1_enable")).unwrap();
+		println!(
+			"{:>3} °C -> pwm1 {:>3} ({:>3.0}%), pwm1_enable {}",
+			temp,
+			pwm,
+			pwm_to_duty(pwm),
+			enable
+		);
+	}
+
+	restore_auto_fan_control();
+	fan_control.tick();
+	println!(
+		"restored, pwm1_enable {}",
+		read_sysfs_value::<u8>(pwm_chip.join("pwm1_enable")).unwrap()
+	);
+
+	drop(fan_control);
+	let _e = fs::remove_dir_all(&sys);
+}
@@ -0,0 +1,48 @@
+//! Synthetic frame-time client, streams frame presents to a running
+//! `machinepmmeter`.
+//!
+//! `cargo run --example frametime_client -- [fps] [seconds]`, every 100th frame
+//! is a 3x stutter.
+
+use machinepmmeter_frametimeproto::FrametimeMessage;
+use machinepmmeter_frametimeproto::frametime_socket_path;
+use std::borrow::Cow;
+use std::io::BufWriter;
+use std::io::Write;
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+use std::time::Instant;
+
+fn main() -> std::io::Result<()> {
+	let mut args = std::env::args().skip(1);
+	let fps: f64 = args.next().and_then(|a| a.parse().ok()).unwrap_or(60.0);
+	let seconds: f64 = args.next().and_then(|a| a.parse().ok()).unwrap_or(30.0);
+
+	let path = frametime_socket_path();
+	println!("Connecting to {:?}, {} FPS for {} s.", path, fps, seconds);
+	let mut stream = BufWriter::new(UnixStream::connect(&path)?);
+	FrametimeMessage::Hello {
+		pid: std::process::id(),
+		exe: Cow::Borrowed("frametime_client"),
+	}
+	.write_to(&mut stream)?;
+
+	let start = Instant::now();
+	let frametime = Duration::from_secs_f64(1.0 / fps);
+	let mut frame = 0u64;
+	while start.elapsed().as_secs_f64() < seconds {
+		frame += 1;
+		std::thread::sleep(match frame % 100 {
+			0 => frametime * 3,
+			_ => frametime,
+		});
+
+		FrametimeMessage::Frame {
+			timestamp_ns: start.elapsed().as_nanos() as u64,
+		}
+		.write_to(&mut stream)?;
+		stream.flush()?;
+	}
+
+	Ok(())
+}
@@ -0,0 +1,88 @@
+//! Stand-in for the Feral GameMode daemon (`com.feralinteractive.GameMode` on
+//! the session bus), enough for the "gamemode" page.
+//!
+//! ```sh
+//! dbus-run-session -- sh -c '
+//!     cargo run --example gamemode_standin &
+//!     sleep 1
+//!     machinepmmeter &
+//!     dbus-send --session --print-reply --dest=com.feralinteractive.GameMode \
+//!         /com/feralinteractive/GameMode com.feralinteractive.GameMode.RegisterGame int32:$$
+//!     ...'
+//! ```
+
+use dbus::MethodErr;
+use dbus::Path;
+use dbus::blocking::Connection;
+use dbus_crossroads::Crossroads;
+use std::collections::BTreeMap;
+
+const GAMEMODE_DEST: &str = "com.feralinteractive.GameMode";
+const GAMEMODE_PATH: &str = "/com/feralinteractive/GameMode";
+
+#[derive(Debug, Default)]
+struct Daemon {
+	/// pid -> executable
+	games: BTreeMap<i32, String>,
+}
+
+#[derive(Debug)]
+struct Game {
+	pid: i32,
+	exe: String,
+}
+
+fn game_path(pid: i32) -> Path<'static> {
+	Path::from(format!("{}/Games/{}", GAMEMODE_PATH, pid))
+}
+
+fn main() -> Result<(), dbus::Error> {
+	let conn = Connection::new_session()?;
+	conn.request_name(GAMEMODE_DEST, false, true, false)?;
+
+	let mut cr = Crossroads::new();
+	let game_token = cr.register("com.feralinteractive.GameMode.Game", |b| {
+		b.property("ProcessId").get(|_, game: &mut Game| Ok(game.pid));
+		b.property("Executable").get(|_, game: &mut Game| Ok(game.exe.clone()));
+	});
+	let daemon_token = cr.register("com.feralinteractive.GameMode", move |b| {
+		b.property("ClientCount")
+			.get(|_, daemon: &mut Daemon| Ok(daemon.games.len() as i32));
+		b.method("ListGames", (), ("games",), |_, daemon: &mut Daemon, ()| {
+			let games: Vec<(i32, Path<'static>)> =
+				daemon.games.keys().map(|pid| (*pid, game_path(*pid))).collect();
+
+			Ok((games,))
+		});
+		b.method_with_cr("RegisterGame", ("pid",), ("result",), move |ctx, cr, (pid,): (i32,)| {
+			let exe = std::fs::read_link(format!("/proc/{}/exe", pid))
+				.map(|a| a.to_string_lossy().into_owned())
+				.unwrap_or_default();
+			let daemon: &mut Daemon = cr
+				.data_mut(ctx.path())
+				.ok_or_else(|| MethodErr::no_path(ctx.path()))?;
+			daemon.games.insert(pid, exe.clone());
+			println!("RegisterGame {} {:?}", pid, exe);
+
+			cr.insert(game_path(pid), &[game_token], Game { pid, exe });
+			Ok((0i32,))
+		});
+		b.method_with_cr("UnregisterGame", ("pid",), ("result",), |ctx, cr, (pid,): (i32,)| {
+			let daemon: &mut Daemon = cr
+				.data_mut(ctx.path())
+				.ok_or_else(|| MethodErr::no_path(ctx.path()))?;
+			let result = match daemon.games.remove(&pid) {
+				Some(_) => 0i32,
+				None => -1,
+			};
+			println!("UnregisterGame {}", pid);
+
+			let _e = cr.remove::<Game>(&game_path(pid));
+			Ok((result,))
+		});
+	});
+	cr.insert(GAMEMODE_PATH, &[daemon_token], Daemon::default());
+
+	println!("{} is ready", GAMEMODE_DEST);
+	cr.serve(&conn)
+}
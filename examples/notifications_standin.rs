@@ -0,0 +1,120 @@
+//! Stand-in for a notification server (`org.freedesktop.Notifications`),
+//! prints the alert notifications of the dock. Run both on a private session
+//! bus, so the real notification daemon is not involved:
+//!
+//! ```sh
+//! dbus-run-session -- sh -c '
+//!     cargo run --example notifications_standin -- mute &
+//!     sleep 1
+//!     machinepmmeter'
+//! ```
+//!
+//! The optional argument is an action key (`show-dock`, `mute`), invoked right
+//! after every notification.
+
+use dbus::arg::PropMap;
+use dbus::blocking::Connection;
+use dbus_crossroads::Crossroads;
+
+const NOTIFICATIONS_DEST: &str = "org.freedesktop.Notifications";
+const NOTIFICATIONS_PATH: &str = "/org/freedesktop/Notifications";
+
+#[derive(Debug)]
+struct Server {
+	next_id: u32,
+	invoke: Option<String>,
+}
+
+fn main() -> Result<(), dbus::Error> {
+	let invoke = std::env::args().nth(1);
+
+	let conn = Connection::new_session()?;
+	conn.request_name(NOTIFICATIONS_DEST, false, true, false)?;
+
+	let mut cr = Crossroads::new();
+	let token = cr.register("org.freedesktop.Notifications", |b| {
+		let action_invoked = b
+			.signal::<(u32, String), _>("ActionInvoked", ("id", "action_key"))
+			.msg_fn();
+		b.method(
+			"Notify",
+			(
+				"app_name",
+				"replaces_id",
+				"app_icon",
+				"summary",
+				"body",
+				"actions",
+				"hints",
+				"expire_timeout",
+			),
+			("id",),
+			move |ctx,
+			      server: &mut Server,
+			      (app_name, replaces_id, icon, summary, body, actions, _hints, _expire): (
+				String,
+				u32,
+				String,
+				String,
+				String,
+				Vec<String>,
+				PropMap,
+				i32,
+			)| {
+				let id = match replaces_id {
+					0 => {
+						server.next_id += 1;
+						server.next_id
+					}
+					id => id,
+				};
+				println!(
+					"Notify #{} ({}, {}) {}: {:?}, actions: {:?}",
+					id, app_name, icon, summary, body, actions
+				);
+				if let Some(key) = server.invoke.as_ref()
+					&& actions.contains(key)
+				{
+					println!("ActionInvoked #{} {}", id, key);
+					let path = NOTIFICATIONS_PATH.into();
+					ctx.push_msg(action_invoked(&path, &(id, key.clone())));
+				}
+
+				Ok((id,))
+			},
+		);
+		b.method(
+			"CloseNotification",
+			("id",),
+			(),
+			|_, _server: &mut Server, (id,): (u32,)| {
+				println!("CloseNotification #{}", id);
+
+				Ok(())
+			},
+		);
+		b.method(
+			"GetCapabilities",
+			(),
+			("capabilities",),
+			|_, _server: &mut Server, _: ()| Ok((vec!["actions".to_string(), "body".to_string()],)),
+		);
+		b.method(
+			"GetServerInformation",
+			(),
+			("name", "vendor", "version", "spec_version"),
+			|_, _server: &mut Server, _: ()| {
+				Ok((
+					"notifications_standin".to_string(),
+					"machinepmmeter".to_string(),
+					env!("CARGO_PKG_VERSION").to_string(),
+					"1.2".to_string(),
+				))
+			},
+		);
+	});
+	cr.insert(NOTIFICATIONS_PATH, &[token], Server { next_id: 0, invoke });
+
+	println!("{} is ready", NOTIFICATIONS_DEST);
+	cr.serve(&conn)
+}
@@ -0,0 +1,78 @@
+//! Stand-in for power-profiles-daemon (`net.hadess.PowerProfiles`), enough
+//! for the dock head and profile switching. The dock talks to the system bus,
+//! so point it at a private bus:
+//!
+//! ```sh
+//! dbus-run-session -- sh -c '
+//!     export DBUS_SYSTEM_BUS_ADDRESS=$DBUS_SESSION_BUS_ADDRESS
+//!     cargo run --example powerprofiles_standin -- lap-detected &
+//!     sleep 1
+//!     machinepmmeter'
+//! ```
+//!
+//! The optional argument is the `PerformanceDegraded` reason.
+
+use dbus::MethodErr;
+use dbus::arg::PropMap;
+use dbus::arg::Variant;
+use dbus::blocking::Connection;
+use dbus_crossroads::Crossroads;
+
+const POWER_PROFILES_DEST: &str = "net.hadess.PowerProfiles";
+const POWER_PROFILES_PATH: &str = "/net/hadess/PowerProfiles";
+const PROFILES: [&str; 3] = ["power-saver", "balanced", "performance"];
+
+#[derive(Debug)]
+struct Daemon {
+	active: String,
+	degraded: String,
+}
+
+fn main() -> Result<(), dbus::Error> {
+	let degraded = std::env::args().nth(1).unwrap_or_default();
+
+	let conn = Connection::new_system()?;
+	conn.request_name(POWER_PROFILES_DEST, false, true, false)?;
+
+	let mut cr = Crossroads::new();
+	let token = cr.register("net.hadess.PowerProfiles", |b| {
+		b.property("ActiveProfile")
+			.get(|_, daemon: &mut Daemon| Ok(daemon.active.clone()))
+			.set(|_, daemon: &mut Daemon, value: String| {
+				if !PROFILES.contains(&value.as_str()) {
+					return Err(MethodErr::invalid_arg(&value));
+				}
+				println!("ActiveProfile {} -> {}", daemon.active, value);
+				daemon.active = value.clone();
+
+				Ok(Some(value))
+			});
+		b.property("PerformanceDegraded")
+			.get(|_, daemon: &mut Daemon| Ok(daemon.degraded.clone()));
+		b.property("Profiles").get(|_, _daemon: &mut Daemon| {
+			let profiles: Vec<PropMap> = PROFILES
+				.iter()
+				.map(|profile| {
+					let mut a = PropMap::new();
+					a.insert("Profile".into(), Variant(Box::new(profile.to_string())));
+					a.insert("Driver".into(), Variant(Box::new("standin".to_string())));
+
+					a
+				})
+				.collect();
+
+			Ok(profiles)
+		});
+	});
+	cr.insert(
+		POWER_PROFILES_PATH,
+		&[token],
+		Daemon {
+			active: "balanced".into(),
+			degraded,
+		},
+	);
+
+	println!("{} is ready", POWER_PROFILES_DEST);
+	cr.serve(&conn)
+}
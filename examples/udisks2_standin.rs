@@ -0,0 +1,193 @@
+//! Stand-in for UDisks2 (`org.freedesktop.UDisks2`) with an ATA drive that
+//! runs SMART self-tests and an NVMe drive without `Drive.Ata`, enough for the
+//! udisks2 page. The dock talks to the system bus, so point it at a private bus:
+//!
+//! ```sh
+//! dbus-run-session -- sh -c '
+//!     export DBUS_SYSTEM_BUS_ADDRESS=$DBUS_SESSION_BUS_ADDRESS
+//!     cargo run --example udisks2_standin &
+//!     sleep 1
+//!     machinepmmeter'
+//! ```
+//!
+//! A short self-test takes 30 seconds, an extended one 5 minutes.
+
+use dbus::MethodErr;
+use dbus::Path;
+use dbus::arg::PropMap;
+use dbus::blocking::Connection;
+use dbus_crossroads::Crossroads;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+const UDISKS2_DEST: &str = "org.freedesktop.UDisks2";
+const UDISKS2_PATH: &str = "/org/freedesktop/UDisks2";
+const ATA_DRIVE_PATH: &str = "/org/freedesktop/UDisks2/drives/Standin_ATA_SSD_0001";
+const NVME_DRIVE_PATH: &str = "/org/freedesktop/UDisks2/drives/Standin_NVMe_SSD_0002";
+
+/// `(ysqiiixia{sv})` of `SmartGetAttributes`.
+type SmartAttribute = (u8, String, u16, i32, i32, i32, i64, i32, PropMap);
+
+#[derive(Debug)]
+struct Drive {
+	model: &'static str,
+	serial: &'static str,
+	/// A running self-test: the start and the length.
+	selftest: Option<(Instant, Duration)>,
+	selftest_status: &'static str,
+}
+
+impl Drive {
+	fn new(model: &'static str, serial: &'static str) -> Self {
+		Self {
+			model,
+			serial,
+			selftest: None,
+			selftest_status: "success",
+		}
+	}
+
+	/// Finishes the self-test when its time is up.
+	fn selftest_percent_remaining(&mut self) -> i32 {
+		let Some((start, len)) = self.selftest else {
+			return 0;
+		};
+		match start.elapsed() < len {
+			true => 100 - (start.elapsed().as_secs_f64() / len.as_secs_f64() * 100.0) as i32,
+			false => {
+				println!("{}: self-test success", self.serial);
+				self.selftest = None;
+				self.selftest_status = "success";
+
+				0
+			}
+		}
+	}
+}
+
+#[derive(Debug)]
+struct Block {
+	drive: &'static str,
+}
+
+fn main() -> Result<(), dbus::Error> {
+	let conn = Connection::new_system()?;
+	conn.request_name(UDISKS2_DEST, false, true, false)?;
+
+	let mut cr = Crossroads::new();
+	let drive = cr.register("org.freedesktop.UDisks2.Drive", |b| {
+		b.property("Model")
+			.get(|_, drive: &mut Drive| Ok(drive.model.to_string()));
+		b.property("Serial")
+			.get(|_, drive: &mut Drive| Ok(drive.serial.to_string()));
+	});
+	let ata = cr.register("org.freedesktop.UDisks2.Drive.Ata", |b| {
+		b.property("SmartSupported")
+			.get(|_, _drive: &mut Drive| Ok(true));
+		b.property("SmartEnabled")
+			.get(|_, _drive: &mut Drive| Ok(true));
+		b.property("SmartUpdated").get(|_, _drive: &mut Drive| {
+			Ok(SystemTime::now()
+				.duration_since(SystemTime::UNIX_EPOCH)
+				.map_or(1, |a| a.as_secs()))
+		});
+		b.property("SmartFailing")
+			.get(|_, _drive: &mut Drive| Ok(false));
+		b.property("SmartPowerOnSeconds")
+			.get(|_, _drive: &mut Drive| Ok(3600u64 * 1000));
+		// kelvin, 40-44 °C
+		b.property("SmartTemperature").get(|_, _drive: &mut Drive| {
+			let secs = SystemTime::now()
+				.duration_since(SystemTime::UNIX_EPOCH)
+				.map_or(0, |a| a.as_secs());
+
+			Ok(313.15 + (secs % 5) as f64)
+		});
+		b.property("SmartNumAttributesFailing")
+			.get(|_, _drive: &mut Drive| Ok(0i32));
+		b.property("SmartNumAttributesFailedInThePast")
+			.get(|_, _drive: &mut Drive| Ok(0i32));
+		b.property("SmartNumBadSectors")
+			.get(|_, _drive: &mut Drive| Ok(0i64));
+		b.property("SmartSelftestStatus").get(|_, drive: &mut Drive| {
+			drive.selftest_percent_remaining();
+
+			Ok(match drive.selftest {
+				Some(_) => "inprogress".to_string(),
+				None => drive.selftest_status.to_string(),
+			})
+		});
+		b.property("SmartSelftestPercentRemaining")
+			.get(|_, drive: &mut Drive| Ok(drive.selftest_percent_remaining()));
+		b.method(
+			"SmartGetAttributes",
+			("options",),
+			("attributes",),
+			|_, _drive: &mut Drive, _: (PropMap,)| Ok((Vec::<SmartAttribute>::new(),)),
+		);
+		b.method(
+			"SmartSelftestStart",
+			("type", "options"),
+			(),
+			|_, drive: &mut Drive, (kind, _options): (String, PropMap)| {
+				let len = match kind.as_str() {
+					"short" => Duration::from_secs(30),
+					"extended" => Duration::from_secs(5 * 60),
+					_ => return Err(MethodErr::invalid_arg(&kind)),
+				};
+				println!("{}: {} self-test", drive.serial, kind);
+				drive.selftest = Some((Instant::now(), len));
+
+				Ok(())
+			},
+		);
+		b.method(
+			"SmartSelftestAbort",
+			("options",),
+			(),
+			|_, drive: &mut Drive, _: (PropMap,)| {
+				if drive.selftest.take().is_some() {
+					println!("{}: self-test aborted", drive.serial);
+					drive.selftest_status = "aborted";
+				}
+
+				Ok(())
+			},
+		);
+	});
+	let block = cr.register("org.freedesktop.UDisks2.Block", |b| {
+		b.property("Drive")
+			.get(|_, block: &mut Block| Ok(Path::from(block.drive)));
+	});
+
+	let object_manager = cr.object_manager();
+	cr.insert(UDISKS2_PATH, &[object_manager], ());
+	cr.insert(
+		ATA_DRIVE_PATH,
+		&[drive, ata],
+		Drive::new("Standin ATA SSD", "0001"),
+	);
+	cr.insert(
+		NVME_DRIVE_PATH,
+		&[drive],
+		Drive::new("Standin NVMe SSD", "0002"),
+	);
+	cr.insert(
+		"/org/freedesktop/UDisks2/block_devices/sda",
+		&[block],
+		Block {
+			drive: ATA_DRIVE_PATH,
+		},
+	);
+	cr.insert(
+		"/org/freedesktop/UDisks2/block_devices/nvme0n1",
+		&[block],
+		Block {
+			drive: NVME_DRIVE_PATH,
+		},
+	);
+
+	println!("{} is ready", UDISKS2_DEST);
+	cr.serve(&conn)
+}
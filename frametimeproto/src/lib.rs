@@ -0,0 +1,169 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (C) 2025 Denis Kotlyarov (Денис Котляров) <denis2005991@gmail.com>
+
+//! Frame-time ingest protocol of machinepmmeter.
+//!
+//! A client (a game, an injected layer) connects to the Unix stream socket at
+//! [`frametime_socket_path`] and sends text lines:
+//!
+//! ```text
+//! HELLO <pid> <exe>
+//! F <timestamp_ns>
+//! F <timestamp_ns>
+//! ...
+//! ```
+//!
+//! `timestamp_ns` is the monotonic time of a frame present, only the
+//! differences between two frames are used.
+//!
+//! A launcher (`machinepmmeter run`) instead sends `WATCH <pid> <exe>` and
+//! keeps the connection open while the process tree of `pid` should be
+//! monitored.
+//!
+//! `RECORD [path]` starts a session recording of all sensors in the dock (to
+//! the absolute `path` or the recordings directory), `RECORD-STOP` stops it.
+//!
+//! Depends only on `std`, shared by the dock and the preload shim.
+
+use std::borrow::Cow;
+use std::io;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Overrides the socket path (server and clients).
+pub const FRAMETIME_SOCKET_ENV: &str = "MACHINEPMMETER_FRAMETIME_SOCKET";
+pub const FRAMETIME_SOCKET_NAME: &str = "machinepmmeter-frametime.sock";
+
+/// `$MACHINEPMMETER_FRAMETIME_SOCKET`, `$XDG_RUNTIME_DIR/machinepmmeter-frametime.sock`
+/// or the same name in the temporary directory.
+pub fn frametime_socket_path() -> PathBuf {
+	if let Some(path) = std::env::var_os(FRAMETIME_SOCKET_ENV).filter(|a| !a.is_empty()) {
+		return path.into();
+	}
+
+	std::env::var_os("XDG_RUNTIME_DIR")
+		.filter(|a| !a.is_empty())
+		.map_or_else(std::env::temp_dir, PathBuf::from)
+		.join(FRAMETIME_SOCKET_NAME)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrametimeMessage<'a> {
+	Hello { pid: u32, exe: Cow<'a, str> },
+	Frame { timestamp_ns: u64 },
+	Watch { pid: u32, exe: Cow<'a, str> },
+	Record { path: Option<Cow<'a, str>> },
+	RecordStop,
+}
+
+impl<'a> FrametimeMessage<'a> {
+	pub fn parse(line: &'a str) -> Option<Self> {
+		let line = line.trim_end_matches(['\r', '\n']);
+		let (cmd, args) = line.split_once(' ').unwrap_or((line, ""));
+
+		match cmd {
+			"F" => Some(Self::Frame {
+				timestamp_ns: args.trim().parse().ok()?,
+			}),
+			"HELLO" | "WATCH" => {
+				// the name of the executable may contain spaces
+				let (pid, exe) = args.split_once(' ').unwrap_or((args, ""));
+				let (pid, exe) = (pid.parse().ok()?, Cow::Borrowed(exe.trim()));
+
+				Some(match cmd {
+					"HELLO" => Self::Hello { pid, exe },
+					_ => Self::Watch { pid, exe },
+				})
+			}
+			"RECORD" => Some(Self::Record {
+				path: Some(args.trim())
+					.filter(|a| !a.is_empty())
+					.map(Cow::Borrowed),
+			}),
+			"RECORD-STOP" => Some(Self::RecordStop),
+			_ => None,
+		}
+	}
+
+	pub fn write_to(&self, mut w: impl Write) -> io::Result<()> {
+		match self {
+			Self::Hello { pid, exe } | Self::Watch { pid, exe } => {
+				let cmd = match self {
+					Self::Hello { .. } => "HELLO",
+					_ => "WATCH",
+				};
+				// one message per line
+				let exe = exe.replace(['\r', '\n'], " ");
+
+				writeln!(w, "{} {} {}", cmd, pid, exe)
+			}
+			Self::Frame { timestamp_ns } => writeln!(w, "F {}", timestamp_ns),
+			Self::Record { path: Some(path) } => {
+				writeln!(w, "RECORD {}", path.replace(['\r', '\n'], " "))
+			}
+			Self::Record { path: None } => writeln!(w, "RECORD"),
+			Self::RecordStop => writeln!(w, "RECORD-STOP"),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parse_messages() {
+		assert_eq!(
+			FrametimeMessage::parse("HELLO 1234 Some Game.exe\n"),
+			Some(FrametimeMessage::Hello {
+				pid: 1234,
+				exe: "Some Game.exe".into()
+			})
+		);
+		assert_eq!(
+			FrametimeMessage::parse("F 16666667"),
+			Some(FrametimeMessage::Frame {
+				timestamp_ns: 16666667
+			})
+		);
+		assert_eq!(
+			FrametimeMessage::parse("RECORD /tmp/a game.csv"),
+			Some(FrametimeMessage::Record {
+				path: Some("/tmp/a game.csv".into())
+			})
+		);
+		assert_eq!(
+			FrametimeMessage::parse("RECORD"),
+			Some(FrametimeMessage::Record { path: None })
+		);
+		assert_eq!(
+			FrametimeMessage::parse("RECORD-STOP"),
+			Some(FrametimeMessage::RecordStop)
+		);
+		assert_eq!(FrametimeMessage::parse("F now"), None);
+		assert_eq!(FrametimeMessage::parse("BYE"), None);
+	}
+
+	#[test]
+	fn write_messages() {
+		for message in [
+			FrametimeMessage::Watch {
+				pid: 42,
+				exe: "game".into(),
+			},
+			FrametimeMessage::Frame { timestamp_ns: 1 },
+			FrametimeMessage::Record {
+				path: Some("/tmp/session.jsonl".into()),
+			},
+			FrametimeMessage::Record { path: None },
+			FrametimeMessage::RecordStop,
+		] {
+			let mut line = Vec::new();
+			message.write_to(&mut line).unwrap();
+			assert_eq!(
+				FrametimeMessage::parse(std::str::from_utf8(&line).unwrap()).as_ref(),
+				Some(&message)
+			);
+		}
+	}
+}
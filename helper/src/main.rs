@@ -0,0 +1,453 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (C) 2025 Denis Kotlyarov (Денис Котляров) <denis2005991@gmail.com>
+
+//! Privileged helper of machinepmmeter.
+//!
+//! Does the root-only reads and writes (RAPL energy, MSRs, `ryzenadj --info`,
+//! fan PWM, cpufreq governor/EPP/boost) for the dock, which refuses to run as
+//! root. Only peers with an allowed uid are served, only the allow-listed
+//! paths and registers are accepted (see `src/core/helperproto.rs`).
+//!
+//! Started via pkexec, only `--allow-uid` of the calling user is accepted and
+//! the socket is always `/run/machinepmmeter/helper.sock`. As root, the socket
+//! directory must be owned by root and not writable by others. The log goes
+//! to stderr, `RUST_LOG` (`info` by default).
+//!
+//! ```sh
+//! # as a systemd service or from the dock via pkexec
+//! machinepmmeter-helper --allow-uid 1000
+//!
+//! # without root, the same user and a fake sysfs tree
+//! export MACHINEPMMETER_HELPER_SOCKET=$XDG_RUNTIME_DIR/helper.sock
+//! cargo run -p machinepmmeter-helper -- --sys-root /tmp/fake-sys &
+//! ```
+
+#[path = "../../src/core/helperproto.rs"]
+#[allow(dead_code)]
+mod helperproto;
+
+use crate::helperproto::HELPER_MAX_LINE;
+use crate::helperproto::HELPER_MSR_ALLOW;
+use crate::helperproto::HELPER_READ_ALLOW;
+use crate::helperproto::HELPER_SOCKET_PATH;
+use crate::helperproto::HelperRequest;
+use crate::helperproto::helper_socket_path;
+use crate::helperproto::is_path_allowed;
+use crate::helperproto::is_pwm_enable_path;
+use crate::helperproto::is_restored_on_close;
+use crate::helperproto::is_write_allowed;
+use crate::helperproto::ryzenadj_info_to_pairs;
+use log::error;
+use log::info;
+use log::warn;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Write;
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::DirBuilderExt;
+use std::os::unix::fs::FileExt;
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::net::UnixListener;
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+/// `pwm*_enable`: the chip (or the firmware) controls the fan, most drivers.
+const PWM_ENABLE_AUTO: u8 = 2;
+
+#[derive(Debug)]
+struct HelperConfig {
+	socket: PathBuf,
+	allow_uids: Vec<u32>,
+	sys_root: PathBuf,
+	dev_root: PathBuf,
+	ryzenadj: OsString,
+}
+
+impl HelperConfig {
+	/// `pkexec_uid` (`$PKEXEC_UID`): the helper was started by this user via
+	/// pkexec, who can not choose the socket, the roots or the executable.
+	fn from_args(
+		mut args: impl Iterator<Item = OsString>,
+		pkexec_uid: Option<u32>,
+	) -> Result<Self, String> {
+		let mut result = Self {
+			socket: match pkexec_uid {
+				Some(_) => PathBuf::from(HELPER_SOCKET_PATH),
+				None => helper_socket_path(),
+			},
+			allow_uids: Vec::new(),
+			sys_root: PathBuf::from("/sys"),
+			dev_root: PathBuf::from("/dev"),
+			ryzenadj: OsString::from("ryzenadj"),
+		};
+		while let Some(arg) = args.next() {
+			let mut value = || {
+				args.next()
+					.ok_or_else(|| format!("{:?} requires a value", arg))
+			};
+			match arg.to_str() {
+				Some("--socket" | "--sys-root" | "--dev-root" | "--ryzenadj")
+					if pkexec_uid.is_some() =>
+				{
+					return Err(format!("{:?} is not allowed via pkexec", arg));
+				}
+				Some("--socket") => result.socket = value()?.into(),
+				Some("--allow-uid") => {
+					let uid = value()?;
+					let uid = uid
+						.to_str()
+						.and_then(|a| a.parse().ok())
+						.ok_or_else(|| format!("invalid uid {:?}", uid))?;
+					if pkexec_uid.is_some_and(|a| a != uid) {
+						return Err(format!("uid {} is not the pkexec caller", uid));
+					}
+					result.allow_uids.push(uid);
+				}
+				Some("--sys-root") => result.sys_root = value()?.into(),
+				Some("--dev-root") => result.dev_root = value()?.into(),
+				Some("--ryzenadj") => result.ryzenadj = value()?,
+				_ => {
+					return Err(format!(
+						"unknown argument {:?}, expected --socket, --allow-uid, --sys-root, --dev-root or --ryzenadj",
+						arg
+					));
+				}
+			}
+		}
+
+		if result.allow_uids.is_empty() {
+			// started by pkexec, otherwise the user that started the helper
+			let uid = pkexec_uid.unwrap_or_else(|| unsafe { libc::getuid() });
+			result.allow_uids.push(uid);
+		}
+
+		Ok(result)
+	}
+
+	/// `/sys/...` and `/dev/...` under the configured roots.
+	fn map_path(&self, path: &str) -> PathBuf {
+		if let Some(a) = path.strip_prefix("/sys/") {
+			return self.sys_root.join(a);
+		}
+		if let Some(a) = path.strip_prefix("/dev/") {
+			return self.dev_root.join(a);
+		}
+
+		PathBuf::from(path)
+	}
+}
+
+fn peer_uid(stream: &UnixStream) -> io::Result<u32> {
+	let mut cred = libc::ucred {
+		pid: 0,
+		uid: 0,
+		gid: 0,
+	};
+	let mut len = size_of::<libc::ucred>() as libc::socklen_t;
+	let result = unsafe {
+		libc::getsockopt(
+			stream.as_raw_fd(),
+			libc::SOL_SOCKET,
+			libc::SO_PEERCRED,
+			&mut cred as *mut libc::ucred as *mut libc::c_void,
+			&mut len,
+		)
+	};
+	if result != 0 {
+		return Err(io::Error::last_os_error());
+	}
+
+	Ok(cred.uid)
+}
+
+/// `pwm*_enable` and cpufreq attributes (connection, mapped path, value to
+/// restore) of every connection.
+type TakenAttrs = Arc<Mutex<Vec<(usize, PathBuf, String)>>>;
+
+/// The governors first (see `restore_cpufreq_values` of the dock).
+fn restore_taken(taken: &TakenAttrs, id: Option<usize>) {
+	let Ok(mut taken) = taken.lock() else {
+		return;
+	};
+	let (mut restore, keep): (Vec<_>, Vec<_>) = std::mem::take(&mut *taken)
+		.into_iter()
+		.partition(|(a, _, _)| id.is_none_or(|id| id == *a));
+	*taken = keep;
+
+	restore.sort_by_key(|(_, path, _)| !path.ends_with("scaling_governor"));
+	for (_, path, value) in restore {
+		match fs::write(&path, format!("{}\n", value)) {
+			Ok(()) => info!("#[helper] restored {:?}: {}", path, value),
+			Err(e) => error!("#[helper] I can't restore {:?}: {}", path, e),
+		}
+	}
+}
+
+fn handle_request(
+	config: &HelperConfig,
+	request: &HelperRequest,
+	id: usize,
+	taken: &TakenAttrs,
+) -> Result<String, String> {
+	match request {
+		HelperRequest::Read { path } => {
+			if !is_path_allowed(HELPER_READ_ALLOW, path) {
+				return Err(format!("{} is not allowed", path));
+			}
+
+			fs::read_to_string(config.map_path(path))
+				.map(|a| a.trim().to_string())
+				.map_err(|e| e.to_string())
+		}
+		HelperRequest::Write { path, value } => {
+			if !is_write_allowed(path, value) {
+				return Err(format!("{} {} is not allowed", path, value));
+			}
+			let mapped = config.map_path(path);
+
+			if is_restored_on_close(path) {
+				let mut taken = taken.lock().map_err(|e| e.to_string())?;
+				if !taken.iter().any(|(a, b, _)| *a == id && *b == mapped) {
+					let original = fs::read_to_string(&mapped).map(|a| a.trim().to_string());
+					let original = match is_pwm_enable_path(path) {
+						// full speed (0) and manual (1) are not safe to be left in
+						true => original
+							.ok()
+							.and_then(|a| a.parse::<u8>().ok())
+							.filter(|a| *a >= PWM_ENABLE_AUTO)
+							.unwrap_or(PWM_ENABLE_AUTO)
+							.to_string(),
+						false => original.map_err(|e| e.to_string())?,
+					};
+					taken.push((id, mapped.clone(), original));
+				}
+			}
+
+			fs::write(&mapped, format!("{}\n", value))
+				.map(|_| String::new())
+				.map_err(|e| e.to_string())
+		}
+		HelperRequest::Msr { cpu, reg } => {
+			if !HELPER_MSR_ALLOW.contains(reg) {
+				return Err(format!("MSR {:#x} is not allowed", reg));
+			}
+
+			let mut buf = [0u8; 8];
+			fs::File::open(config.dev_root.join(format!("cpu/{}/msr", cpu)))
+				.and_then(|a| a.read_exact_at(&mut buf, *reg as u64))
+				.map(|_| format!("{:#x}", u64::from_ne_bytes(buf)))
+				.map_err(|e| e.to_string())
+		}
+		HelperRequest::RyzenadjInfo => {
+			let output = Command::new(&config.ryzenadj)
+				.arg("--info")
+				.output()
+				.map_err(|e| e.to_string())?;
+			let pairs = ryzenadj_info_to_pairs(&String::from_utf8_lossy(&output.stdout));
+			if !output.status.success() || pairs.is_empty() {
+				return Err(format!("ryzenadj --info: {}", output.status));
+			}
+
+			Ok(pairs)
+		}
+	}
+}
+
+fn serve_client(config: &HelperConfig, stream: UnixStream, id: usize, taken: &TakenAttrs) {
+	let mut writer = match stream.try_clone() {
+		Ok(a) => a,
+		Err(e) => {
+			error!("#[helper] client #{}: {}", id, e);
+			return;
+		}
+	};
+	let mut reader = BufReader::new(stream);
+	let mut line = String::new();
+	loop {
+		line.clear();
+		match reader.by_ref().take(HELPER_MAX_LINE).read_line(&mut line) {
+			Ok(0) | Err(_) => break,
+			Ok(n) if n as u64 >= HELPER_MAX_LINE => {
+				let _e = writeln!(writer, "ERR the line is too long");
+				break;
+			}
+			Ok(_) => {}
+		}
+
+		let response = match HelperRequest::parse(&line) {
+			Some(request) => handle_request(config, &request, id, taken),
+			None => Err("unknown request".to_string()),
+		};
+		let result = match response {
+			Ok(value) if value.is_empty() => writeln!(writer, "OK"),
+			Ok(value) => writeln!(writer, "OK {}", value),
+			Err(e) => {
+				warn!("#[helper] client #{}: {:?}: {}", id, line.trim_end(), e);
+
+				writeln!(writer, "ERR {}", e.replace('\n', " "))
+			}
+		};
+		if result.is_err() {
+			break;
+		}
+	}
+
+	restore_taken(taken, Some(id));
+}
+
+/// The socket directory of a root helper: only root can replace the socket
+/// file (with a symlink, for example).
+fn check_socket_dir(dir: &Path) -> io::Result<()> {
+	let meta = fs::symlink_metadata(dir)?;
+	if !meta.file_type().is_dir() || meta.uid() != 0 || meta.mode() & 0o022 != 0 {
+		return Err(io::Error::new(
+			io::ErrorKind::PermissionDenied,
+			format!(
+				"{:?} must be a directory of root, not writable by others",
+				dir
+			),
+		));
+	}
+
+	Ok(())
+}
+
+/// Binds the socket, a stale socket file of a previous run is replaced.
+fn bind_helper_socket(config: &HelperConfig) -> io::Result<UnixListener> {
+	let path: &Path = &config.socket;
+	let euid = unsafe { libc::geteuid() };
+	if let Some(dir) = path.parent() {
+		fs::DirBuilder::new()
+			.recursive(true)
+			.mode(0o755)
+			.create(dir)?;
+		if euid == 0 {
+			check_socket_dir(dir)?;
+		}
+	}
+	match fs::symlink_metadata(path) {
+		Ok(meta) => {
+			if UnixStream::connect(path).is_ok() {
+				return Err(io::ErrorKind::AddrInUse.into());
+			}
+			if !meta.file_type().is_socket() {
+				return Err(io::Error::new(
+					io::ErrorKind::AlreadyExists,
+					format!("{:?} is not a socket", path),
+				));
+			}
+			fs::remove_file(path)?;
+		}
+		Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+		Err(e) => return Err(e),
+	}
+
+	// the uid is also checked on every connection
+	let (umask, owner) = match config.allow_uids.as_slice() {
+		[uid] => (0o177, Some(*uid)),
+		_ => (0o111, None),
+	};
+	// the mode is set by bind, there is no window with a wider one; nothing
+	// else creates files at this point
+	let old_umask = unsafe { libc::umask(umask) };
+	let listener = UnixListener::bind(path);
+	unsafe { libc::umask(old_umask) };
+	let listener = listener?;
+	if let Some(uid) = owner.filter(|a| *a != euid) {
+		std::os::unix::fs::lchown(path, Some(uid), None)?;
+	}
+
+	Ok(listener)
+}
+
+/// SIGINT/SIGTERM: the taken fans and cpufreq attributes are restored before
+/// exit.
+fn spawn_signal_thread(taken: TakenAttrs) {
+	unsafe {
+		let mut set: libc::sigset_t = std::mem::zeroed();
+		libc::sigemptyset(&mut set);
+		libc::sigaddset(&mut set, libc::SIGINT);
+		libc::sigaddset(&mut set, libc::SIGTERM);
+		// inherited by all threads started later
+		libc::pthread_sigmask(libc::SIG_BLOCK, &set, std::ptr::null_mut());
+
+		std::thread::spawn(move || {
+			let mut signum = 0;
+			libc::sigwait(&set, &mut signum);
+			info!("#[helper] signal {}, exit", signum);
+
+			restore_taken(&taken, None);
+			std::process::exit(0);
+		});
+	}
+}
+
+fn main() {
+	// the helper logs without RUST_LOG too (journal of the service)
+	env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+	let pkexec_uid = match std::env::var_os("PKEXEC_UID") {
+		Some(a) => match a.to_str().and_then(|a| a.parse().ok()) {
+			Some(a) => Some(a),
+			None => {
+				error!("#[helper] invalid PKEXEC_UID {:?}", a);
+				std::process::exit(2);
+			}
+		},
+		None => None,
+	};
+	let config = match HelperConfig::from_args(std::env::args_os().skip(1), pkexec_uid) {
+		Ok(a) => Arc::new(a),
+		Err(e) => {
+			error!("#[helper] {}", e);
+			std::process::exit(2);
+		}
+	};
+	let taken = TakenAttrs::default();
+	spawn_signal_thread(taken.clone());
+
+	let listener = match bind_helper_socket(&config) {
+		Ok(a) => a,
+		Err(e) => {
+			error!("#[helper] I can't listen on {:?}: {}", config.socket, e);
+			std::process::exit(1);
+		}
+	};
+	info!(
+		"#[helper] Listening on {:?}, allowed uids: {:?}",
+		config.socket, config.allow_uids
+	);
+
+	for (id, stream) in listener.incoming().enumerate() {
+		let stream = match stream {
+			Ok(a) => a,
+			Err(e) => {
+				error!("#[helper] accept: {}", e);
+				continue;
+			}
+		};
+		match peer_uid(&stream) {
+			Ok(uid) if uid == 0 || config.allow_uids.contains(&uid) => {}
+			Ok(uid) => {
+				warn!("#[helper] client #{}: uid {} is not allowed", id, uid);
+				continue;
+			}
+			Err(e) => {
+				error!("#[helper] client #{}: SO_PEERCRED: {}", id, e);
+				continue;
+			}
+		}
+
+		let (config, taken) = (config.clone(), taken.clone());
+		std::thread::spawn(move || serve_client(&config, stream, id, &taken));
+	}
+}
@@ -0,0 +1,155 @@
+//! The helper binary as the current user, with a fake sysfs tree.
+
+#[path = "../../src/core/helperproto.rs"]
+#[allow(dead_code)]
+mod helperproto;
+
+use crate::helperproto::HELPER_SOCKET_ENV;
+use crate::helperproto::HelperClient;
+use std::fs;
+use std::path::Path;
+use std::process::Child;
+use std::process::Command;
+use std::process::Stdio;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+const HWMON: &str = "/sys/class/hwmon/hwmon0";
+const POLICY: &str = "/sys/devices/system/cpu/cpufreq/policy0";
+
+/// Killed on drop, also when an assert fails.
+struct Helper(Child);
+
+impl Drop for Helper {
+	fn drop(&mut self) {
+		let _e = self.0.kill();
+		let _e = self.0.wait();
+	}
+}
+
+fn write_attr(sys: &Path, path: &str, value: &str) {
+	let path = sys.join(path.trim_start_matches("/sys/"));
+	fs::create_dir_all(path.parent().unwrap()).unwrap();
+	fs::write(path, value).unwrap();
+}
+
+fn read_attr(sys: &Path, path: &str) -> String {
+	fs::read_to_string(sys.join(path.trim_start_matches("/sys/")))
+		.unwrap()
+		.trim()
+		.to_string()
+}
+
+/// Waits up to 5s for the file to have the value.
+fn wait_attr(sys: &Path, path: &str, value: &str) -> bool {
+	let time = Instant::now();
+	while time.elapsed() < Duration::from_secs(5) {
+		if read_attr(sys, path) == value {
+			return true;
+		}
+		thread::sleep(Duration::from_millis(20));
+	}
+
+	false
+}
+
+fn connect(socket: &Path) -> HelperClient {
+	let time = Instant::now();
+	loop {
+		match HelperClient::connect(socket) {
+			Ok(a) => return a,
+			Err(e) if time.elapsed() > Duration::from_secs(5) => {
+				panic!("the helper is not listening on {:?}: {}", socket, e)
+			}
+			Err(_) => thread::sleep(Duration::from_millis(20)),
+		}
+	}
+}
+
+#[test]
+fn helper() {
+	let dir = std::env::temp_dir().join(format!("machinepmmeter-helper-{}", std::process::id()));
+	let _e = fs::remove_dir_all(&dir);
+	let sys = dir.join("sys");
+	let socket = dir.join("run").join("helper.sock");
+	write_attr(&sys, &format!("{}/pwm1", HWMON), "100\n");
+	write_attr(&sys, &format!("{}/pwm1_enable", HWMON), "2\n");
+	write_attr(&sys, &format!("{}/scaling_governor", POLICY), "schedutil\n");
+	write_attr(
+		&sys,
+		"/sys/class/powercap/intel-rapl:0/energy_uj",
+		"123456789\n",
+	);
+	fs::write(dir.join("secret"), "secret\n").unwrap();
+
+	let helper = Helper(
+		Command::new(env!("CARGO_BIN_EXE_machinepmmeter-helper"))
+			.arg("--sys-root")
+			.arg(&sys)
+			.env(HELPER_SOCKET_ENV, &socket)
+			.env_remove("PKEXEC_UID")
+			.stdin(Stdio::null())
+			.spawn()
+			.unwrap(),
+	);
+
+	let mut client = connect(&socket);
+	// read
+	assert_eq!(
+		client
+			.read("/sys/class/powercap/intel-rapl:0/energy_uj")
+			.unwrap(),
+		"123456789"
+	);
+
+	// write
+	client
+		.write(&format!("{}/pwm1_enable", HWMON), "1")
+		.unwrap();
+	client.write(&format!("{}/pwm1", HWMON), "200").unwrap();
+	client
+		.write(&format!("{}/scaling_governor", POLICY), "performance")
+		.unwrap();
+	assert_eq!(read_attr(&sys, &format!("{}/pwm1_enable", HWMON)), "1");
+	assert_eq!(read_attr(&sys, &format!("{}/pwm1", HWMON)), "200");
+	assert_eq!(
+		read_attr(&sys, &format!("{}/scaling_governor", POLICY)),
+		"performance"
+	);
+
+	// rejection, the connection stays usable
+	for path in [
+		"/sys/class/powercap/intel-rapl:0/../../../../secret",
+		"/etc/passwd",
+		"/sys/class/hwmon/hwmon0/pwm1",
+	] {
+		assert!(client.read(path).is_err(), "{}", path);
+	}
+	assert!(client.write(&format!("{}/pwm1", HWMON), "256").is_err());
+	assert!(
+		client
+			.write(&format!("{}/../../../../secret", HWMON), "1")
+			.is_err()
+	);
+	assert!(
+		client
+			.request(&helperproto::HelperRequest::Msr { cpu: 0, reg: 0x10 })
+			.is_err()
+	);
+	assert_eq!(fs::read_to_string(dir.join("secret")).unwrap(), "secret\n");
+	assert_eq!(read_attr(&sys, &format!("{}/pwm1", HWMON)), "200");
+
+	// restore on disconnect, the duty is left as is
+	drop(client);
+	assert!(wait_attr(&sys, &format!("{}/pwm1_enable", HWMON), "2"));
+	assert!(wait_attr(
+		&sys,
+		&format!("{}/scaling_governor", POLICY),
+		"schedutil"
+	));
+	assert_eq!(read_attr(&sys, &format!("{}/pwm1", HWMON)), "200");
+
+	drop(helper);
+	let _e = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,219 @@
+// SPDX-License-Identifier: GPL-3.0-or-later
+// Copyright (C) 2025 Denis Kotlyarov (Денис Котляров) <denis2005991@gmail.com>
+
+//! LD_PRELOAD frame-timing shim.
+//!
+//! Hooks `glXSwapBuffers` and `eglSwapBuffers` (also when resolved through
+//! `glXGetProcAddress*`/`eglGetProcAddress`) and streams the present
+//! timestamps to the running dock over the frame-time socket.
+//!
+//! ```sh
+//! cargo build -p machinepmmeter-preload
+//! Xvfb :99 &
+//! DISPLAY=:99 LIBGL_ALWAYS_SOFTWARE=1 \
+//!     LD_PRELOAD=target/debug/libmachinepmmeter_preload.so glxgears
+//! ```
+//!
+//! `tests/glxgears.rs` does the same against a temporary socket
+//! (`cargo test -p machinepmmeter-preload -- --ignored`).
+
+use libc::c_char;
+use libc::c_uint;
+use libc::c_ulong;
+use libc::c_void;
+use machinepmmeter_frametimeproto::FrametimeMessage;
+use machinepmmeter_frametimeproto::frametime_socket_path;
+use std::borrow::Cow;
+use std::ffi::CStr;
+use std::io::Write;
+use std::os::unix::net::UnixStream;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Pause between two connection attempts when the dock is not running.
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+/// The render thread is never blocked for longer.
+const WRITE_TIMEOUT: Duration = Duration::from_millis(2);
+
+type GlxSwapBuffersFn = unsafe extern "C" fn(*mut c_void, c_ulong);
+type EglSwapBuffersFn = unsafe extern "C" fn(*mut c_void, *mut c_void) -> c_uint;
+type GetProcAddressFn = unsafe extern "C" fn(*const c_char) -> *mut c_void;
+
+/// Next definition of `name` after this library, or from `lib` if it was
+/// loaded privately (`dlopen` with `RTLD_LOCAL`).
+fn next_symbol(name: &CStr, lib: &CStr) -> Option<usize> {
+	let mut ptr = unsafe { libc::dlsym(libc::RTLD_NEXT, name.as_ptr()) };
+	if ptr.is_null() {
+		let handle = unsafe { libc::dlopen(lib.as_ptr(), libc::RTLD_LAZY | libc::RTLD_NOLOAD) };
+		if !handle.is_null() {
+			ptr = unsafe { libc::dlsym(handle, name.as_ptr()) };
+		}
+	}
+
+	(!ptr.is_null()).then_some(ptr as usize)
+}
+
+/// `0` is not cached, the lookup is retried on the next call (the library
+/// may be `dlopen`ed later).
+macro_rules! next_fn {
+	[ $name:literal, $lib:literal as $ty:ty ] => {{
+		static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+		let mut next = NEXT.load(Ordering::Acquire);
+		if next == 0 {
+			next = next_symbol($name, $lib).unwrap_or(0);
+			NEXT.store(next, Ordering::Release);
+		}
+
+		(next != 0).then(|| unsafe { std::mem::transmute::<usize, $ty>(next) })
+	}};
+}
+
+#[derive(Debug)]
+struct Reporter {
+	pid: u32,
+	stream: Option<UnixStream>,
+	last_attempt: Option<Instant>,
+}
+
+static REPORTER: Mutex<Reporter> = Mutex::new(Reporter {
+	pid: 0,
+	stream: None,
+	last_attempt: None,
+});
+
+fn monotonic_ns() -> u64 {
+	let mut ts = libc::timespec {
+		tv_sec: 0,
+		tv_nsec: 0,
+	};
+	unsafe {
+		libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts);
+	}
+
+	ts.tv_sec as u64 * 1_000_000_000 + ts.tv_nsec as u64
+}
+
+fn connect() -> Option<UnixStream> {
+	let stream = UnixStream::connect(frametime_socket_path()).ok()?;
+	stream.set_write_timeout(Some(WRITE_TIMEOUT)).ok()?;
+
+	let exe = std::env::current_exe()
+		.ok()
+		.and_then(|a| a.file_name().map(|a| a.to_string_lossy().into_owned()))
+		.unwrap_or_default();
+	FrametimeMessage::Hello {
+		pid: std::process::id(),
+		exe: Cow::Owned(exe),
+	}
+	.write_to(&stream)
+	.ok()?;
+
+	Some(stream)
+}
+
+fn report_frame() {
+	let timestamp_ns = monotonic_ns();
+	// another thread is presenting right now, not worth waiting for
+	let Ok(mut reporter) = REPORTER.try_lock() else {
+		return;
+	};
+
+	let pid = std::process::id();
+	if reporter.pid != pid {
+		// first frame or after fork
+		reporter.pid = pid;
+		reporter.stream = None;
+		reporter.last_attempt = None;
+	}
+	if reporter.stream.is_none() {
+		if reporter.last_attempt.is_some_and(|a| a.elapsed() < RECONNECT_INTERVAL) {
+			return;
+		}
+		reporter.last_attempt = Some(Instant::now());
+		reporter.stream = connect();
+	}
+
+	if let Some(ref mut stream) = reporter.stream {
+		let mut line = Vec::with_capacity(24);
+		let _e = FrametimeMessage::Frame { timestamp_ns }.write_to(&mut line);
+		if stream.write_all(&line).is_err() {
+			reporter.stream = None;
+		}
+	}
+}
+
+/// # Safety
+///
+/// Called by the application as `glXSwapBuffers`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn glXSwapBuffers(dpy: *mut c_void, drawable: c_ulong) {
+	if let Some(next) = next_fn!(c"glXSwapBuffers", c"libGL.so.1" as GlxSwapBuffersFn) {
+		unsafe { next(dpy, drawable) };
+	}
+	report_frame();
+}
+
+/// # Safety
+///
+/// Called by the application as `eglSwapBuffers`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn eglSwapBuffers(dpy: *mut c_void, surface: *mut c_void) -> c_uint {
+	let result = match next_fn!(c"eglSwapBuffers", c"libEGL.so.1" as EglSwapBuffersFn) {
+		Some(next) => unsafe { next(dpy, surface) },
+		None => 0,
+	};
+	report_frame();
+
+	result
+}
+
+/// `glXSwapBuffers` resolved at runtime is redirected to the hook.
+unsafe fn glx_get_proc_address(next: Option<GetProcAddressFn>, name: *const c_char) -> *mut c_void {
+	if !name.is_null() && unsafe { CStr::from_ptr(name) } == c"glXSwapBuffers" {
+		return glXSwapBuffers as GlxSwapBuffersFn as *mut c_void;
+	}
+
+	match next {
+		Some(next) => unsafe { next(name) },
+		None => std::ptr::null_mut(),
+	}
+}
+
+/// # Safety
+///
+/// Called by the application as `glXGetProcAddress`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn glXGetProcAddress(name: *const c_char) -> *mut c_void {
+	let next = next_fn!(c"glXGetProcAddress", c"libGL.so.1" as GetProcAddressFn);
+
+	unsafe { glx_get_proc_address(next, name) }
+}
+
+/// # Safety
+///
+/// Called by the application as `glXGetProcAddressARB`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn glXGetProcAddressARB(name: *const c_char) -> *mut c_void {
+	let next = next_fn!(c"glXGetProcAddressARB", c"libGL.so.1" as GetProcAddressFn);
+
+	unsafe { glx_get_proc_address(next, name) }
+}
+
+/// # Safety
+///
+/// Called by the application as `eglGetProcAddress`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn eglGetProcAddress(name: *const c_char) -> *mut c_void {
+	if !name.is_null() && unsafe { CStr::from_ptr(name) } == c"eglSwapBuffers" {
+		return eglSwapBuffers as EglSwapBuffersFn as *mut c_void;
+	}
+
+	match next_fn!(c"eglGetProcAddress", c"libEGL.so.1" as GetProcAddressFn) {
+		Some(next) => unsafe { next(name) },
+		None => std::ptr::null_mut(),
+	}
+}
@@ -0,0 +1,147 @@
+//! The shim in glxgears under Xvfb, against a temporary frame-time socket.
+//! Needs `Xvfb` and `glxgears` (mesa-utils) in `PATH` and the built shim
+//! (`cargo test` does not build the cdylib):
+//!
+//! ```sh
+//! cargo build -p machinepmmeter-preload
+//! cargo test -p machinepmmeter-preload -- --ignored
+//! ```
+
+use machinepmmeter_frametimeproto::FRAMETIME_SOCKET_ENV;
+use machinepmmeter_frametimeproto::FrametimeMessage;
+use std::fs;
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::os::unix::net::UnixListener;
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::process::Child;
+use std::process::Command;
+use std::process::Stdio;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Killed on drop, also when an assert fails.
+struct Killed(Child);
+
+impl Drop for Killed {
+	fn drop(&mut self) {
+		let _e = self.0.kill();
+		let _e = self.0.wait();
+	}
+}
+
+/// `$MACHINEPMMETER_PRELOAD` or the shim of this build, next to the test
+/// binary (`target/<profile>/deps`).
+fn preload_lib() -> PathBuf {
+	if let Some(lib) = std::env::var_os("MACHINEPMMETER_PRELOAD").filter(|a| !a.is_empty()) {
+		return lib.into();
+	}
+	let deps = std::env::current_exe()
+		.unwrap()
+		.parent()
+		.unwrap()
+		.to_path_buf();
+
+	[deps.parent().unwrap(), &deps]
+		.into_iter()
+		.map(|a| a.join("libmachinepmmeter_preload.so"))
+		.find(|a| a.exists())
+		.expect("libmachinepmmeter_preload.so is not built, cargo build -p machinepmmeter-preload")
+}
+
+/// Xvfb on a free display, returns the display (`:N`).
+fn start_xvfb() -> (Killed, String) {
+	let mut xvfb = Command::new("Xvfb")
+		.args([
+			"-displayfd",
+			"1",
+			"-screen",
+			"0",
+			"640x480x24",
+			"-nolisten",
+			"tcp",
+		])
+		.stdout(Stdio::piped())
+		.stderr(Stdio::null())
+		.spawn()
+		.expect("Xvfb is not installed");
+	let mut display = String::new();
+	BufReader::new(xvfb.stdout.take().unwrap())
+		.read_line(&mut display)
+		.unwrap();
+
+	(Killed(xvfb), format!(":{}", display.trim()))
+}
+
+fn accept(listener: &UnixListener) -> UnixStream {
+	listener.set_nonblocking(true).unwrap();
+	let time = Instant::now();
+	loop {
+		match listener.accept() {
+			Ok((a, _)) => {
+				a.set_nonblocking(false).unwrap();
+				a.set_read_timeout(Some(TIMEOUT)).unwrap();
+
+				return a;
+			}
+			Err(e) if e.kind() == io::ErrorKind::WouldBlock && time.elapsed() < TIMEOUT => {
+				thread::sleep(Duration::from_millis(20));
+			}
+			Err(e) => panic!("glxgears did not connect: {}", e),
+		}
+	}
+}
+
+#[test]
+#[ignore = "needs Xvfb and glxgears"]
+fn glxgears_frames() {
+	let dir = std::env::temp_dir().join(format!("machinepmmeter-preload-{}", std::process::id()));
+	let _e = fs::remove_dir_all(&dir);
+	fs::create_dir_all(&dir).unwrap();
+	let socket = dir.join("frametime.sock");
+	let listener = UnixListener::bind(&socket).unwrap();
+
+	let (_xvfb, display) = start_xvfb();
+	let glxgears = Killed(
+		Command::new("glxgears")
+			.env("DISPLAY", &display)
+			.env("LIBGL_ALWAYS_SOFTWARE", "1")
+			.env("LD_PRELOAD", preload_lib())
+			.env(FRAMETIME_SOCKET_ENV, &socket)
+			.stdout(Stdio::null())
+			.stderr(Stdio::null())
+			.spawn()
+			.expect("glxgears is not installed"),
+	);
+
+	let mut lines = BufReader::new(accept(&listener)).lines();
+	let mut next = || lines.next().expect("glxgears disconnected").unwrap();
+	let line = next();
+	match FrametimeMessage::parse(&line) {
+		Some(FrametimeMessage::Hello { pid, exe }) => {
+			assert_eq!(pid, glxgears.0.id());
+			assert_eq!(exe, "glxgears");
+		}
+		_ => panic!("expected HELLO, got {:?}", line),
+	}
+
+	let mut last_timestamp_ns = 0;
+	for _ in 0..30 {
+		let line = next();
+		match FrametimeMessage::parse(&line) {
+			Some(FrametimeMessage::Frame { timestamp_ns }) => {
+				assert!(timestamp_ns > last_timestamp_ns);
+				last_timestamp_ns = timestamp_ns;
+			}
+			_ => panic!("expected a frame, got {:?}", line),
+		}
+	}
+
+	drop(glxgears);
+	let _e = fs::remove_dir_all(&dir);
+}
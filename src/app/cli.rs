@@ -1,7 +1,9 @@
 use anyhow::Result as anyhowResult;
 use anyhow::anyhow;
 use clap::Parser;
+use clap::Subcommand;
 use directories::ProjectDirs;
+use std::ffi::OsString;
 use std::path::Path;
 use std::path::PathBuf;
 
@@ -21,6 +23,43 @@ pub struct AppCli {
 	/// Allow saving default AppConfig if it doesn't exist
 	#[clap(long, value_parser, default_value = "true")]
 	allow_save_default_app_config: bool,
+
+	/// Start a session recording of all sensors to this file (CSV or JSONL by
+	/// the extension), Shift+F12 or the tray stops it; forwarded to the dock
+	/// if it is already running
+	#[clap(long, value_parser, value_name = "PATH")]
+	record: Option<PathBuf>,
+
+	#[clap(subcommand)]
+	command: Option<AppCommand>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AppCommand {
+	/// Run a command (a game) with frame-timing capture and process monitoring,
+	/// e.g. the Steam launch option `machinepmmeter run -- %command%`
+	Run {
+		/// Record all sensors while the command runs, `--record=PATH` for a
+		/// file (CSV or JSONL by the extension)
+		#[clap(long, value_name = "PATH", num_args = 0..=1, require_equals = true)]
+		record: Option<Option<PathBuf>>,
+
+		/// The command and its arguments
+		#[clap(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+		command: Vec<OsString>,
+	},
+
+	/// Start or stop the session recording of the running dock
+	Record {
+		/// The file (CSV or JSONL by the extension), `[recording]` names it if
+		/// not set
+		#[clap(value_name = "PATH", conflicts_with = "stop")]
+		path: Option<PathBuf>,
+
+		/// Stop the recording
+		#[clap(long)]
+		stop: bool,
+	},
 }
 
 impl AppCli {
@@ -34,6 +73,21 @@ impl AppCli {
 		self.allow_save_default_app_config
 	}
 
+	#[inline]
+	pub fn get_record(&self) -> Option<&Path> {
+		self.record.as_deref()
+	}
+
+	#[inline]
+	pub fn get_command(&self) -> Option<&AppCommand> {
+		self.command.as_ref()
+	}
+
+	/// `[recording] dir` if not set.
+	pub fn default_recordings_dir() -> Option<PathBuf> {
+		ProjectDirs::from("com", "ulinkot", APP_PKG_NAME).map(|a| a.data_dir().join("recordings"))
+	}
+
 	pub fn search_default_appconfigpath<R>(
 		&self,
 		next: impl FnOnce(&'_ Path) -> anyhowResult<R>,
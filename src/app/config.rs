@@ -1,9 +1,18 @@
 use crate::app::consts::UPPERCASE_APP_PKG_NAME;
 use crate::app::dockwindow::PosINScreen;
+use crate::core::alert::AlertRuleConfig;
+use crate::core::cpufreq::CpuPresetConfig;
 use crate::core::eightbitcolor::EightBitColor;
+use crate::core::fancurve::FanCurveConfig;
+use crate::core::notifications::NotificationsAppConfig;
+use crate::core::recording::RecordingAppConfig;
+use crate::core::sensoroverride::SensorsAppConfig;
+use crate::core::threshold::ThresholdsAppConfig;
 use gtk::pango;
 use serde::Deserialize;
 use std::borrow::Cow;
+use std::num::NonZeroUsize;
+use std::time::Duration;
 
 #[derive(Deserialize, Debug)]
 pub struct AppConfig {
@@ -12,6 +21,26 @@ pub struct AppConfig {
 	window: WindowAppConfig,
 	all_font: FontAppConfig,
 	color: ColorAppConfig,
+	#[serde(default)]
+	gamemode: GameModeAppConfig,
+	#[serde(default)]
+	fan_curve: Vec<FanCurveConfig>,
+	#[serde(default)]
+	cpu_preset: Vec<CpuPresetConfig>,
+	#[serde(default)]
+	helper: HelperAppConfig,
+	#[serde(default)]
+	page: Vec<PageAppConfig>,
+	#[serde(default)]
+	sensors: SensorsAppConfig,
+	#[serde(default)]
+	thresholds: ThresholdsAppConfig,
+	#[serde(default)]
+	alert: Vec<AlertRuleConfig>,
+	#[serde(default)]
+	notifications: NotificationsAppConfig,
+	#[serde(default)]
+	recording: RecordingAppConfig,
 }
 
 #[derive(Deserialize, Debug)]
@@ -107,6 +136,16 @@ impl Default for AppConfig {
 			window: WindowAppConfig::default(),
 			all_font: FontAppConfig::default(),
 			color: ColorAppConfig::default(),
+			gamemode: GameModeAppConfig::default(),
+			fan_curve: Vec::new(),
+			cpu_preset: Vec::new(),
+			helper: HelperAppConfig::default(),
+			page: Vec::new(),
+			sensors: SensorsAppConfig::default(),
+			thresholds: ThresholdsAppConfig::default(),
+			alert: Vec::new(),
+			notifications: NotificationsAppConfig::default(),
+			recording: RecordingAppConfig::default(),
 		}
 	}
 }
@@ -145,6 +184,165 @@ impl Default for ColorAppConfig {
 	}
 }
 
+#[derive(Deserialize, Debug, Default)]
+pub struct GameModeAppConfig {
+	/// Show the dock when a game registers with GameMode, hide it when the
+	/// last one leaves.
+	auto_show_hide: Option<bool>,
+}
+
+impl GameModeAppConfig {
+	#[inline]
+	pub fn get_auto_show_hide(&self) -> bool {
+		self.auto_show_hide.unwrap_or(false)
+	}
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct HelperAppConfig {
+	/// Start the privileged helper via pkexec if it is not running (as a
+	/// systemd service).
+	pkexec: Option<bool>,
+}
+
+impl HelperAppConfig {
+	#[inline]
+	pub fn get_pkexec(&self) -> bool {
+		self.pkexec.unwrap_or(false)
+	}
+}
+
+/// Backends of `[[page]]`, the default order (the first nine have the
+/// Num1-Num9 hotkeys).
+pub const PAGE_NAMES: &[&str] = &[
+	"demo",
+	"lm_sensors",
+	"udisks2",
+	"sysinfo",
+	"fans",
+	"rapl",
+	"frametime",
+	"process",
+	"gamemode",
+	"wireless",
+	"edac",
+	"throttle",
+	"interrupts",
+	"schedstat",
+	"kmsg",
+];
+
+/// `[[page]]` of AppConfig, only the listed pages are shown, in this order
+/// (all pages of [`PAGE_NAMES`] if there is none).
+///
+/// ```toml
+/// [[page]]
+/// name = "lm_sensors"
+/// len = 600          # history length
+/// redraw_ms = 16     # lm_sensors, udisks2: graph + limit + current
+/// step_len = 5       # lm_sensors, udisks2: values per redraw step
+/// step_ms = 1        # lm_sensors, udisks2: time of a value
+///
+/// [[page]]
+/// name = "fans"
+/// sampling_ms = 2000 # the other pages
+///
+/// [[page]]
+/// name = "interrupts"
+/// heat_row = true    # interrupts, schedstat: per-CPU heat row
+/// irq_count = 6      # interrupts: the busiest IRQs
+/// softirq_count = 4  # interrupts: the busiest softirqs
+///
+/// [[page]]
+/// name = "kmsg"
+/// events = 50        # kmsg: the number of events shown
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+pub struct PageAppConfig {
+	name: String,
+	len: Option<usize>,
+	sampling_ms: Option<u64>,
+	redraw_ms: Option<u64>,
+	step_len: Option<NonZeroUsize>,
+	step_ms: Option<u64>,
+	heat_row: Option<bool>,
+	irq_count: Option<usize>,
+	softirq_count: Option<usize>,
+	events: Option<usize>,
+}
+
+impl PageAppConfig {
+	#[inline]
+	pub fn new(name: impl Into<String>) -> Self {
+		Self {
+			name: name.into(),
+			len: None,
+			sampling_ms: None,
+			redraw_ms: None,
+			step_len: None,
+			step_ms: None,
+			heat_row: None,
+			irq_count: None,
+			softirq_count: None,
+			events: None,
+		}
+	}
+
+	#[inline]
+	pub fn get_name(&self) -> &str {
+		&self.name
+	}
+
+	#[inline]
+	pub fn get_len_or(&self, default: usize) -> usize {
+		self.len.filter(|a| *a > 0).unwrap_or(default)
+	}
+
+	#[inline]
+	pub fn get_sampling_time_or(&self, default: Duration) -> Duration {
+		self.sampling_ms
+			.filter(|a| *a > 0)
+			.map_or(default, Duration::from_millis)
+	}
+
+	#[inline]
+	pub fn get_redraw_time_or(&self, default: Duration) -> Duration {
+		self.redraw_ms
+			.filter(|a| *a > 0)
+			.map_or(default, Duration::from_millis)
+	}
+
+	#[inline]
+	pub fn get_step_len_or(&self, default: NonZeroUsize) -> NonZeroUsize {
+		self.step_len.unwrap_or(default)
+	}
+
+	#[inline]
+	pub fn get_step_time_or(&self, default: Duration) -> Duration {
+		self.step_ms.map_or(default, Duration::from_millis)
+	}
+
+	#[inline]
+	pub fn get_heat_row_or(&self, default: bool) -> bool {
+		self.heat_row.unwrap_or(default)
+	}
+
+	#[inline]
+	pub fn get_irq_count_or(&self, default: usize) -> usize {
+		self.irq_count.unwrap_or(default)
+	}
+
+	#[inline]
+	pub fn get_softirq_count_or(&self, default: usize) -> usize {
+		self.softirq_count.unwrap_or(default)
+	}
+
+	#[inline]
+	pub fn get_events_or(&self, default: usize) -> usize {
+		self.events.filter(|a| *a > 0).unwrap_or(default)
+	}
+}
+
 impl AppConfig {
 	#[inline]
 	pub fn get_name(&self) -> Option<&str> {
@@ -170,6 +368,87 @@ impl AppConfig {
 	pub const fn get_color_app_config(&self) -> &ColorAppConfig {
 		&self.color
 	}
+
+	#[inline]
+	pub const fn get_gamemode_app_config(&self) -> &GameModeAppConfig {
+		&self.gamemode
+	}
+
+	#[inline]
+	pub fn get_fan_curves(&self) -> &[FanCurveConfig] {
+		&self.fan_curve
+	}
+
+	#[inline]
+	pub fn get_cpu_presets(&self) -> &[CpuPresetConfig] {
+		&self.cpu_preset
+	}
+
+	#[inline]
+	pub const fn get_helper_app_config(&self) -> &HelperAppConfig {
+		&self.helper
+	}
+
+	#[inline]
+	pub const fn get_sensors_app_config(&self) -> &SensorsAppConfig {
+		&self.sensors
+	}
+
+	#[inline]
+	pub const fn get_thresholds_app_config(&self) -> &ThresholdsAppConfig {
+		&self.thresholds
+	}
+
+	#[inline]
+	pub fn get_alerts(&self) -> &[AlertRuleConfig] {
+		&self.alert
+	}
+
+	#[inline]
+	pub const fn get_notifications_app_config(&self) -> &NotificationsAppConfig {
+		&self.notifications
+	}
+
+	#[inline]
+	pub const fn get_recording_app_config(&self) -> &RecordingAppConfig {
+		&self.recording
+	}
+
+	/// `[[page]]` (without the repeated ones) or all pages in the default
+	/// order.
+	pub fn get_pages(&self) -> Vec<PageAppConfig> {
+		if self.page.is_empty() {
+			return PAGE_NAMES
+				.iter()
+				.filter(|a| cfg!(feature = "demo_mode") || **a != "demo")
+				.map(|a| PageAppConfig::new(*a))
+				.collect();
+		}
+
+		self.page
+			.iter()
+			.enumerate()
+			.filter(|(i, page)| {
+				!self.page[..*i]
+					.iter()
+					.any(|a| a.get_name() == page.get_name())
+			})
+			.map(|(_, a)| a.clone())
+			.collect()
+	}
+
+	/// Unknown and repeated `[[page]]` names, reported when the file is loaded.
+	pub fn get_invalid_page_names(&self) -> Vec<&str> {
+		let mut result = Vec::new();
+		for (i, page) in self.page.iter().enumerate() {
+			let name = page.get_name();
+			if !PAGE_NAMES.contains(&name) || self.page[..i].iter().any(|a| a.get_name() == name) {
+				result.push(name);
+			}
+		}
+
+		result
+	}
 }
 
 impl AsRef<FontAppConfig> for AppConfig {
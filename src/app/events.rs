@@ -15,12 +15,19 @@ pub fn app_event_channel() -> (AppEventSender, Receiver<AppEvents>) {
 pub enum AppEvents {
 	Keyboard(AppKeyboardEvents),
 	ToggleDockWindowVisibility,
+	ShowDockWindow,
+	HideDockWindow,
 	ShowOrFocusAboutDialog,
 	Exit,
 	MoveDockWindowToNextPosition,
 	MoveTabToPrevPosition,
 	MoveTabToNextPosition,
+	NextPowerProfile,
+	NextCpuPreset,
 	KeyboardListenerEnabled(bool),
+	MuteNotifications,
+	SaveFlightRecording,
+	ToggleRecording,
 }
 
 #[derive(Clone)]
@@ -60,6 +67,18 @@ impl AppEventSender {
 		self.__send(AppEvents::ToggleDockWindowVisibility);
 	}
 
+	#[inline]
+	pub fn show_window(&self) {
+		trace!("#[AppEventSender] show_window");
+		self.__send(AppEvents::ShowDockWindow);
+	}
+
+	#[inline]
+	pub fn hide_window(&self) {
+		trace!("#[AppEventSender] hide_window");
+		self.__send(AppEvents::HideDockWindow);
+	}
+
 	#[inline]
 	pub fn move_window_to_next_position(&self) {
 		trace!("#[AppEventSender] move_window_to_next_position");
@@ -78,6 +97,36 @@ impl AppEventSender {
 		self.__send(AppEvents::MoveTabToPrevPosition);
 	}
 
+	#[inline]
+	pub fn next_power_profile(&self) {
+		trace!("#[AppEventSender] next_power_profile");
+		self.__send(AppEvents::NextPowerProfile);
+	}
+
+	#[inline]
+	pub fn next_cpu_preset(&self) {
+		trace!("#[AppEventSender] next_cpu_preset");
+		self.__send(AppEvents::NextCpuPreset);
+	}
+
+	#[inline]
+	pub fn mute_notifications(&self) {
+		trace!("#[AppEventSender] mute_notifications");
+		self.__send(AppEvents::MuteNotifications);
+	}
+
+	#[inline]
+	pub fn save_flight_recording(&self) {
+		trace!("#[AppEventSender] save_flight_recording");
+		self.__send(AppEvents::SaveFlightRecording);
+	}
+
+	#[inline]
+	pub fn toggle_recording(&self) {
+		trace!("#[AppEventSender] toggle_recording");
+		self.__send(AppEvents::ToggleRecording);
+	}
+
 	#[inline]
 	pub fn show_or_focus_aboutdialog(&self) {
 		trace!("#[AppEventSender] show_or_focus_aboutdialog");
@@ -0,0 +1,145 @@
+use crate::core::cpufreq::CpuFreqWriter;
+use crate::core::fancurve::PwmWriter;
+use crate::core::helperproto::HELPER_SOCKET_PATH;
+use crate::core::helperproto::HelperClient;
+use crate::core::helperproto::helper_socket_path;
+use log::info;
+use log::warn;
+use std::io;
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Overrides the path of the helper executable.
+pub const HELPER_EXE_ENV: &str = "MACHINEPMMETER_HELPER";
+pub const HELPER_EXE_NAME: &str = "machinepmmeter-helper";
+/// How long to wait for the helper (and the polkit password dialog).
+const HELPER_START_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// `$MACHINEPMMETER_HELPER`, next to the executable or the usual libexec
+/// directories.
+pub fn find_helper_exe() -> Option<PathBuf> {
+	if let Some(path) = std::env::var_os(HELPER_EXE_ENV).filter(|a| !a.is_empty()) {
+		return Some(path.into());
+	}
+
+	let exe_dir = std::env::current_exe()
+		.ok()
+		.and_then(|a| a.parent().map(Path::to_path_buf));
+	exe_dir
+		.into_iter()
+		.chain(
+			[
+				"/usr/local/libexec",
+				"/usr/libexec",
+				"/usr/lib/machinepmmeter",
+			]
+			.map(PathBuf::from),
+		)
+		.map(|a| a.join(HELPER_EXE_NAME))
+		.find(|a| a.is_file())
+}
+
+/// Only one `pkexec` at a time, `true` once it was started.
+static HELPER_STARTED: Mutex<bool> = Mutex::new(false);
+
+/// Connects to the helper (a systemd service or an already started one),
+/// with `is_pkexec` (`[helper] pkexec = true`) starts it via pkexec once.
+/// Blocks while the password is requested, not for the GUI thread.
+pub fn connect_helper(is_pkexec: bool) -> Option<HelperClient> {
+	let mut path = helper_socket_path();
+	if let Ok(a) = HelperClient::connect(&path) {
+		return Some(a);
+	}
+	if !is_pkexec {
+		return None;
+	}
+	// a helper started via pkexec only listens on the default socket
+	if path != Path::new(HELPER_SOCKET_PATH) {
+		warn!(
+			"#[helper] {:?} is ignored via pkexec, using {:?}.",
+			path, HELPER_SOCKET_PATH
+		);
+		path = PathBuf::from(HELPER_SOCKET_PATH);
+	}
+
+	let Ok(mut is_started) = HELPER_STARTED.lock() else {
+		return None;
+	};
+	if !*is_started {
+		*is_started = true;
+
+		let Some(exe) = find_helper_exe() else {
+			warn!(
+				"#[helper] {} was not found (set {}).",
+				HELPER_EXE_NAME, HELPER_EXE_ENV
+			);
+			return None;
+		};
+		info!("#[helper] Starting {:?} via pkexec.", exe);
+		let uid = unsafe { libc::getuid() };
+		let result = Command::new("pkexec")
+			.arg(&exe)
+			.arg("--allow-uid")
+			.arg(uid.to_string())
+			.stdin(Stdio::null())
+			.process_group(0)
+			.spawn();
+		if let Err(e) = result {
+			warn!("#[helper] pkexec: {}", e);
+			return None;
+		}
+	}
+
+	let time = Instant::now();
+	loop {
+		match HelperClient::connect(&path) {
+			Ok(a) => return Some(a),
+			Err(e) if time.elapsed() >= HELPER_START_TIMEOUT => {
+				warn!("#[helper] Connect to {:?}: {}", path, e);
+
+				return None;
+			}
+			Err(_) => std::thread::sleep(Duration::from_millis(250)),
+		}
+	}
+}
+
+/// Fan writes through the helper, which also returns the fans to automatic
+/// mode when the dock exits or crashes.
+impl PwmWriter for HelperClient {
+	fn write_pwm(&mut self, path: &Path, value: u8) -> io::Result<()> {
+		let path = path
+			.to_str()
+			.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "non-UTF-8 path"))?;
+
+		self.write(path, &value.to_string())
+	}
+
+	#[inline]
+	fn restores_on_exit(&self) -> bool {
+		true
+	}
+}
+
+/// Governor/EPP/boost writes through the helper, which also restores the
+/// original values when the dock exits or crashes.
+impl CpuFreqWriter for HelperClient {
+	fn write_attr(&mut self, path: &Path, value: &str) -> io::Result<()> {
+		let path = path
+			.to_str()
+			.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "non-UTF-8 path"))?;
+
+		self.write(path, value)
+	}
+
+	#[inline]
+	fn restores_on_exit(&self) -> bool {
+		true
+	}
+}
@@ -7,6 +7,10 @@ use log::error;
 #[derive(Debug, Clone, Copy)]
 pub enum AppKeyboardEvents {
 	ShiftF8,
+	ShiftF9,
+	ShiftF10,
+	ShiftF11,
+	ShiftF12,
 	KeyA,
 	KeyD,
 	KeyP,
@@ -26,7 +30,7 @@ pub enum AppKeyboardEvents {
 
 pub fn spawn_keyboard_thread(esender: AppEventSender) {
 	std::thread::spawn(move || {
-		let keyboard_listener = KeyboardListenerBuilder::with_len::<18>()
+		let keyboard_listener = KeyboardListenerBuilder::with_len::<22>()
 			.key_mapping(|key_mapping| {
 				key_mapping[0].set_key(Key::ShiftLeft);
 				key_mapping[1].set_key(Key::ShiftRight);
@@ -46,6 +50,10 @@ pub fn spawn_keyboard_thread(esender: AppEventSender) {
 				key_mapping[15].set_key(Key::Num8);
 				key_mapping[16].set_key(Key::Num9);
 				key_mapping[17].set_key(Key::KeyP);
+				key_mapping[18].set_key(Key::F9);
+				key_mapping[19].set_key(Key::F10);
+				key_mapping[20].set_key(Key::F11);
+				key_mapping[21].set_key(Key::F12);
 			})
 			.handler(enc!((esender) move |state_array, _key, _state| {
 				let mut sa_iter = state_array.iter();
@@ -69,6 +77,10 @@ pub fn spawn_keyboard_thread(esender: AppEventSender) {
 							}
 							esender.keyboard_event(match pressed_key {
 								Some(Key::F8) => AppKeyboardEvents::ShiftF8,
+								Some(Key::F9) => AppKeyboardEvents::ShiftF9,
+								Some(Key::F10) => AppKeyboardEvents::ShiftF10,
+								Some(Key::F11) => AppKeyboardEvents::ShiftF11,
+								Some(Key::F12) => AppKeyboardEvents::ShiftF12,
 								Some(Key::KpPlus) => AppKeyboardEvents::KeyPlus,
 								Some(Key::KpMinus) => AppKeyboardEvents::KeyMinus,
 								Some(Key::Escape) => AppKeyboardEvents::Escape,
@@ -0,0 +1,278 @@
+use crate::app::cli::AppCli;
+use anyhow::Context;
+use anyhow::Result as anyhowResult;
+use log::info;
+use log::trace;
+use log::warn;
+use machinepmmeter_frametimeproto::FRAMETIME_SOCKET_ENV;
+use machinepmmeter_frametimeproto::FrametimeMessage;
+use machinepmmeter_frametimeproto::frametime_socket_path;
+use std::borrow::Cow;
+use std::ffi::OsStr;
+use std::ffi::OsString;
+use std::os::unix::net::UnixStream;
+use std::os::unix::process::CommandExt;
+use std::os::unix::process::ExitStatusExt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::Stdio;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Overrides the path of the frame-timing shim.
+pub const PRELOAD_LIB_ENV: &str = "MACHINEPMMETER_PRELOAD";
+pub const PRELOAD_LIB_NAME: &str = "libmachinepmmeter_preload.so";
+/// How long to wait for a freshly started dock.
+const DOCK_START_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// `$MACHINEPMMETER_PRELOAD`, next to the executable or the usual library
+/// directories.
+pub fn find_preload_lib() -> Option<PathBuf> {
+	if let Some(path) = std::env::var_os(PRELOAD_LIB_ENV).filter(|a| !a.is_empty()) {
+		return Some(path.into());
+	}
+
+	let exe_dir = std::env::current_exe()
+		.ok()
+		.and_then(|a| a.parent().map(Path::to_path_buf));
+	exe_dir
+		.into_iter()
+		.chain(
+			[
+				"/usr/local/lib/machinepmmeter",
+				"/usr/lib/machinepmmeter",
+				"/usr/lib64/machinepmmeter",
+			]
+			.map(PathBuf::from),
+		)
+		.map(|a| a.join(PRELOAD_LIB_NAME))
+		.find(|a| a.is_file())
+}
+
+/// `LD_PRELOAD` with `lib` appended (Steam already preloads its overlay).
+fn append_ld_preload(current: Option<&OsStr>, lib: &Path) -> OsString {
+	let mut result = OsString::new();
+	if let Some(current) = current.filter(|a| !a.is_empty()) {
+		result.push(current);
+		result.push(":");
+	}
+	result.push(lib);
+
+	result
+}
+
+/// Connects to the running dock, starts it if necessary.
+fn connect_or_start_dock(cli: &AppCli, path: &Path) -> anyhowResult<UnixStream> {
+	if let Ok(a) = UnixStream::connect(path) {
+		return Ok(a);
+	}
+
+	info!("#[run] The dock is not running, starting.");
+	let mut dock = Command::new(std::env::current_exe().context("Current executable.")?);
+	if let Some(app_config) = cli.get_app_config() {
+		dock.arg("--app-config").arg(app_config);
+	}
+	// not a part of the game (Steam overlay, game detection)
+	for key in [
+		"LD_PRELOAD",
+		"SteamAppId",
+		"SteamGameId",
+		"STEAM_COMPAT_APP_ID",
+		"STEAM_COMPAT_DATA_PATH",
+	] {
+		dock.env_remove(key);
+	}
+	// must outlive the command and not receive its signals
+	dock.stdin(Stdio::null()).process_group(0).spawn().context("Start the dock.")?;
+
+	let time = Instant::now();
+	loop {
+		std::thread::sleep(Duration::from_millis(100));
+		match UnixStream::connect(path) {
+			Ok(a) => return Ok(a),
+			Err(e) if time.elapsed() >= DOCK_START_TIMEOUT => {
+				return Err(e).with_context(|| format!("Connect to the dock {:?}.", path));
+			}
+			Err(_) => {}
+		}
+	}
+}
+
+/// `RECORD [path]`, the path is absolute (the dock has another working
+/// directory).
+pub fn record_message(path: Option<&Path>) -> anyhowResult<FrametimeMessage<'static>> {
+	let path = match path {
+		Some(path) => {
+			let path =
+				std::path::absolute(path).with_context(|| format!("Recording path {:?}.", path))?;
+
+			Some(Cow::Owned(path.to_string_lossy().into_owned()))
+		}
+		None => None,
+	};
+
+	Ok(FrametimeMessage::Record { path })
+}
+
+/// Sends `message` to the running dock.
+#[inline]
+pub fn send_to_dock(message: &FrametimeMessage) -> anyhowResult<()> {
+	send_to(&frametime_socket_path(), message)
+}
+
+/// Sends `message` to the frametime socket at `path`, the dock listens on it
+/// with any set of pages.
+fn send_to(path: &Path, message: &FrametimeMessage) -> anyhowResult<()> {
+	let dock = UnixStream::connect(path)
+		.with_context(|| format!("The dock is not running, connect to {:?}.", path))?;
+
+	message
+		.write_to(&dock)
+		.with_context(|| format!("Write to the dock {:?}.", path))
+}
+
+/// `machinepmmeter record [PATH]` and `machinepmmeter record --stop`.
+pub fn record_command(path: Option<&Path>, stop: bool) -> anyhowResult<()> {
+	let message = match stop {
+		true => FrametimeMessage::RecordStop,
+		false => record_message(path)?,
+	};
+	send_to_dock(&message)?;
+	info!("#[run] Sent to the dock: {:?}", message);
+
+	Ok(())
+}
+
+/// `--record` of a second instance, returns `true` if the running dock has
+/// started the recording.
+pub fn forward_record(path: &Path) -> bool {
+	match record_message(Some(path)).and_then(|a| send_to_dock(&a)) {
+		Ok(()) => {
+			info!(
+				"#[run] The dock is already running, it records to {:?}.",
+				path
+			);
+
+			true
+		}
+		Err(e) => {
+			trace!("#[run] {:#}", e);
+
+			false
+		}
+	}
+}
+
+/// `machinepmmeter run [--record[=PATH]] -- <command>`, returns the exit code
+/// of the command.
+pub fn run_command(
+	cli: &AppCli,
+	command: &[OsString],
+	record: Option<Option<&Path>>,
+) -> anyhowResult<i32> {
+	let (program, args) = command.split_first().context("Empty command.")?;
+	let record = record.map(record_message).transpose()?;
+	let path = frametime_socket_path();
+	let dock = match connect_or_start_dock(cli, &path) {
+		Ok(a) => Some(a),
+		Err(e) => {
+			warn!("#[run] {:#}, the command is started without monitoring.", e);
+
+			None
+		}
+	};
+
+	let mut child = Command::new(program);
+	child.args(args).env(FRAMETIME_SOCKET_ENV, &path);
+	match find_preload_lib() {
+		Some(lib) => {
+			info!("#[run] Frame timing: {:?}", lib);
+			child.env(
+				"LD_PRELOAD",
+				append_ld_preload(std::env::var_os("LD_PRELOAD").as_deref(), &lib),
+			);
+		}
+		None => warn!(
+			"#[run] {} was not found (set {}), frame timing is disabled.",
+			PRELOAD_LIB_NAME, PRELOAD_LIB_ENV
+		),
+	}
+	let mut child = child
+		.spawn()
+		.with_context(|| format!("Run {:?}.", program))?;
+
+	// the process tree is monitored while this connection is open
+	if let Some(ref dock) = dock {
+		let exe = Path::new(program)
+			.file_name()
+			.map_or_else(|| program.to_string_lossy(), |a| a.to_string_lossy());
+		let watch = FrametimeMessage::Watch {
+			pid: child.id(),
+			exe: Cow::Borrowed(&exe),
+		};
+		if let Err(e) = watch.write_to(dock) {
+			warn!("#[run] The dock does not accept the process: {}", e);
+		}
+		if let Some(ref record) = record
+			&& let Err(e) = record.write_to(dock)
+		{
+			warn!("#[run] The dock does not start the recording: {}", e);
+		}
+	}
+
+	let status = child.wait().context("Wait for the command.")?;
+	if let Some(ref dock) = dock
+		&& record.is_some()
+		&& let Err(e) = FrametimeMessage::RecordStop.write_to(dock)
+	{
+		warn!("#[run] The dock does not stop the recording: {}", e);
+	}
+	drop(dock);
+
+	Ok(status
+		.code()
+		.or_else(|| status.signal().map(|a| 128 + a))
+		.unwrap_or(1))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::core::frametimeserver::FrametimeCommand;
+	use crate::core::frametimeserver::spawn_frametime_server;
+	use std::sync::Mutex;
+	use std::sync::mpsc;
+
+	#[test]
+	fn record_without_frametime_page() {
+		let dir = std::env::temp_dir().join(format!("run-record-{}", std::process::id()));
+		let path = dir.join("frametime.sock");
+		assert!(send_to(&path, &FrametimeMessage::RecordStop).is_err());
+
+		// only the socket service of main, no pages
+		let (sender, recv) = mpsc::channel();
+		let sender = Mutex::new(sender);
+		let _server = spawn_frametime_server(path.clone(), move |a| {
+			sender.lock().unwrap().send(a).unwrap();
+		})
+		.unwrap();
+		let timeout = Duration::from_secs(5);
+		send_to(
+			&path,
+			&record_message(Some(&dir.join("session.csv"))).unwrap(),
+		)
+		.unwrap();
+		assert_eq!(
+			recv.recv_timeout(timeout).unwrap(),
+			FrametimeCommand::Record(Some(dir.join("session.csv")))
+		);
+		send_to(&path, &FrametimeMessage::RecordStop).unwrap();
+		assert_eq!(
+			recv.recv_timeout(timeout).unwrap(),
+			FrametimeCommand::RecordStop
+		);
+
+		let _e = std::fs::remove_dir_all(&dir);
+	}
+}
@@ -36,6 +36,30 @@ pub fn app_traymenu(tx_appevents: &AppEventSender) -> TrayMenu {
 		}));
 	});
 
+	let next_power_profile = enc!((tx_appevents) &mut move |vi: &mut ViIconMenuItem| {
+		vi.connect_activate(enc!((tx_appevents) move |_| {
+			tx_appevents.next_power_profile();
+		}));
+	});
+
+	let next_cpu_preset = enc!((tx_appevents) &mut move |vi: &mut ViIconMenuItem| {
+		vi.connect_activate(enc!((tx_appevents) move |_| {
+			tx_appevents.next_cpu_preset();
+		}));
+	});
+
+	let toggle_recording = enc!((tx_appevents) &mut move |vi: &mut ViIconMenuItem| {
+		vi.connect_activate(enc!((tx_appevents) move |_| {
+			tx_appevents.toggle_recording();
+		}));
+	});
+
+	let save_flight_recording = enc!((tx_appevents) &mut move |vi: &mut ViIconMenuItem| {
+		vi.connect_activate(enc!((tx_appevents) move |_| {
+			tx_appevents.save_flight_recording();
+		}));
+	});
+
 	let abouttheprogram = enc!((tx_appevents) &mut move |vi: &mut ViIconMenuItem| {
 		vi.connect_activate(enc!((tx_appevents) move |_| {
 			tx_appevents.show_or_focus_aboutdialog();
@@ -65,6 +89,28 @@ pub fn app_traymenu(tx_appevents: &AppEventSender) -> TrayMenu {
 				next_position,
 			),
 			TrayMenuItem::Separator,
+			TrayMenuItem::icon_item(
+				"power-profile-balanced-symbolic",
+				"Next power profile",
+				next_power_profile,
+			),
+			TrayMenuItem::icon_item(
+				"power-profile-performance-symbolic",
+				"Next CPU preset",
+				next_cpu_preset,
+			),
+			TrayMenuItem::Separator,
+			TrayMenuItem::icon_item(
+				"media-record-symbolic",
+				"Start | Stop recording",
+				toggle_recording,
+			),
+			TrayMenuItem::icon_item(
+				"document-save-symbolic",
+				"Save last minutes",
+				save_flight_recording,
+			),
+			TrayMenuItem::Separator,
 			TrayMenuItem::item("About the program", abouttheprogram),
 			TrayMenuItem::icon_item("system-shutdown-symbolic", "Exit", exit),
 		]
@@ -0,0 +1,919 @@
+//! Alert rules of AppConfig, evaluated against the sensor registry.
+//!
+//! ```toml
+//! [sensors.alias]
+//! "cpu.tctl" = "lm_sensors/k10temp-pci-00c3/temp1"
+//!
+//! [[alert]]
+//! name = "CPU is hot"
+//! rule = "cpu.tctl > 90 for 5s"
+//! severity = "critical"  # info, warning (default), critical
+//! hysteresis = 3.0       # clears below 87, 0 if not set
+//! cooldown_ms = 60000    # between two alerts of the rule, 0 if not set
+//!
+//! [[alert]]
+//! rule = "fan1 < 300 while cpu.tctl > 60 and fans/nct6798/fan2 < 300"
+//!
+//! [[alert]]
+//! name = "Package power"
+//! rule = "rapl/intel-rapl:0/package-0 > 120 for 10s"
+//! on_enter = "powerprofilesctl set power-saver"  # sh -c, see `alerthook`
+//! on_exit = "powerprofilesctl set balanced"
+//! hook_timeout_ms = 10000   # killed after, default
+//! hook_interval_ms = 60000  # between two starts of a hook, default
+//! ```
+//!
+//! A sensor is an alias, a sensor ID, the end of a sensor ID (`fan1`,
+//! `nct6798/fan1`) or a display name. An end or a name of several sensors is
+//! rejected, the rule needs the full ID or an alias.
+
+use crate::core::sensorregistry::SENSOR_REGISTRY;
+use crate::core::sensorregistry::SensorEntry;
+use log::info;
+use log::trace;
+use log::warn;
+use serde::Deserialize;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::RwLock;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+/// Rules are evaluated this often (the samplers update the registry at
+/// their own rate).
+pub const ALERT_EVAL_INTERVAL: Duration = Duration::from_millis(250);
+/// The sensors of a rule are reported as missing after this time (the pages
+/// register them while starting).
+const ALERT_RESOLVE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertSeverity {
+	Info,
+	#[default]
+	Warning,
+	Critical,
+}
+
+impl AlertSeverity {
+	pub const fn as_str(&self) -> &'static str {
+		match self {
+			Self::Info => "info",
+			Self::Warning => "warning",
+			Self::Critical => "critical",
+		}
+	}
+}
+
+impl Display for AlertSeverity {
+	#[inline]
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.as_str())
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertOp {
+	Gt,
+	Ge,
+	Lt,
+	Le,
+}
+
+impl AlertOp {
+	pub const fn as_str(&self) -> &'static str {
+		match self {
+			Self::Gt => ">",
+			Self::Ge => ">=",
+			Self::Lt => "<",
+			Self::Le => "<=",
+		}
+	}
+}
+
+/// `cpu.tctl > 90`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertCondition {
+	sensor: String,
+	op: AlertOp,
+	value: f64,
+}
+
+impl AlertCondition {
+	#[inline]
+	pub fn get_sensor(&self) -> &str {
+		&self.sensor
+	}
+
+	#[inline]
+	pub const fn get_op(&self) -> AlertOp {
+		self.op
+	}
+
+	/// The threshold.
+	#[inline]
+	pub const fn get_value(&self) -> f64 {
+		self.value
+	}
+
+	#[inline]
+	pub fn is_met(&self, value: f64) -> bool {
+		self.is_met_with(value, 0.0)
+	}
+
+	/// With the threshold moved by `hysteresis` towards the normal values, for
+	/// an active alert.
+	pub fn is_met_with(&self, value: f64, hysteresis: f64) -> bool {
+		match self.op {
+			AlertOp::Gt => value > self.value - hysteresis,
+			AlertOp::Ge => value >= self.value - hysteresis,
+			AlertOp::Lt => value < self.value + hysteresis,
+			AlertOp::Le => value <= self.value + hysteresis,
+		}
+	}
+}
+
+impl Display for AlertCondition {
+	#[inline]
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{} {} {}", self.sensor, self.op.as_str(), self.value)
+	}
+}
+
+/// `<condition> [for <duration>] [while <condition> [and <condition>]...]`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(try_from = "String")]
+pub struct AlertRule {
+	condition: AlertCondition,
+	/// The condition has to be met for this long.
+	duration: Duration,
+	/// Only while all of these are met, without a duration and hysteresis.
+	gates: Vec<AlertCondition>,
+}
+
+impl AlertRule {
+	#[inline]
+	pub const fn get_condition(&self) -> &AlertCondition {
+		&self.condition
+	}
+
+	#[inline]
+	pub const fn get_duration(&self) -> Duration {
+		self.duration
+	}
+
+	#[inline]
+	pub fn get_gates(&self) -> &[AlertCondition] {
+		&self.gates
+	}
+}
+
+impl Display for AlertRule {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.condition)?;
+		if !self.duration.is_zero() {
+			write!(f, " for {:?}", self.duration)?;
+		}
+		for (i, gate) in self.gates.iter().enumerate() {
+			write!(f, " {} {}", if i == 0 { "while" } else { "and" }, gate)?;
+		}
+
+		Ok(())
+	}
+}
+
+/// `5s`, `500ms`, `2m`, `1h`.
+fn parse_duration(a: &str) -> Option<Duration> {
+	let (value, scale) = if let Some(a) = a.strip_suffix("ms") {
+		(a, 0.001)
+	} else if let Some(a) = a.strip_suffix('s') {
+		(a, 1.0)
+	} else if let Some(a) = a.strip_suffix('m') {
+		(a, 60.0)
+	} else if let Some(a) = a.strip_suffix('h') {
+		(a, 3600.0)
+	} else {
+		return None;
+	};
+
+	value
+		.parse::<f64>()
+		.ok()
+		.filter(|a| a.is_finite() && *a >= 0.0)
+		.map(|a| Duration::from_secs_f64(a * scale))
+}
+
+/// Words, the operators do not need spaces around them (`temp1>90`).
+fn tokenize(rule: &str) -> Vec<&str> {
+	let mut result = Vec::new();
+	for word in rule.split_whitespace() {
+		let mut rest = word;
+		while let Some(i) = rest.find(['<', '>']) {
+			let len = match rest[i + 1..].starts_with('=') {
+				true => 2,
+				false => 1,
+			};
+			if i != 0 {
+				result.push(&rest[..i]);
+			}
+			result.push(&rest[i..i + len]);
+			rest = &rest[i + len..];
+		}
+		if !rest.is_empty() {
+			result.push(rest);
+		}
+	}
+
+	result
+}
+
+fn parse_condition<'a>(
+	tokens: &mut impl Iterator<Item = &'a str>,
+) -> Result<AlertCondition, String> {
+	let sensor = tokens.next().ok_or("expected a sensor")?;
+	let op = match tokens.next() {
+		Some(">") => AlertOp::Gt,
+		Some(">=") => AlertOp::Ge,
+		Some("<") => AlertOp::Lt,
+		Some("<=") => AlertOp::Le,
+		a => {
+			return Err(format!(
+				"expected >, >=, < or <= after {:?}, found {:?}",
+				sensor, a
+			));
+		}
+	};
+	let value = tokens
+		.next()
+		.and_then(|a| a.parse::<f64>().ok())
+		.filter(|a| a.is_finite())
+		.ok_or_else(|| format!("expected a number after {:?} {}", sensor, op.as_str()))?;
+
+	Ok(AlertCondition {
+		sensor: sensor.to_string(),
+		op,
+		value,
+	})
+}
+
+impl FromStr for AlertRule {
+	type Err = String;
+
+	fn from_str(rule: &str) -> Result<Self, Self::Err> {
+		let tokens = tokenize(rule);
+		let mut tokens = tokens.into_iter().peekable();
+
+		let condition = parse_condition(&mut tokens)?;
+		let mut duration = Duration::ZERO;
+		if tokens.next_if_eq(&"for").is_some() {
+			duration = tokens
+				.next()
+				.and_then(parse_duration)
+				.ok_or("expected a duration after `for` (5s, 500ms, 2m)")?;
+		}
+		let mut gates = Vec::new();
+		if tokens.next_if_eq(&"while").is_some() {
+			gates.push(parse_condition(&mut tokens)?);
+			while tokens.next_if_eq(&"and").is_some() {
+				gates.push(parse_condition(&mut tokens)?);
+			}
+		}
+		if let Some(a) = tokens.next() {
+			return Err(format!("unexpected {:?} in {:?}", a, rule));
+		}
+
+		Ok(Self {
+			condition,
+			duration,
+			gates,
+		})
+	}
+}
+
+impl TryFrom<String> for AlertRule {
+	type Error = String;
+
+	#[inline]
+	fn try_from(a: String) -> Result<Self, Self::Error> {
+		a.parse()
+	}
+}
+
+/// `[[alert]]` of AppConfig.
+#[derive(Deserialize, Debug, Clone)]
+pub struct AlertRuleConfig {
+	name: Option<String>,
+	rule: AlertRule,
+	#[serde(default)]
+	severity: AlertSeverity,
+	hysteresis: Option<f64>,
+	cooldown_ms: Option<u64>,
+	on_enter: Option<String>,
+	on_exit: Option<String>,
+	hook_timeout_ms: Option<u64>,
+	hook_interval_ms: Option<u64>,
+}
+
+impl AlertRuleConfig {
+	/// The rule itself if not set.
+	pub fn get_name(&self) -> String {
+		self.name.clone().unwrap_or_else(|| self.rule.to_string())
+	}
+
+	#[inline]
+	pub const fn get_rule(&self) -> &AlertRule {
+		&self.rule
+	}
+
+	#[inline]
+	pub const fn get_severity(&self) -> AlertSeverity {
+		self.severity
+	}
+
+	#[inline]
+	pub fn get_hysteresis(&self) -> f64 {
+		self.hysteresis.unwrap_or(0.0).max(0.0)
+	}
+
+	#[inline]
+	pub fn get_cooldown(&self) -> Duration {
+		self.cooldown_ms
+			.map_or(Duration::ZERO, Duration::from_millis)
+	}
+
+	/// The command when the rule starts firing.
+	#[inline]
+	pub fn get_on_enter(&self) -> Option<&str> {
+		self.on_enter.as_deref().filter(|a| !a.trim().is_empty())
+	}
+
+	/// The command when the rule stops firing.
+	#[inline]
+	pub fn get_on_exit(&self) -> Option<&str> {
+		self.on_exit.as_deref().filter(|a| !a.trim().is_empty())
+	}
+
+	#[inline]
+	pub fn get_hook_timeout(&self) -> Duration {
+		self.hook_timeout_ms
+			.map_or(Duration::from_secs(10), Duration::from_millis)
+	}
+
+	#[inline]
+	pub fn get_hook_interval(&self) -> Duration {
+		self.hook_interval_ms
+			.map_or(Duration::from_secs(60), Duration::from_millis)
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum AlertStatus {
+	/// Not met, or its sensors were not found.
+	#[default]
+	Inactive,
+	/// Met, waiting for the duration (or the cooldown).
+	Pending,
+	Firing,
+}
+
+/// The state of a rule, for the UI and the other consumers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertState {
+	pub name: String,
+	pub rule: String,
+	pub severity: AlertSeverity,
+	pub status: AlertStatus,
+	/// The sensor ID of the condition, once found.
+	pub sensor: Option<String>,
+	/// The last value of the condition sensor.
+	pub value: Option<f64>,
+	/// Since when the rule is firing.
+	pub since: Option<SystemTime>,
+	/// How long the condition has been met (by the sample times).
+	pub duration: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlertEventKind {
+	Fired,
+	Cleared,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertEvent {
+	pub kind: AlertEventKind,
+	/// The index of the rule in `[[alert]]`.
+	pub index: usize,
+	pub state: AlertState,
+}
+
+/// The states of all rules and the subscribers of the transitions.
+#[derive(Debug)]
+pub struct AlertBoard {
+	states: RwLock<Vec<AlertState>>,
+	subscribers: Mutex<Vec<async_channel::Sender<AlertEvent>>>,
+}
+
+pub static ALERTS: AlertBoard = AlertBoard::new();
+
+impl Default for AlertBoard {
+	#[inline]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl AlertBoard {
+	pub const fn new() -> Self {
+		Self {
+			states: RwLock::new(Vec::new()),
+			subscribers: Mutex::new(Vec::new()),
+		}
+	}
+
+	pub fn states(&self) -> Vec<AlertState> {
+		match self.states.read() {
+			Ok(a) => a.clone(),
+			Err(e) => e.into_inner().clone(),
+		}
+	}
+
+	/// The firing rules, the most severe first.
+	pub fn firing(&self) -> Vec<AlertState> {
+		let mut result: Vec<AlertState> = self
+			.states()
+			.into_iter()
+			.filter(|a| a.status == AlertStatus::Firing)
+			.collect();
+		result.sort_by_key(|a| Reverse(a.severity));
+
+		result
+	}
+
+	/// Every consumer gets all transitions. A consumer that does not keep up
+	/// misses them (the states are still up to date).
+	pub fn subscribe(&self) -> async_channel::Receiver<AlertEvent> {
+		let (sender, recv) = async_channel::bounded(32);
+		match self.subscribers.lock() {
+			Ok(mut a) => a.push(sender),
+			Err(e) => e.into_inner().push(sender),
+		}
+
+		recv
+	}
+
+	fn set_states(&self, states: Vec<AlertState>) {
+		match self.states.write() {
+			Ok(mut a) => *a = states,
+			Err(e) => *e.into_inner() = states,
+		}
+	}
+
+	fn set_state(&self, index: usize, state: &AlertState) {
+		let mut states = match self.states.write() {
+			Ok(a) => a,
+			Err(e) => e.into_inner(),
+		};
+		if let Some(a) = states.get_mut(index) {
+			a.clone_from(state);
+		}
+	}
+
+	fn publish(&self, event: AlertEvent) {
+		let mut subscribers = match self.subscribers.lock() {
+			Ok(a) => a,
+			Err(e) => e.into_inner(),
+		};
+		subscribers.retain(|a| match a.try_send(event.clone()) {
+			Ok(()) => true,
+			Err(async_channel::TrySendError::Full(_)) => {
+				trace!(
+					"#[alert] A consumer is behind, {:?} was dropped",
+					event.kind
+				);
+
+				true
+			}
+			Err(async_channel::TrySendError::Closed(_)) => false,
+		});
+	}
+}
+
+/// Alias, ID, end of an ID, display name (case-insensitive).
+fn resolve_sensor(
+	sensor: &str,
+	aliases: &HashMap<String, String>,
+	entries: &[Arc<SensorEntry>],
+) -> Result<Arc<SensorEntry>, String> {
+	let sensor = aliases.get(sensor).map_or(sensor, String::as_str);
+	if let Some(a) = entries.iter().find(|a| a.id().as_str() == sensor) {
+		return Ok(a.clone());
+	}
+
+	let suffix = format!("/{}", sensor);
+	let mut found: Vec<_> = entries
+		.iter()
+		.filter(|a| a.id().ends_with(&suffix))
+		.collect();
+	if found.is_empty() {
+		found = entries
+			.iter()
+			.filter(|a| a.name().eq_ignore_ascii_case(sensor))
+			.collect();
+	}
+	match found.as_slice() {
+		[a] => Ok((*a).clone()),
+		[] => Err(format!("{:?} was not found", sensor)),
+		a => Err(format!(
+			"{:?} is ambiguous: {}",
+			sensor,
+			a.iter()
+				.map(|a| a.id().as_str())
+				.collect::<Vec<_>>()
+				.join(", ")
+		)),
+	}
+}
+
+/// A rule with its sensors and timers.
+struct AlertRunner {
+	index: usize,
+	config: AlertRuleConfig,
+	/// The condition, then the gates.
+	entries: Option<Vec<Arc<SensorEntry>>>,
+	is_missing_reported: bool,
+	pending_since: Option<Instant>,
+	last_fired: Option<Instant>,
+	state: AlertState,
+}
+
+impl AlertRunner {
+	fn new(index: usize, config: AlertRuleConfig) -> Self {
+		let state = AlertState {
+			name: config.get_name(),
+			rule: config.get_rule().to_string(),
+			severity: config.get_severity(),
+			status: AlertStatus::Inactive,
+			sensor: None,
+			value: None,
+			since: None,
+			duration: Duration::ZERO,
+		};
+
+		Self {
+			index,
+			config,
+			entries: None,
+			is_missing_reported: false,
+			pending_since: None,
+			last_fired: None,
+			state,
+		}
+	}
+
+	fn resolve(&mut self, aliases: &HashMap<String, String>, started: Instant) -> bool {
+		if self.entries.is_some() {
+			return true;
+		}
+
+		let rule = self.config.get_rule();
+		let registry = SENSOR_REGISTRY.entries();
+		let entries: Result<Vec<_>, _> = std::iter::once(rule.get_condition())
+			.chain(rule.get_gates())
+			.map(|a| resolve_sensor(a.get_sensor(), aliases, &registry))
+			.collect();
+		match entries {
+			Ok(entries) => {
+				trace!(
+					"#[alert] {:?}: {:?}",
+					self.state.name,
+					entries.iter().map(|a| a.id().as_str()).collect::<Vec<_>>()
+				);
+				self.state.sensor = Some(entries[0].id().to_string());
+				self.entries = Some(entries);
+
+				true
+			}
+			Err(e) => {
+				if !self.is_missing_reported && started.elapsed() >= ALERT_RESOLVE_TIMEOUT {
+					warn!(
+						"#[alert] {:?}: {} in {:?}, see the trace log for the sensor IDs",
+						self.state.name, e, self.state.rule
+					);
+					self.is_missing_reported = true;
+				}
+
+				false
+			}
+		}
+	}
+
+	/// The new status, an event on a transition to or from firing.
+	fn tick(&mut self) -> Option<AlertEventKind> {
+		let entries = self.entries.as_ref()?;
+		let rule = self.config.get_rule();
+
+		let sample = entries[0].sample();
+		self.state.value = sample.map(|a| a.value);
+		let is_gate_open = rule
+			.get_gates()
+			.iter()
+			.zip(&entries[1..])
+			.all(|(gate, entry)| entry.sample().is_some_and(|a| gate.is_met(a.value)));
+
+		let (old_status, now) = (self.state.status, Instant::now());
+		match self.state.status {
+			AlertStatus::Inactive | AlertStatus::Pending => match sample {
+				Some(sample) if is_gate_open && rule.get_condition().is_met(sample.value) => {
+					let since = *self.pending_since.get_or_insert(sample.instant);
+					let is_cooled_down = self
+						.last_fired
+						.is_none_or(|a| now.duration_since(a) >= self.config.get_cooldown());
+					if sample.instant.duration_since(since) >= rule.get_duration() && is_cooled_down
+					{
+						self.state.status = AlertStatus::Firing;
+						self.state.since = Some(SystemTime::now());
+						self.last_fired = Some(now);
+					} else {
+						self.state.status = AlertStatus::Pending;
+					}
+				}
+				_ => {
+					self.state.status = AlertStatus::Inactive;
+					self.pending_since = None;
+				}
+			},
+			AlertStatus::Firing => {
+				let is_met = sample.is_some_and(|a| {
+					rule.get_condition()
+						.is_met_with(a.value, self.config.get_hysteresis())
+				});
+				if !is_met || !is_gate_open {
+					self.state.status = AlertStatus::Inactive;
+					self.state.since = None;
+					self.pending_since = None;
+				}
+			}
+		}
+		self.state.duration = match (self.pending_since, sample) {
+			(Some(since), Some(sample)) => sample.instant.duration_since(since),
+			_ => Duration::ZERO,
+		};
+
+		match (old_status, self.state.status) {
+			(AlertStatus::Firing, AlertStatus::Firing) => None,
+			(_, AlertStatus::Firing) => Some(AlertEventKind::Fired),
+			(AlertStatus::Firing, _) => Some(AlertEventKind::Cleared),
+			_ => None,
+		}
+	}
+}
+
+/// Evaluates the rules in the background, also while the dock is hidden.
+/// The states and transitions are published to [`ALERTS`].
+pub fn spawn_alert_engine(
+	rules: Vec<AlertRuleConfig>,
+	aliases: HashMap<String, String>,
+	interval: Duration,
+) {
+	if rules.is_empty() {
+		return;
+	}
+	let mut runners: Vec<AlertRunner> = rules
+		.into_iter()
+		.enumerate()
+		.map(|(index, config)| AlertRunner::new(index, config))
+		.collect();
+	ALERTS.set_states(runners.iter().map(|a| a.state.clone()).collect());
+
+	std::thread::spawn(move || {
+		let started = Instant::now();
+		loop {
+			for runner in &mut runners {
+				if !runner.resolve(&aliases, started) {
+					continue;
+				}
+				let kind = runner.tick();
+				ALERTS.set_state(runner.index, &runner.state);
+
+				if let Some(kind) = kind {
+					match kind {
+						AlertEventKind::Fired => warn!(
+							"#[alert] {} ({}): {:?} = {:?}",
+							runner.state.name,
+							runner.state.severity,
+							runner.state.sensor,
+							runner.state.value
+						),
+						AlertEventKind::Cleared => info!("#[alert] {}: cleared", runner.state.name),
+					}
+					ALERTS.publish(AlertEvent {
+						kind,
+						index: runner.index,
+						state: runner.state.clone(),
+					});
+				}
+			}
+
+			std::thread::sleep(interval);
+		}
+	});
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::core::sensorregistry::SensorId;
+	use crate::core::sensorregistry::SensorKind;
+	use crate::core::sensorregistry::SensorRegistry;
+	use crate::core::sensorregistry::SensorUnit;
+
+	fn condition(sensor: &str, op: AlertOp, value: f64) -> AlertCondition {
+		AlertCondition {
+			sensor: sensor.to_string(),
+			op,
+			value,
+		}
+	}
+
+	fn runner(rule: &str, hysteresis: f64, cooldown_ms: u64) -> AlertRunner {
+		AlertRunner::new(
+			0,
+			AlertRuleConfig {
+				name: None,
+				rule: rule.parse().unwrap(),
+				severity: AlertSeverity::Warning,
+				hysteresis: Some(hysteresis),
+				cooldown_ms: Some(cooldown_ms),
+				on_enter: None,
+				on_exit: None,
+				hook_timeout_ms: None,
+				hook_interval_ms: None,
+			},
+		)
+	}
+
+	fn register(registry: &SensorRegistry, device: &str, feature: &str) -> Arc<SensorEntry> {
+		registry.register(
+			SensorId::new("test", device, feature),
+			format!("{} {}", device, feature),
+			SensorKind::Other,
+			SensorUnit::None,
+		)
+	}
+
+	#[test]
+	fn parse_rules() {
+		let rule: AlertRule = "x > 90 for 5s".parse().unwrap();
+		assert_eq!(rule.get_condition(), &condition("x", AlertOp::Gt, 90.0));
+		assert_eq!(rule.get_duration(), Duration::from_secs(5));
+		assert!(rule.get_gates().is_empty());
+
+		let rule: AlertRule = "a<300 while b>60 and c<1".parse().unwrap();
+		assert_eq!(rule.get_condition(), &condition("a", AlertOp::Lt, 300.0));
+		assert_eq!(rule.get_duration(), Duration::ZERO);
+		assert_eq!(
+			rule.get_gates(),
+			&[
+				condition("b", AlertOp::Gt, 60.0),
+				condition("c", AlertOp::Lt, 1.0)
+			]
+		);
+
+		let rule: AlertRule = "temp1>=90.5 for 500ms while fan1<=0".parse().unwrap();
+		assert_eq!(rule.get_condition(), &condition("temp1", AlertOp::Ge, 90.5));
+		assert_eq!(rule.get_duration(), Duration::from_millis(500));
+		assert_eq!(rule.get_gates(), &[condition("fan1", AlertOp::Le, 0.0)]);
+	}
+
+	#[test]
+	fn parse_bad_rules() {
+		for rule in [
+			"",
+			"x",
+			"x = 90",
+			"x > hot",
+			"x > inf",
+			"x > 90 for",
+			"x > 90 for 5",
+			"x > 90 for -5s",
+			"x > 90 while",
+			"x > 90 while y",
+			"x > 90 and y < 1",
+			"x > 90 for 5s extra",
+		] {
+			assert!(rule.parse::<AlertRule>().is_err(), "{:?}", rule);
+		}
+	}
+
+	#[test]
+	fn resolve_sensors() {
+		let registry = SensorRegistry::new();
+		let fan1 = register(&registry, "nct6798", "fan1");
+		register(&registry, "nct6798", "temp1");
+		register(&registry, "k10temp", "temp1");
+		let entries = registry.entries();
+		let aliases = HashMap::from([("cpu".to_string(), "test/k10temp/temp1".to_string())]);
+		let resolve = |a| resolve_sensor(a, &aliases, &entries).map(|a| a.id().to_string());
+
+		assert_eq!(resolve("test/nct6798/fan1").unwrap(), "test/nct6798/fan1");
+		assert_eq!(resolve("fan1").unwrap(), fan1.id().as_str());
+		assert_eq!(resolve("NCT6798 FAN1").unwrap(), "test/nct6798/fan1");
+		assert_eq!(resolve("cpu").unwrap(), "test/k10temp/temp1");
+		assert_eq!(resolve("k10temp/temp1").unwrap(), "test/k10temp/temp1");
+		assert!(resolve("temp1").unwrap_err().contains("ambiguous"));
+		assert!(resolve("fan2").unwrap_err().contains("not found"));
+	}
+
+	#[test]
+	fn pending_firing_hysteresis() {
+		let registry = SensorRegistry::new();
+		let x = register(&registry, "chip", "x");
+		let mut runner = runner("x > 90 for 50ms", 3.0, 0);
+		runner.entries = Some(vec![x.clone()]);
+
+		assert_eq!(runner.tick(), None);
+		assert_eq!(runner.state.status, AlertStatus::Inactive);
+
+		x.update(95.0, 0.0, 100.0);
+		assert_eq!(runner.tick(), None);
+		assert_eq!(runner.state.status, AlertStatus::Pending);
+
+		std::thread::sleep(Duration::from_millis(60));
+		x.update(95.0, 0.0, 100.0);
+		assert_eq!(runner.tick(), Some(AlertEventKind::Fired));
+		assert_eq!(runner.state.status, AlertStatus::Firing);
+		assert!(runner.state.since.is_some());
+		assert!(runner.state.duration >= Duration::from_millis(50));
+
+		// within the hysteresis
+		x.update(88.0, 0.0, 100.0);
+		assert_eq!(runner.tick(), None);
+		assert_eq!(runner.state.status, AlertStatus::Firing);
+
+		x.update(86.0, 0.0, 100.0);
+		assert_eq!(runner.tick(), Some(AlertEventKind::Cleared));
+		assert_eq!(runner.state.status, AlertStatus::Inactive);
+		assert_eq!(runner.state.since, None);
+		assert_eq!(runner.state.duration, Duration::ZERO);
+
+		// a dip below the threshold restarts the duration
+		x.update(95.0, 0.0, 100.0);
+		assert_eq!(runner.tick(), None);
+		x.update(80.0, 0.0, 100.0);
+		assert_eq!(runner.tick(), None);
+		assert_eq!(runner.state.status, AlertStatus::Inactive);
+	}
+
+	#[test]
+	fn cooldown() {
+		let registry = SensorRegistry::new();
+		let x = register(&registry, "chip", "x");
+		let mut runner = runner("x > 90", 0.0, 100);
+		runner.entries = Some(vec![x.clone()]);
+
+		x.update(95.0, 0.0, 100.0);
+		assert_eq!(runner.tick(), Some(AlertEventKind::Fired));
+		x.update(80.0, 0.0, 100.0);
+		assert_eq!(runner.tick(), Some(AlertEventKind::Cleared));
+
+		x.update(95.0, 0.0, 100.0);
+		assert_eq!(runner.tick(), None);
+		assert_eq!(runner.state.status, AlertStatus::Pending);
+
+		std::thread::sleep(Duration::from_millis(110));
+		x.update(95.0, 0.0, 100.0);
+		assert_eq!(runner.tick(), Some(AlertEventKind::Fired));
+	}
+
+	#[test]
+	fn gates() {
+		let registry = SensorRegistry::new();
+		let (a, b) = (
+			register(&registry, "chip", "a"),
+			register(&registry, "chip", "b"),
+		);
+		let mut runner = runner("a < 300 while b > 60", 0.0, 0);
+		runner.entries = Some(vec![a.clone(), b.clone()]);
+
+		a.update(100.0, 0.0, 2000.0);
+		// without a sample of the gate
+		assert_eq!(runner.tick(), None);
+		b.update(50.0, 0.0, 100.0);
+		assert_eq!(runner.tick(), None);
+		assert_eq!(runner.state.status, AlertStatus::Inactive);
+
+		b.update(70.0, 0.0, 100.0);
+		assert_eq!(runner.tick(), Some(AlertEventKind::Fired));
+		assert_eq!(runner.state.value, Some(100.0));
+
+		b.update(50.0, 0.0, 100.0);
+		assert_eq!(runner.tick(), Some(AlertEventKind::Cleared));
+	}
+}
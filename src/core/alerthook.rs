@@ -0,0 +1,347 @@
+//! `on_enter`/`on_exit` commands of the `[[alert]]` rules.
+//!
+//! A command runs with `sh -c` in its own process group, with the alert in
+//! the environment:
+//!
+//! | variable                          | value                               |
+//! |-----------------------------------|-------------------------------------|
+//! | `MACHINEPMMETER_ALERT_EVENT`      | `enter`, `exit`                     |
+//! | `MACHINEPMMETER_ALERT_NAME`       | `CPU is hot`                        |
+//! | `MACHINEPMMETER_ALERT_SEVERITY`   | `info`, `warning`, `critical`       |
+//! | `MACHINEPMMETER_ALERT_RULE`       | `cpu.tctl > 90 for 5s`              |
+//! | `MACHINEPMMETER_ALERT_SENSOR`     | `lm_sensors/k10temp-pci-00c3/temp1` |
+//! | `MACHINEPMMETER_ALERT_VALUE`      | `92.5`                              |
+//! | `MACHINEPMMETER_ALERT_OP`         | `>`                                 |
+//! | `MACHINEPMMETER_ALERT_THRESHOLD`  | `90`                                |
+//!
+//! The group is killed after `hook_timeout_ms`, the output goes to the log.
+//! A hook starts at most once per `hook_interval_ms` and never twice at once,
+//! but the exit hook always follows an enter hook that ran (to undo it).
+
+use crate::core::alert::ALERTS;
+use crate::core::alert::AlertEvent;
+use crate::core::alert::AlertEventKind;
+use crate::core::alert::AlertRuleConfig;
+use log::error;
+use log::info;
+use log::trace;
+use log::warn;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::os::unix::process::CommandExt;
+use std::process::Command;
+use std::process::Stdio;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use std::time::Instant;
+
+pub const ALERT_HOOK_ENV_PREFIX: &str = "MACHINEPMMETER_ALERT_";
+/// The lines of stdout and stderr in the log, per run.
+const ALERT_HOOK_MAX_LOG_LINES: usize = 64;
+/// How long the output is read after the command exited (a background
+/// child may keep the pipes open), then the readers are detached.
+const ALERT_HOOK_READER_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlertHookKind {
+	Enter,
+	Exit,
+}
+
+impl AlertHookKind {
+	pub const fn as_str(&self) -> &'static str {
+		match self {
+			Self::Enter => "enter",
+			Self::Exit => "exit",
+		}
+	}
+}
+
+/// The hooks of a rule and their last runs.
+#[derive(Debug)]
+struct AlertHooks {
+	config: AlertRuleConfig,
+	last_start: Option<Instant>,
+	/// The enter hook ran, the exit hook must run.
+	is_entered: bool,
+	/// The thread of the last hook.
+	thread: Option<JoinHandle<()>>,
+}
+
+impl AlertHooks {
+	fn new(config: AlertRuleConfig) -> Self {
+		Self {
+			config,
+			last_start: None,
+			is_entered: false,
+			thread: None,
+		}
+	}
+
+	fn command(&self, kind: AlertHookKind) -> Option<&str> {
+		match kind {
+			AlertHookKind::Enter => self.config.get_on_enter(),
+			AlertHookKind::Exit => self.config.get_on_exit(),
+		}
+	}
+
+	/// The command to run now, `None` if rate limited.
+	fn next(&mut self, kind: AlertHookKind) -> Option<String> {
+		let command = self.command(kind)?.to_string();
+		let is_paired_exit = kind == AlertHookKind::Exit && self.is_entered;
+		if !is_paired_exit {
+			if self.thread.as_ref().is_some_and(|a| !a.is_finished()) {
+				trace!("#[alerthook] {}: still running", self.config.get_name());
+				return None;
+			}
+			if self
+				.last_start
+				.is_some_and(|a| a.elapsed() < self.config.get_hook_interval())
+			{
+				trace!("#[alerthook] {}: rate limited", self.config.get_name());
+				return None;
+			}
+		}
+		self.is_entered = kind == AlertHookKind::Enter;
+		self.last_start = Some(Instant::now());
+
+		Some(command)
+	}
+
+	/// Starts the hook of `kind` in the background (the next events are not
+	/// delayed by a slow command). A paired exit hook waits for the enter hook.
+	fn run(&mut self, kind: AlertHookKind, event: &AlertEvent) {
+		let Some(command) = self.next(kind) else {
+			return;
+		};
+
+		let env = hook_env(kind, &self.config, event);
+		let name = format!("{} ({})", self.config.get_name(), kind.as_str());
+		let timeout = self.config.get_hook_timeout();
+		let previous = self.thread.take();
+		self.thread = Some(std::thread::spawn(move || {
+			if let Some(previous) = previous {
+				let _e = previous.join();
+			}
+			run_hook(&name, &command, &env, timeout);
+		}));
+	}
+}
+
+/// Logs the lines of a pipe of the hook.
+fn spawn_log_reader(
+	name: String,
+	stream: &'static str,
+	pipe: impl Read + Send + 'static,
+) -> JoinHandle<()> {
+	std::thread::spawn(move || {
+		for (i, line) in BufReader::new(pipe).lines().enumerate() {
+			let Ok(line) = line else {
+				break;
+			};
+			match i {
+				i if i < ALERT_HOOK_MAX_LOG_LINES => {
+					info!("#[alerthook] {} {}: {}", name, stream, line)
+				}
+				// drained, so the command does not block on a full pipe
+				i if i == ALERT_HOOK_MAX_LOG_LINES => {
+					info!("#[alerthook] {} {}: ...", name, stream)
+				}
+				_ => {}
+			}
+		}
+	})
+}
+
+/// Runs the command until it exits or the timeout, blocking.
+fn run_hook(name: &str, command: &str, env: &[(String, String)], timeout: Duration) {
+	info!("#[alerthook] {}: {}", name, command);
+	let time = Instant::now();
+	let mut child = match Command::new("sh")
+		.arg("-c")
+		.arg(command)
+		.envs(env.iter().map(|(k, v)| (k, v)))
+		.stdin(Stdio::null())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.process_group(0)
+		.spawn()
+	{
+		Ok(a) => a,
+		Err(e) => {
+			error!("#[alerthook] {}: I can't start {:?}: {}", name, command, e);
+
+			return;
+		}
+	};
+	let readers = [
+		child
+			.stdout
+			.take()
+			.map(|a| spawn_log_reader(name.to_string(), "stdout", a)),
+		child
+			.stderr
+			.take()
+			.map(|a| spawn_log_reader(name.to_string(), "stderr", a)),
+	];
+
+	let status = loop {
+		match child.try_wait() {
+			Ok(Some(status)) => break Some(status),
+			Ok(None) if time.elapsed() >= timeout => {
+				warn!(
+					"#[alerthook] {}: timed out after {:?}, killing",
+					name, timeout
+				);
+				// the whole group, `sh -c` may have children
+				unsafe {
+					libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+				}
+				break child.wait().ok();
+			}
+			Ok(None) => std::thread::sleep(Duration::from_millis(50)),
+			Err(e) => {
+				error!("#[alerthook] {}: {}", name, e);
+				break None;
+			}
+		}
+	};
+	let deadline = Instant::now() + ALERT_HOOK_READER_TIMEOUT;
+	for reader in readers.into_iter().flatten() {
+		while !reader.is_finished() && Instant::now() < deadline {
+			std::thread::sleep(Duration::from_millis(10));
+		}
+		match reader.is_finished() {
+			true => {
+				let _e = reader.join();
+			}
+			false => trace!("#[alerthook] {}: the output is still open", name),
+		}
+	}
+
+	match status {
+		Some(status) if status.success() => {
+			info!("#[alerthook] {}: done in {:?}", name, time.elapsed())
+		}
+		Some(status) => warn!("#[alerthook] {}: {}", name, status),
+		None => {}
+	}
+}
+
+fn hook_env(
+	kind: AlertHookKind,
+	config: &AlertRuleConfig,
+	event: &AlertEvent,
+) -> Vec<(String, String)> {
+	let condition = config.get_rule().get_condition();
+	let state = &event.state;
+
+	[
+		("EVENT", kind.as_str().to_string()),
+		("NAME", state.name.clone()),
+		("SEVERITY", state.severity.as_str().to_string()),
+		("RULE", state.rule.clone()),
+		("SENSOR", state.sensor.clone().unwrap_or_default()),
+		(
+			"VALUE",
+			state.value.map(|a| a.to_string()).unwrap_or_default(),
+		),
+		("OP", condition.get_op().as_str().to_string()),
+		("THRESHOLD", condition.get_value().to_string()),
+	]
+	.into_iter()
+	.map(|(k, v)| (format!("{}{}", ALERT_HOOK_ENV_PREFIX, k), v))
+	.collect()
+}
+
+/// Runs the hooks of the rules on the transitions of [`ALERTS`], in the
+/// background. The indexes of `rules` are the ones of the alert engine.
+pub fn spawn_alert_hooks(rules: &[AlertRuleConfig]) {
+	if rules
+		.iter()
+		.all(|a| a.get_on_enter().is_none() && a.get_on_exit().is_none())
+	{
+		return;
+	}
+
+	let mut hooks: Vec<AlertHooks> = rules.iter().cloned().map(AlertHooks::new).collect();
+	let recv = ALERTS.subscribe();
+	std::thread::spawn(move || {
+		while let Ok(event) = recv.recv_blocking() {
+			let Some(hooks) = hooks.get_mut(event.index) else {
+				continue;
+			};
+			let kind = match event.kind {
+				AlertEventKind::Fired => AlertHookKind::Enter,
+				AlertEventKind::Cleared => AlertHookKind::Exit,
+			};
+			hooks.run(kind, &event);
+		}
+	});
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::core::alert::AlertSeverity;
+	use crate::core::alert::AlertState;
+	use crate::core::alert::AlertStatus;
+
+	fn event(kind: AlertEventKind) -> AlertEvent {
+		AlertEvent {
+			kind,
+			index: 0,
+			state: AlertState {
+				name: "hot".to_string(),
+				rule: "x > 90".to_string(),
+				severity: AlertSeverity::Warning,
+				status: AlertStatus::Firing,
+				sensor: Some("test/chip/x".to_string()),
+				value: Some(95.0),
+				since: None,
+				duration: Duration::ZERO,
+			},
+		}
+	}
+
+	#[test]
+	fn paired_exit_after_enter() {
+		let path = std::env::temp_dir().join(format!("alerthook-{}", std::process::id()));
+		let config: AlertRuleConfig = toml::from_str(&format!(
+			r#"
+			rule = "x > 90"
+			on_enter = "sleep 0.3; echo $MACHINEPMMETER_ALERT_EVENT >> '{0}'"
+			on_exit = "echo $MACHINEPMMETER_ALERT_EVENT >> '{0}'"
+			hook_interval_ms = 60000
+			"#,
+			path.display()
+		))
+		.unwrap();
+		let mut hooks = AlertHooks::new(config);
+
+		hooks.run(AlertHookKind::Enter, &event(AlertEventKind::Fired));
+		// still running and rate limited, but the exit undoes the enter
+		hooks.run(AlertHookKind::Exit, &event(AlertEventKind::Cleared));
+		// an enter of the next firing is rate limited
+		assert_eq!(hooks.next(AlertHookKind::Enter), None);
+		hooks.thread.take().unwrap().join().unwrap();
+
+		let output = std::fs::read_to_string(&path);
+		let _e = std::fs::remove_file(&path);
+		assert_eq!(output.unwrap(), "enter\nexit\n");
+	}
+
+	#[test]
+	fn open_output_after_exit() {
+		let time = Instant::now();
+		// the background child keeps stdout open
+		run_hook(
+			"test",
+			"sleep 3 & echo started",
+			&[],
+			Duration::from_secs(5),
+		);
+		assert!(time.elapsed() < ALERT_HOOK_READER_TIMEOUT + Duration::from_millis(1500));
+	}
+}
@@ -0,0 +1,517 @@
+use log::error;
+use log::info;
+use log::warn;
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::mpsc;
+
+/// `[[cpu_preset]]` of AppConfig, switched from the tray and with Shift+F10.
+///
+/// ```toml
+/// [[cpu_preset]]
+/// name = "battery"
+/// governor = "powersave"  # scaling_governor
+/// epp = "power"           # energy_performance_preference
+/// boost = false
+/// policies = [0, 1]       # policy0, policy1, all if not set
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+pub struct CpuPresetConfig {
+	name: String,
+	governor: Option<String>,
+	epp: Option<String>,
+	boost: Option<bool>,
+	policies: Option<Vec<u32>>,
+}
+
+impl CpuPresetConfig {
+	#[inline]
+	pub fn get_name(&self) -> &str {
+		&self.name
+	}
+
+	#[inline]
+	pub fn get_governor(&self) -> Option<&str> {
+		self.governor.as_deref()
+	}
+
+	#[inline]
+	pub fn get_epp(&self) -> Option<&str> {
+		self.epp.as_deref()
+	}
+
+	#[inline]
+	pub const fn get_boost(&self) -> Option<bool> {
+		self.boost
+	}
+
+	#[inline]
+	pub fn is_policy(&self, policy: u32) -> bool {
+		self.policies.as_ref().is_none_or(|a| a.contains(&policy))
+	}
+}
+
+/// `<sys>/devices/system/cpu/cpufreq/policy*`.
+pub fn cpufreq_policies(sys_root: impl AsRef<Path>) -> Vec<(u32, PathBuf)> {
+	let Ok(dir) = fs::read_dir(sys_root.as_ref().join("devices/system/cpu/cpufreq")) else {
+		return Vec::new();
+	};
+
+	let mut result: Vec<(u32, PathBuf)> = dir
+		.filter_map(|a| a.ok())
+		.filter_map(|a| {
+			let policy = a
+				.file_name()
+				.to_str()?
+				.strip_prefix("policy")?
+				.parse()
+				.ok()?;
+
+			Some((policy, a.path()))
+		})
+		.collect();
+	result.sort_unstable_by_key(|a| a.0);
+
+	result
+}
+
+/// Writes the cpufreq attributes.
+pub trait CpuFreqWriter: Send {
+	fn write_attr(&mut self, path: &Path, value: &str) -> io::Result<()>;
+
+	/// The writer restores the original values itself when the dock exits
+	/// (the privileged helper), nothing has to be restored here.
+	#[inline]
+	fn restores_on_exit(&self) -> bool {
+		false
+	}
+}
+
+impl<W: CpuFreqWriter + ?Sized> CpuFreqWriter for Box<W> {
+	#[inline]
+	fn write_attr(&mut self, path: &Path, value: &str) -> io::Result<()> {
+		(**self).write_attr(path, value)
+	}
+
+	#[inline]
+	fn restores_on_exit(&self) -> bool {
+		(**self).restores_on_exit()
+	}
+}
+
+/// Writes sysfs directly, the attributes must be writable by the user
+/// (otherwise through the privileged helper).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SysfsCpuFreqWriter;
+
+impl CpuFreqWriter for SysfsCpuFreqWriter {
+	#[inline]
+	fn write_attr(&mut self, path: &Path, value: &str) -> io::Result<()> {
+		fs::write(path, format!("{}\n", value))
+	}
+}
+
+/// Original values of the changed attributes, shared with the panic hook.
+static RESTORE_CPUFREQ: Mutex<Vec<(PathBuf, String)>> = Mutex::new(Vec::new());
+
+/// Writes back the original governor, EPP and boost values.
+/// On exit, on a panic and on SIGINT/SIGTERM.
+pub fn restore_cpufreq() {
+	let restore = match RESTORE_CPUFREQ.lock() {
+		Ok(mut a) => std::mem::take(&mut *a),
+		Err(e) => std::mem::take(&mut *e.into_inner()),
+	};
+	restore_cpufreq_values(&mut SysfsCpuFreqWriter, restore);
+}
+
+/// The governors first, then the rest in the order of the changes
+/// (intel_pstate rejects EPP writes with `EBUSY` while the governor is
+/// `performance`).
+fn restore_cpufreq_values(writer: &mut impl CpuFreqWriter, mut restore: Vec<(PathBuf, String)>) {
+	restore.sort_by_key(|(path, _)| !path.ends_with("scaling_governor"));
+	for (path, value) in restore {
+		match writer.write_attr(&path, &value) {
+			Ok(()) => info!("#[cpufreq] {:?}: {}", path, value),
+			Err(e) => error!("#[cpufreq] I can't restore {:?}: {}", path, e),
+		}
+	}
+}
+
+/// Applies the presets, remembers the original values.
+pub struct CpuFreqControl<W: CpuFreqWriter> {
+	sys_root: PathBuf,
+	writer: W,
+	/// Original values if the writer does not restore them itself.
+	original: Vec<(PathBuf, String)>,
+}
+
+impl<W: CpuFreqWriter> CpuFreqControl<W> {
+	pub fn new(sys_root: impl Into<PathBuf>, writer: W) -> Self {
+		Self {
+			sys_root: sys_root.into(),
+			writer,
+			original: Vec::new(),
+		}
+	}
+
+	fn write(&mut self, path: PathBuf, value: &str) {
+		let Ok(current) = fs::read_to_string(&path) else {
+			warn!("#[cpufreq] {:?} does not exist", path);
+			return;
+		};
+		let current = current.trim();
+		if current == value {
+			return;
+		}
+
+		if !self.original.iter().any(|(a, _)| *a == path) {
+			self.original.push((path.clone(), current.to_string()));
+			if !self.writer.restores_on_exit()
+				&& let Ok(mut restore) = RESTORE_CPUFREQ.lock()
+			{
+				restore.push((path.clone(), current.to_string()));
+			}
+		}
+		match self.writer.write_attr(&path, value) {
+			Ok(()) => info!("#[cpufreq] {:?}: {}", path, value),
+			Err(e) => error!("#[cpufreq] {:?}: {}: {}", path, value, e),
+		}
+	}
+
+	/// Governor and EPP, then boost of the selected policies. The EPP is
+	/// written before a switch to `performance` (see
+	/// [`restore_cpufreq_values`]). Boost is per policy (amd-pstate), global
+	/// (acpi-cpufreq) or `no_turbo` (intel_pstate).
+	pub fn apply(&mut self, preset: &CpuPresetConfig) {
+		let mut is_boost_done = false;
+		for (policy, dir) in cpufreq_policies(&self.sys_root) {
+			if !preset.is_policy(policy) {
+				continue;
+			}
+
+			let governor = preset.get_governor().filter(|governor| {
+				let available = fs::read_to_string(dir.join("scaling_available_governors"));
+				if available.is_ok_and(|a| !a.split_whitespace().any(|a| a == *governor)) {
+					warn!(
+						"#[cpufreq] policy{}: governor {:?} is not available",
+						policy, governor
+					);
+
+					return false;
+				}
+
+				true
+			});
+			let is_epp_first = governor == Some("performance");
+			if let (Some(epp), true) = (preset.get_epp(), is_epp_first) {
+				self.write(dir.join("energy_performance_preference"), epp);
+			}
+			if let Some(governor) = governor {
+				self.write(dir.join("scaling_governor"), governor);
+			}
+			if let (Some(epp), false) = (preset.get_epp(), is_epp_first) {
+				self.write(dir.join("energy_performance_preference"), epp);
+			}
+			if let Some(boost) = preset.get_boost() {
+				let path = dir.join("boost");
+				if path.is_file() {
+					self.write(path, if boost { "1" } else { "0" });
+					is_boost_done = true;
+				}
+			}
+		}
+
+		if let (Some(boost), false) = (preset.get_boost(), is_boost_done) {
+			let cpu = self.sys_root.join("devices/system/cpu");
+			let boost_path = cpu.join("cpufreq/boost");
+			let no_turbo_path = cpu.join("intel_pstate/no_turbo");
+			if boost_path.is_file() {
+				self.write(boost_path, if boost { "1" } else { "0" });
+			} else if no_turbo_path.is_file() {
+				self.write(no_turbo_path, if boost { "0" } else { "1" });
+			} else {
+				warn!("#[cpufreq] boost can not be switched on this platform");
+			}
+		}
+	}
+
+	/// Writes back the original values.
+	pub fn restore(&mut self) {
+		let original = std::mem::take(&mut self.original);
+		if let Ok(mut restore) = RESTORE_CPUFREQ.lock() {
+			restore.retain(|(a, _)| !original.iter().any(|(b, _)| a == b));
+		}
+		restore_cpufreq_values(&mut self.writer, original);
+	}
+}
+
+impl<W: CpuFreqWriter> Drop for CpuFreqControl<W> {
+	#[inline]
+	fn drop(&mut self) {
+		if !self.original.is_empty() {
+			self.restore();
+		}
+	}
+}
+
+/// Switches the presets from the GUI (tray, hotkey).
+#[derive(Debug, Clone)]
+pub struct CpuPresetControl(mpsc::Sender<()>);
+
+impl CpuPresetControl {
+	/// Cycles through the presets and the original values.
+	#[inline]
+	pub fn next_preset(&self) {
+		let _e = self.0.send(());
+	}
+}
+
+/// The writer is created on the first switch (the helper may ask for a
+/// password), sends the name of the active preset, `None` for the original
+/// values.
+pub fn spawn_cpu_preset_control<W: CpuFreqWriter + 'static>(
+	sys_root: impl Into<PathBuf>,
+	presets: Vec<CpuPresetConfig>,
+	make_writer: impl FnOnce() -> W + Send + 'static,
+) -> (CpuPresetControl, async_channel::Receiver<Option<String>>) {
+	let sys_root = sys_root.into();
+	let (control_sender, control_recv) = mpsc::channel();
+	let (sender, recv) = async_channel::bounded(4);
+	std::thread::spawn(move || {
+		if presets.is_empty() {
+			return;
+		}
+		if control_recv.recv().is_err() {
+			return;
+		}
+		let mut control = CpuFreqControl::new(sys_root, make_writer());
+
+		// `presets.len()` - the original values
+		let mut index = 0;
+		loop {
+			let name = match presets.get(index) {
+				Some(preset) => {
+					info!("#[cpufreq] Switch to {:?}", preset.get_name());
+					// the unset fields of a preset keep the original values
+					control.restore();
+					control.apply(preset);
+
+					Some(preset.get_name().to_string())
+				}
+				None => {
+					info!("#[cpufreq] Restore the original values");
+					control.restore();
+
+					None
+				}
+			};
+			if sender.send_blocking(name).is_err() {
+				return;
+			}
+
+			if control_recv.recv().is_err() {
+				return;
+			}
+			index = (index + 1) % (presets.len() + 1);
+		}
+	});
+
+	(CpuPresetControl(control_sender), recv)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// Writes the fake sysfs tree, logs the writes.
+	#[derive(Debug, Default)]
+	struct LogWriter(Vec<(String, String)>);
+
+	impl CpuFreqWriter for LogWriter {
+		fn write_attr(&mut self, path: &Path, value: &str) -> io::Result<()> {
+			let name = path.components().rev().take(2).collect::<Vec<_>>();
+			self.0.push((
+				format!(
+					"{}/{}",
+					name[1].as_os_str().to_string_lossy(),
+					name[0].as_os_str().to_string_lossy()
+				),
+				value.to_string(),
+			));
+
+			fs::write(path, format!("{}\n", value))
+		}
+	}
+
+	/// `policies` with `powersave`, `balance_performance`.
+	fn fake_sysfs(name: &str, policies: u32) -> PathBuf {
+		let root = std::env::temp_dir().join(format!("cpufreq-{}-{}", name, std::process::id()));
+		let _e = fs::remove_dir_all(&root);
+		for policy in 0..policies {
+			let dir = root.join(format!("devices/system/cpu/cpufreq/policy{}", policy));
+			fs::create_dir_all(&dir).unwrap();
+			for (name, value) in [
+				("scaling_governor", "powersave"),
+				("scaling_available_governors", "performance powersave"),
+				("energy_performance_preference", "balance_performance"),
+			] {
+				fs::write(dir.join(name), format!("{}\n", value)).unwrap();
+			}
+		}
+
+		root
+	}
+
+	fn read(root: &Path, path: &str) -> String {
+		fs::read_to_string(root.join("devices/system/cpu").join(path))
+			.unwrap()
+			.trim()
+			.to_string()
+	}
+
+	fn preset(governor: Option<&str>, epp: Option<&str>, boost: Option<bool>) -> CpuPresetConfig {
+		CpuPresetConfig {
+			name: "test".into(),
+			governor: governor.map(Into::into),
+			epp: epp.map(Into::into),
+			boost,
+			policies: None,
+		}
+	}
+
+	#[test]
+	fn policies() {
+		let root = fake_sysfs("policies", 3);
+		assert_eq!(
+			cpufreq_policies(&root)
+				.iter()
+				.map(|a| a.0)
+				.collect::<Vec<_>>(),
+			[0, 1, 2]
+		);
+
+		let mut control = CpuFreqControl::new(&root, LogWriter::default());
+		control.apply(&CpuPresetConfig {
+			policies: Some(vec![0, 2]),
+			..preset(Some("performance"), None, None)
+		});
+		assert_eq!(
+			read(&root, "cpufreq/policy0/scaling_governor"),
+			"performance"
+		);
+		assert_eq!(read(&root, "cpufreq/policy1/scaling_governor"), "powersave");
+		assert_eq!(
+			read(&root, "cpufreq/policy2/scaling_governor"),
+			"performance"
+		);
+
+		drop(control);
+		assert_eq!(read(&root, "cpufreq/policy0/scaling_governor"), "powersave");
+		assert_eq!(read(&root, "cpufreq/policy2/scaling_governor"), "powersave");
+
+		let _e = fs::remove_dir_all(&root);
+	}
+
+	#[test]
+	fn unavailable_governor() {
+		let root = fake_sysfs("governor", 1);
+		let mut control = CpuFreqControl::new(&root, LogWriter::default());
+		control.apply(&preset(Some("schedutil"), Some("power"), None));
+		assert_eq!(
+			control.writer.0,
+			[(
+				"policy0/energy_performance_preference".into(),
+				"power".into()
+			)]
+		);
+		assert_eq!(read(&root, "cpufreq/policy0/scaling_governor"), "powersave");
+
+		let _e = fs::remove_dir_all(&root);
+	}
+
+	#[test]
+	fn boost() {
+		let root = fake_sysfs("boost", 2);
+		let cpu = root.join("devices/system/cpu");
+		fs::create_dir_all(cpu.join("intel_pstate")).unwrap();
+		fs::write(cpu.join("intel_pstate/no_turbo"), "0\n").unwrap();
+
+		// intel_pstate
+		let mut control = CpuFreqControl::new(&root, LogWriter::default());
+		control.apply(&preset(None, None, Some(false)));
+		assert_eq!(read(&root, "intel_pstate/no_turbo"), "1");
+		control.restore();
+		assert_eq!(read(&root, "intel_pstate/no_turbo"), "0");
+
+		// acpi-cpufreq
+		fs::write(cpu.join("cpufreq/boost"), "1\n").unwrap();
+		control.apply(&preset(None, None, Some(false)));
+		assert_eq!(read(&root, "cpufreq/boost"), "0");
+		assert_eq!(read(&root, "intel_pstate/no_turbo"), "0");
+		control.restore();
+
+		// amd-pstate
+		for policy in ["policy0", "policy1"] {
+			fs::write(cpu.join("cpufreq").join(policy).join("boost"), "1\n").unwrap();
+		}
+		control.writer.0.clear();
+		control.apply(&preset(None, None, Some(false)));
+		assert_eq!(
+			control.writer.0,
+			[
+				("policy0/boost".into(), "0".into()),
+				("policy1/boost".into(), "0".into())
+			]
+		);
+		assert_eq!(read(&root, "cpufreq/boost"), "1");
+
+		drop(control);
+		let _e = fs::remove_dir_all(&root);
+	}
+
+	#[test]
+	fn performance_order() {
+		let root = fake_sysfs("order", 1);
+		let mut control = CpuFreqControl::new(&root, LogWriter::default());
+
+		// no EPP writes while the governor is `performance`
+		control.apply(&preset(Some("performance"), Some("performance"), None));
+		control.restore();
+		assert_eq!(
+			control.writer.0,
+			[
+				(
+					"policy0/energy_performance_preference".into(),
+					"performance".into()
+				),
+				("policy0/scaling_governor".into(), "performance".into()),
+				("policy0/scaling_governor".into(), "powersave".into()),
+				(
+					"policy0/energy_performance_preference".into(),
+					"balance_performance".into()
+				),
+			]
+		);
+
+		control.writer.0.clear();
+		control.apply(&preset(Some("powersave"), Some("power"), None));
+		assert_eq!(
+			control.writer.0,
+			[(
+				"policy0/energy_performance_preference".into(),
+				"power".into()
+			)]
+		);
+
+		drop(control);
+		assert_eq!(
+			read(&root, "cpufreq/policy0/energy_performance_preference"),
+			"balance_performance"
+		);
+		let _e = fs::remove_dir_all(&root);
+	}
+}
@@ -0,0 +1,577 @@
+use log::error;
+use log::info;
+use log::warn;
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::RwLock;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+pub const SYS_PATH: &str = "/sys";
+/// The duty of the curves is updated this often.
+pub const FAN_CURVE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// `pwm*_enable`: the duty is set by us.
+pub const PWM_ENABLE_MANUAL: u8 = 1;
+/// `pwm*_enable`: the chip (or the firmware) controls the fan, most drivers.
+pub const PWM_ENABLE_AUTO: u8 = 2;
+
+/// `[[fan_curve]]` of AppConfig.
+///
+/// ```toml
+/// [[fan_curve]]
+/// chip = "nct6798"       # hwmon `name` of the PWM
+/// pwm = 2                # pwm2
+/// temp_chip = "k10temp"  # the same chip if not set
+/// temp = 1               # temp1_input
+/// points = [[40, 25], [60, 45], [80, 100]] # °C, duty %
+/// hysteresis = 3.0       # °C, 2 if not set
+/// min_duty = 20          # %, 0 if not set
+/// ```
+#[derive(Deserialize, Debug, Clone)]
+pub struct FanCurveConfig {
+	chip: String,
+	pwm: u32,
+	temp_chip: Option<String>,
+	temp: u32,
+	points: Vec<(f64, f64)>,
+	hysteresis: Option<f64>,
+	min_duty: Option<f64>,
+}
+
+impl FanCurveConfig {
+	#[inline]
+	pub fn get_chip(&self) -> &str {
+		&self.chip
+	}
+
+	#[inline]
+	pub const fn get_pwm(&self) -> u32 {
+		self.pwm
+	}
+
+	#[inline]
+	pub fn get_temp_chip(&self) -> &str {
+		self.temp_chip.as_deref().unwrap_or(&self.chip)
+	}
+
+	#[inline]
+	pub const fn get_temp(&self) -> u32 {
+		self.temp
+	}
+
+	#[inline]
+	pub fn get_hysteresis(&self) -> f64 {
+		self.hysteresis.unwrap_or(2.0).max(0.0)
+	}
+
+	#[inline]
+	pub fn get_min_duty(&self) -> f64 {
+		self.min_duty.unwrap_or(0.0).clamp(0.0, 100.0)
+	}
+
+	#[inline]
+	pub fn get_curve(&self) -> FanCurve {
+		FanCurve::new(self.points.clone())
+	}
+}
+
+/// Temperature (°C) to duty (%), linear between the points.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FanCurve(Vec<(f64, f64)>);
+
+impl FanCurve {
+	pub fn new(mut points: Vec<(f64, f64)>) -> Self {
+		points.retain(|(temp, duty)| temp.is_finite() && duty.is_finite());
+		points.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+		Self(points)
+	}
+
+	pub fn duty_at(&self, temp: f64) -> f64 {
+		let (Some(first), Some(last)) = (self.0.first(), self.0.last()) else {
+			// no curve, no risk
+			return 100.0;
+		};
+		let duty = if temp <= first.0 {
+			first.1
+		} else if temp >= last.0 {
+			last.1
+		} else {
+			self.0
+				.windows(2)
+				.find(|a| temp <= a[1].0)
+				.map_or(last.1, |a| {
+					let ((t0, d0), (t1, d1)) = (a[0], a[1]);
+
+					d0 + (d1 - d0) * (temp - t0) / (t1 - t0)
+				})
+		};
+
+		duty.clamp(0.0, 100.0)
+	}
+}
+
+/// A curve with hysteresis and minimum duty.
+#[derive(Debug, Clone)]
+pub struct FanCurveController {
+	curve: FanCurve,
+	hysteresis: f64,
+	min_duty: f64,
+	/// (temperature, duty) of the last change
+	state: Option<(f64, f64)>,
+}
+
+impl FanCurveController {
+	#[inline]
+	pub const fn new(curve: FanCurve, hysteresis: f64, min_duty: f64) -> Self {
+		Self {
+			curve,
+			hysteresis,
+			min_duty,
+			state: None,
+		}
+	}
+
+	/// The duty (%) for `temp`. A rising temperature is followed at once, a
+	/// falling one only after it has dropped by `hysteresis`.
+	pub fn update(&mut self, temp: f64) -> f64 {
+		let duty = match self.state {
+			Some((last_temp, duty)) if temp < last_temp && temp > last_temp - self.hysteresis => {
+				duty
+			}
+			_ => {
+				let duty = self.curve.duty_at(temp);
+				self.state = Some((temp, duty));
+
+				duty
+			}
+		};
+
+		duty.max(self.min_duty)
+	}
+}
+
+/// `0..=100` % to `0..=255`.
+#[inline]
+pub fn duty_to_pwm(duty: f64) -> u8 {
+	(duty.clamp(0.0, 100.0) * 2.55).round() as u8
+}
+
+/// `0..=255` to `0..=100` %.
+#[inline]
+pub fn pwm_to_duty(pwm: u8) -> f64 {
+	pwm as f64 / 2.55
+}
+
+/// Writes `pwm*` and `pwm*_enable` attributes.
+pub trait PwmWriter: Send {
+	fn write_pwm(&mut self, path: &Path, value: u8) -> io::Result<()>;
+
+	/// The writer returns the fans to automatic mode itself when the dock
+	/// exits (the privileged helper), nothing has to be restored here.
+	#[inline]
+	fn restores_on_exit(&self) -> bool {
+		false
+	}
+}
+
+impl<W: PwmWriter + ?Sized> PwmWriter for Box<W> {
+	#[inline]
+	fn write_pwm(&mut self, path: &Path, value: u8) -> io::Result<()> {
+		(**self).write_pwm(path, value)
+	}
+
+	#[inline]
+	fn restores_on_exit(&self) -> bool {
+		(**self).restores_on_exit()
+	}
+}
+
+/// Writes sysfs directly, the attributes must be writable by the user
+/// (otherwise through the privileged helper).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SysfsPwmWriter;
+
+impl PwmWriter for SysfsPwmWriter {
+	#[inline]
+	fn write_pwm(&mut self, path: &Path, value: u8) -> io::Result<()> {
+		fs::write(path, format!("{}\n", value))
+	}
+}
+
+/// `<sys>/class/hwmon/hwmon*` with the `name` of the chip.
+pub fn hwmon_dirs(sys_root: impl AsRef<Path>) -> Vec<(String, PathBuf)> {
+	let Ok(dir) = fs::read_dir(sys_root.as_ref().join("class/hwmon")) else {
+		return Vec::new();
+	};
+
+	let mut result: Vec<(String, PathBuf)> = dir
+		.filter_map(|a| a.ok())
+		.filter_map(|a| {
+			let path = a.path();
+			let name = fs::read_to_string(path.join("name")).ok()?;
+
+			Some((name.trim().to_string(), path))
+		})
+		.collect();
+	result.sort_by(|a, b| a.1.cmp(&b.1));
+
+	result
+}
+
+pub fn find_hwmon_dir(sys_root: impl AsRef<Path>, chip: &str) -> Option<PathBuf> {
+	hwmon_dirs(sys_root)
+		.into_iter()
+		.find(|(name, _)| name == chip)
+		.map(|(_, path)| path)
+}
+
+/// A numeric sysfs attribute.
+pub fn read_sysfs_value<T: std::str::FromStr>(path: impl AsRef<Path>) -> Option<T> {
+	fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+#[derive(Debug)]
+struct FanCurveChannel {
+	pwm_path: PathBuf,
+	enable_path: PathBuf,
+	temp_path: PathBuf,
+	controller: FanCurveController,
+	/// The restore value is recorded (the first tick).
+	is_taken: bool,
+}
+
+/// `pwm*_enable` values to restore on exit, shared with the panic hook.
+static RESTORE_PWM_ENABLE: Mutex<Vec<(PathBuf, u8)>> = Mutex::new(Vec::new());
+static FAN_CONTROL_RELEASED: AtomicBool = AtomicBool::new(false);
+
+/// Returns the taken PWM channels to automatic mode, the fan control stops.
+/// On exit, on a panic and on SIGINT/SIGTERM.
+pub fn restore_auto_fan_control() {
+	FAN_CONTROL_RELEASED.store(true, Ordering::SeqCst);
+
+	let restore = match RESTORE_PWM_ENABLE.lock() {
+		Ok(mut a) => std::mem::take(&mut *a),
+		Err(e) => std::mem::take(&mut *e.into_inner()),
+	};
+	let mut writer = SysfsPwmWriter;
+	for (path, value) in restore {
+		match writer.write_pwm(&path, value) {
+			Ok(()) => info!("#[fancurve] {:?}: {}", path, value),
+			Err(e) => error!("#[fancurve] I can't restore {:?}: {}", path, e),
+		}
+	}
+}
+
+/// The configured curves of the existing channels.
+pub struct FanControl<W: PwmWriter> {
+	channels: Vec<FanCurveChannel>,
+	writer: W,
+}
+
+impl<W: PwmWriter> FanControl<W> {
+	/// Channels of missing chips/attributes are skipped (with a warning).
+	pub fn new(sys_root: impl AsRef<Path>, configs: &[FanCurveConfig], writer: W) -> Self {
+		let sys_root = sys_root.as_ref();
+		let channels = configs
+			.iter()
+			.filter_map(|config| {
+				let Some(hwmon) = find_hwmon_dir(sys_root, config.get_chip()) else {
+					warn!("#[fancurve] chip {:?} was not found", config.get_chip());
+					return None;
+				};
+				let Some(temp_hwmon) = find_hwmon_dir(sys_root, config.get_temp_chip()) else {
+					warn!(
+						"#[fancurve] chip {:?} was not found",
+						config.get_temp_chip()
+					);
+					return None;
+				};
+				let pwm_path = hwmon.join(format!("pwm{}", config.get_pwm()));
+				let temp_path = temp_hwmon.join(format!("temp{}_input", config.get_temp()));
+				if !pwm_path.is_file() || !temp_path.is_file() {
+					warn!(
+						"#[fancurve] {:?} or {:?} does not exist",
+						pwm_path, temp_path
+					);
+					return None;
+				}
+
+				Some(FanCurveChannel {
+					enable_path: hwmon.join(format!("pwm{}_enable", config.get_pwm())),
+					pwm_path,
+					temp_path,
+					controller: FanCurveController::new(
+						config.get_curve(),
+						config.get_hysteresis(),
+						config.get_min_duty(),
+					),
+					is_taken: false,
+				})
+			})
+			.collect();
+
+		Self { channels, writer }
+	}
+
+	/// `pwm*` paths of the controlled channels.
+	pub fn pwm_paths(&self) -> impl Iterator<Item = &Path> {
+		self.channels.iter().map(|a| a.pwm_path.as_path())
+	}
+
+	/// Switches the channels to manual mode (once) and writes the duty of the
+	/// current temperatures. A channel without a temperature runs at full
+	/// speed.
+	pub fn tick(&mut self) {
+		if FAN_CONTROL_RELEASED.load(Ordering::SeqCst) {
+			return;
+		}
+
+		for channel in &mut self.channels {
+			let enable = read_sysfs_value::<u8>(&channel.enable_path);
+			if !channel.is_taken && !self.writer.restores_on_exit() {
+				let Ok(mut restore) = RESTORE_PWM_ENABLE.lock() else {
+					return;
+				};
+				if !restore.iter().any(|(a, _)| *a == channel.enable_path) {
+					// full speed (0) and manual (also left by a crashed run)
+					// are not safe to be left in
+					let auto = enable
+						.filter(|a| *a >= PWM_ENABLE_AUTO)
+						.unwrap_or(PWM_ENABLE_AUTO);
+					restore.push((channel.enable_path.clone(), auto));
+				}
+			}
+			channel.is_taken = true;
+			if enable != Some(PWM_ENABLE_MANUAL) {
+				if let Err(e) = self
+					.writer
+					.write_pwm(&channel.enable_path, PWM_ENABLE_MANUAL)
+				{
+					error!("#[fancurve] {:?}: {}", channel.enable_path, e);
+					continue;
+				}
+				info!("#[fancurve] {:?}: manual", channel.enable_path);
+			}
+
+			let duty = match read_sysfs_value::<f64>(&channel.temp_path) {
+				Some(millidegree) => channel.controller.update(millidegree / 1000.0),
+				None => 100.0,
+			};
+			if let Err(e) = self.writer.write_pwm(&channel.pwm_path, duty_to_pwm(duty)) {
+				error!("#[fancurve] {:?}: {}", channel.pwm_path, e);
+			}
+		}
+	}
+}
+
+impl<W: PwmWriter> Drop for FanControl<W> {
+	#[inline]
+	fn drop(&mut self) {
+		if !self.channels.is_empty() {
+			restore_auto_fan_control();
+		}
+	}
+}
+
+/// `pwm*` paths of the running curves, for the duty of the fans page.
+#[derive(Debug, Clone, Default)]
+pub struct FanCurvePwms(Arc<RwLock<Vec<PathBuf>>>);
+
+impl FanCurvePwms {
+	pub fn contains(&self, path: &Path) -> bool {
+		match self.0.read() {
+			Ok(a) => a.iter().any(|a| a == path),
+			Err(e) => e.into_inner().iter().any(|a| a == path),
+		}
+	}
+}
+
+/// Runs the curves in their own thread, with or without the fans page. The
+/// writer is created in the thread (the helper may ask for a password).
+pub fn spawn_fan_control<W: PwmWriter + 'static>(
+	sys_root: impl Into<PathBuf>,
+	configs: Vec<FanCurveConfig>,
+	interval: Duration,
+	make_writer: impl FnOnce() -> W + Send + 'static,
+) -> FanCurvePwms {
+	let pwms = FanCurvePwms::default();
+	if configs.is_empty() {
+		return pwms;
+	}
+
+	let sys_root = sys_root.into();
+	let result = pwms.clone();
+	std::thread::spawn(move || {
+		let mut control = FanControl::new(sys_root, &configs, make_writer());
+		info!(
+			"#[fancurve] {} of {} fan curves are active",
+			control.pwm_paths().count(),
+			configs.len()
+		);
+		if control.channels.is_empty() {
+			return;
+		}
+		match pwms.0.write() {
+			Ok(mut a) => *a = control.pwm_paths().map(Path::to_path_buf).collect(),
+			Err(e) => *e.into_inner() = control.pwm_paths().map(Path::to_path_buf).collect(),
+		}
+
+		while !FAN_CONTROL_RELEASED.load(Ordering::SeqCst) {
+			control.tick();
+
+			std::thread::sleep(interval);
+		}
+	});
+
+	result
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A hwmon `name` with its attributes.
+	fn write_hwmon(sys: &Path, hwmon: &str, attrs: &[(&str, &str)]) {
+		let dir = sys.join("class/hwmon").join(hwmon);
+		fs::create_dir_all(&dir).unwrap();
+		for (name, value) in attrs {
+			fs::write(dir.join(name), format!("{}\n", value)).unwrap();
+		}
+	}
+
+	fn read_attr(sys: &Path, hwmon: &str, name: &str) -> Option<u8> {
+		read_sysfs_value(sys.join("class/hwmon").join(hwmon).join(name))
+	}
+
+	#[test]
+	fn curve() {
+		let curve = FanCurve::new(vec![
+			(80.0, 100.0),
+			(40.0, 25.0),
+			(60.0, 45.0),
+			(f64::NAN, 0.0),
+		]);
+		assert_eq!(
+			curve,
+			FanCurve(vec![(40.0, 25.0), (60.0, 45.0), (80.0, 100.0)])
+		);
+		assert_eq!(curve.duty_at(20.0), 25.0);
+		assert_eq!(curve.duty_at(40.0), 25.0);
+		assert_eq!(curve.duty_at(50.0), 35.0);
+		assert_eq!(curve.duty_at(70.0), 72.5);
+		assert_eq!(curve.duty_at(95.0), 100.0);
+		assert_eq!(FanCurve::new(vec![(40.0, 150.0)]).duty_at(50.0), 100.0);
+		assert_eq!(FanCurve::new(Vec::new()).duty_at(30.0), 100.0);
+
+		assert_eq!(duty_to_pwm(0.0), 0);
+		assert_eq!(duty_to_pwm(100.0), 255);
+		assert_eq!(duty_to_pwm(120.0), 255);
+		assert_eq!(pwm_to_duty(255), 100.0);
+	}
+
+	#[test]
+	fn hysteresis() {
+		let curve = FanCurve::new(vec![(40.0, 20.0), (80.0, 100.0)]);
+		let mut controller = FanCurveController::new(curve, 3.0, 30.0);
+		// min_duty
+		assert_eq!(controller.update(40.0), 30.0);
+		assert_eq!(controller.update(60.0), 60.0);
+		// rising at once
+		assert_eq!(controller.update(61.0), 62.0);
+		// falling by less than 3 °C keeps the duty
+		assert_eq!(controller.update(59.0), 62.0);
+		assert_eq!(controller.update(58.5), 62.0);
+		assert_eq!(controller.update(58.0), 56.0);
+		assert_eq!(controller.update(57.0), 56.0);
+		assert_eq!(controller.update(59.0), 58.0);
+	}
+
+	#[test]
+	fn pwm_enable() {
+		let sys =
+			std::env::temp_dir().join(format!("machinepmmeter-fancurve-{}", std::process::id()));
+		let _e = fs::remove_dir_all(&sys);
+		// pwm2 was left in manual mode (a crashed run)
+		write_hwmon(
+			&sys,
+			"hwmon0",
+			&[
+				("name", "nct6798"),
+				("pwm1", "80"),
+				("pwm1_enable", "5"),
+				("pwm2", "80"),
+				("pwm2_enable", "1"),
+			],
+		);
+		write_hwmon(
+			&sys,
+			"hwmon1",
+			&[("name", "k10temp"), ("temp1_input", "50000")],
+		);
+		let configs: Vec<FanCurveConfig> = [
+			"chip = \"nct6798\"\npwm = 1\ntemp_chip = \"k10temp\"\ntemp = 1\npoints = [[40, 20], [80, 100]]",
+			"chip = \"nct6798\"\npwm = 2\ntemp_chip = \"k10temp\"\ntemp = 1\npoints = [[40, 20], [80, 100]]",
+			// skipped
+			"chip = \"nct6798\"\npwm = 3\ntemp_chip = \"k10temp\"\ntemp = 1\npoints = []",
+			"chip = \"it8686\"\npwm = 1\ntemp = 1\npoints = []",
+		]
+		.into_iter()
+		.map(|a| toml::from_str(a).unwrap())
+		.collect();
+
+		// on drop
+		let mut control = FanControl::new(&sys, &configs, SysfsPwmWriter);
+		assert_eq!(control.pwm_paths().count(), 2);
+		control.tick();
+		assert_eq!(
+			read_attr(&sys, "hwmon0", "pwm1_enable"),
+			Some(PWM_ENABLE_MANUAL)
+		);
+		assert_eq!(
+			read_attr(&sys, "hwmon0", "pwm2_enable"),
+			Some(PWM_ENABLE_MANUAL)
+		);
+		// 40% at 50 °C
+		assert_eq!(read_attr(&sys, "hwmon0", "pwm1"), Some(102));
+		write_hwmon(&sys, "hwmon1", &[("temp1_input", "70000")]);
+		control.tick();
+		assert_eq!(read_attr(&sys, "hwmon0", "pwm1"), Some(204));
+		drop(control);
+		assert_eq!(read_attr(&sys, "hwmon0", "pwm1_enable"), Some(5));
+		assert_eq!(
+			read_attr(&sys, "hwmon0", "pwm2_enable"),
+			Some(PWM_ENABLE_AUTO)
+		);
+		assert_eq!(read_attr(&sys, "hwmon0", "pwm1"), Some(204));
+
+		// on restore, the control stops
+		FAN_CONTROL_RELEASED.store(false, Ordering::SeqCst);
+		let mut control = FanControl::new(&sys, &configs, SysfsPwmWriter);
+		control.tick();
+		assert_eq!(
+			read_attr(&sys, "hwmon0", "pwm1_enable"),
+			Some(PWM_ENABLE_MANUAL)
+		);
+		restore_auto_fan_control();
+		assert_eq!(read_attr(&sys, "hwmon0", "pwm1_enable"), Some(5));
+		assert_eq!(
+			read_attr(&sys, "hwmon0", "pwm2_enable"),
+			Some(PWM_ENABLE_AUTO)
+		);
+		write_hwmon(&sys, "hwmon1", &[("temp1_input", "40000")]);
+		control.tick();
+		assert_eq!(read_attr(&sys, "hwmon0", "pwm1_enable"), Some(5));
+		assert_eq!(read_attr(&sys, "hwmon0", "pwm1"), Some(204));
+		drop(control);
+
+		let _e = fs::remove_dir_all(&sys);
+	}
+}
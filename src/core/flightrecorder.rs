@@ -0,0 +1,329 @@
+//! The last minutes of all sensors in memory, written to a recording when an
+//! alert fires or on the `save last minutes` hotkey, for the stutters and
+//! overheats that are hard to reproduce.
+
+use crate::core::alert::ALERTS;
+use crate::core::alert::AlertEventKind;
+use crate::core::alert::AlertSeverity;
+use crate::core::recording::RecordingFormat;
+use crate::core::recording::RecordingHeader;
+use crate::core::recording::RecordingSensor;
+use crate::core::recording::RecordingWriter;
+use crate::core::recording::recording_path;
+use crate::core::sensorregistry::SENSOR_REGISTRY;
+use crate::core::sensorregistry::SensorEntry;
+use crate::core::steamgame::CurrentSteamGame;
+use log::error;
+use log::info;
+use log::trace;
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::mpsc;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+/// `[recording.flight_recorder]` of AppConfig.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct FlightRecorderConfig {
+	enabled: Option<bool>,
+	minutes: Option<f64>,
+	interval_ms: Option<u64>,
+	on_alert: Option<AlertSeverity>,
+}
+
+impl FlightRecorderConfig {
+	#[inline]
+	pub fn is_enabled(&self) -> bool {
+		self.enabled.unwrap_or(true)
+	}
+
+	/// Kept in memory.
+	#[inline]
+	pub fn get_time(&self) -> Duration {
+		Duration::from_secs_f64(self.minutes.unwrap_or(5.0).clamp(0.1, 120.0) * 60.0)
+	}
+
+	#[inline]
+	pub fn get_interval(&self) -> Duration {
+		Duration::from_millis(self.interval_ms.unwrap_or(1000).max(50))
+	}
+
+	/// The minimal severity of the alerts that dump the ring.
+	#[inline]
+	pub fn get_on_alert(&self) -> AlertSeverity {
+		self.on_alert.unwrap_or(AlertSeverity::Critical)
+	}
+}
+
+/// The values of a tick by the registry index, `NaN` - no new sample.
+#[derive(Debug)]
+struct FlightFrame {
+	time: SystemTime,
+	values: Box<[f32]>,
+}
+
+#[derive(Debug)]
+struct FlightRing {
+	frames: VecDeque<FlightFrame>,
+	capacity: usize,
+	/// The last seen `seq` of the entries.
+	seqs: Vec<u64>,
+}
+
+impl FlightRing {
+	fn new(capacity: usize) -> Self {
+		Self {
+			frames: VecDeque::with_capacity(capacity),
+			capacity,
+			seqs: Vec::new(),
+		}
+	}
+
+	fn push(&mut self, entries: &[Arc<SensorEntry>]) {
+		self.seqs.resize(entries.len(), 0);
+		let values = entries
+			.iter()
+			.zip(&mut self.seqs)
+			.map(|(entry, last_seq)| {
+				let seq = entry.seq();
+				if seq == *last_seq {
+					return f32::NAN;
+				}
+				*last_seq = seq;
+
+				entry.sample().map_or(f32::NAN, |a| a.value as f32)
+			})
+			.collect();
+
+		if self.frames.len() == self.capacity {
+			self.frames.pop_front();
+		}
+		self.frames.push_back(FlightFrame {
+			time: SystemTime::now(),
+			values,
+		});
+	}
+
+	/// Returns the number of the samples.
+	fn dump(
+		&self,
+		path: &Path,
+		format: RecordingFormat,
+		reason: String,
+		game: &CurrentSteamGame,
+		entries: &[Arc<SensorEntry>],
+	) -> io::Result<usize> {
+		let sensors: Vec<RecordingSensor> = entries.iter().map(|a| (&**a).into()).collect();
+		let started = self.frames.front().map_or_else(SystemTime::now, |a| a.time);
+		let header = RecordingHeader::new(reason, started, game, sensors);
+		let mut writer = RecordingWriter::create(path, format, &header)?;
+		let mut count = 0;
+		for frame in &self.frames {
+			for (sensor, value) in header.sensors.iter().zip(&frame.values) {
+				if value.is_finite() {
+					writer.write_sample(frame.time, sensor, f64::from(*value))?;
+					count += 1;
+				}
+			}
+		}
+		writer.flush()?;
+
+		Ok(count)
+	}
+}
+
+/// Dumps the ring from the GUI (the hotkey).
+#[derive(Debug, Clone)]
+pub struct FlightRecorderControl(mpsc::Sender<String>);
+
+impl FlightRecorderControl {
+	#[inline]
+	pub fn dump(&self, reason: impl Into<String>) {
+		let _e = self.0.send(reason.into());
+	}
+}
+
+/// Samples the sensor registry into the ring in the background, the dumps
+/// are written to `dir` in `format`. The alerts dump it at most once per ring length.
+pub fn spawn_flight_recorder(
+	config: FlightRecorderConfig,
+	dir: PathBuf,
+	format: RecordingFormat,
+	game: CurrentSteamGame,
+) -> FlightRecorderControl {
+	let (control_sender, control_recv) = mpsc::channel::<String>();
+	if !config.is_enabled() {
+		return FlightRecorderControl(control_sender);
+	}
+
+	let alerts = ALERTS.subscribe();
+	std::thread::spawn(move || {
+		let interval = config.get_interval();
+		let capacity = (config.get_time().as_millis() / interval.as_millis()).max(1) as usize;
+		let mut ring = FlightRing::new(capacity);
+		let mut last_alert_dump: Option<Instant> = None;
+		let mut next_tick = Instant::now();
+
+		loop {
+			let reason = match control_recv
+				.recv_timeout(next_tick.saturating_duration_since(Instant::now()))
+			{
+				Ok(reason) => Some(reason),
+				Err(mpsc::RecvTimeoutError::Timeout) => None,
+				Err(mpsc::RecvTimeoutError::Disconnected) => return,
+			};
+			let entries = SENSOR_REGISTRY.entries();
+			if Instant::now() >= next_tick {
+				ring.push(&entries);
+				next_tick += interval;
+				if next_tick < Instant::now() {
+					next_tick = Instant::now() + interval;
+				}
+			}
+
+			let mut reasons: Vec<String> = reason.into_iter().collect();
+			while let Ok(event) = alerts.try_recv() {
+				if event.kind != AlertEventKind::Fired
+					|| event.state.severity < config.get_on_alert()
+				{
+					continue;
+				}
+				if last_alert_dump.is_some_and(|a| a.elapsed() < config.get_time()) {
+					trace!("#[flightrecorder] Already dumped: {}", event.state.name);
+					continue;
+				}
+				last_alert_dump = Some(Instant::now());
+				reasons.push(format!("alert: {}", event.state.name));
+			}
+
+			for reason in reasons {
+				let path = recording_path(&dir, "flight", &reason, format.extension());
+				match ring.dump(&path, format, reason, &game, &entries) {
+					Ok(count) => info!("#[flightrecorder] {} samples -> {:?}", count, path),
+					Err(e) => error!("#[flightrecorder] I can't write {:?}: {}", path, e),
+				}
+			}
+		}
+	});
+
+	FlightRecorderControl(control_sender)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::core::sensorregistry::SensorId;
+	use crate::core::sensorregistry::SensorKind;
+	use crate::core::sensorregistry::SensorRegistry;
+	use crate::core::sensorregistry::SensorUnit;
+
+	fn entries() -> Vec<Arc<SensorEntry>> {
+		let registry = SensorRegistry::new();
+		for feature in ["temp1", "temp2"] {
+			registry.register(
+				SensorId::new("lm_sensors", "k10temp-pci-00c3", feature),
+				feature,
+				SensorKind::Temperature,
+				SensorUnit::Celsius,
+			);
+		}
+
+		registry.entries()
+	}
+
+	fn values(ring: &FlightRing) -> Vec<Vec<f32>> {
+		ring.frames.iter().map(|a| a.values.to_vec()).collect()
+	}
+
+	#[test]
+	fn capacity() {
+		let entries = entries();
+		let mut ring = FlightRing::new(3);
+		for i in 0..5 {
+			entries[0].update(f64::from(i), 0.0, 100.0);
+			ring.push(&entries);
+		}
+
+		assert_eq!(ring.frames.len(), 3);
+		assert_eq!(
+			values(&ring).iter().map(|a| a[0]).collect::<Vec<_>>(),
+			[2.0, 3.0, 4.0]
+		);
+		// never sampled
+		assert!(values(&ring).iter().all(|a| a[1].is_nan()));
+	}
+
+	#[test]
+	fn no_new_sample() {
+		let entries = entries();
+		let mut ring = FlightRing::new(8);
+		entries[0].update(40.0, 0.0, 100.0);
+		entries[1].update(50.0, 0.0, 100.0);
+		ring.push(&entries);
+		// `seq` of temp2 is unchanged
+		entries[0].update(41.0, 0.0, 100.0);
+		ring.push(&entries);
+		ring.push(&entries);
+
+		let values = values(&ring);
+		assert_eq!(values[0], [40.0, 50.0]);
+		assert_eq!(values[1][0], 41.0);
+		assert!(values[1][1].is_nan());
+		assert!(values[2].iter().all(|a| a.is_nan()));
+	}
+
+	#[test]
+	fn dump() {
+		let entries = entries();
+		let mut ring = FlightRing::new(8);
+		entries[0].update(40.0, 0.0, 100.0);
+		ring.push(&entries);
+		entries[0].update(41.0, 0.0, 100.0);
+		entries[1].update(50.5, 0.0, 100.0);
+		ring.push(&entries);
+		ring.push(&entries);
+
+		let path = std::env::temp_dir()
+			.join(format!("flightrecorder-{}", std::process::id()))
+			.join("flight.csv");
+		let count = ring
+			.dump(
+				&path,
+				RecordingFormat::Csv,
+				"hotkey".into(),
+				&CurrentSteamGame::default(),
+				&entries,
+			)
+			.unwrap();
+		assert_eq!(count, 3);
+
+		let data = std::fs::read_to_string(&path).unwrap();
+		assert!(data.starts_with("# app: "));
+		assert!(data.contains("\n# reason: hotkey\n"));
+		assert!(
+			data.contains("\n# sensor,lm_sensors/k10temp-pci-00c3/temp2,temp2,temperature,°C\n")
+		);
+		// without the time
+		let samples: Vec<&str> = data
+			.lines()
+			.skip_while(|a| *a != "time,sensor,value,unit")
+			.skip(1)
+			.filter_map(|a| Some(a.split_once(',')?.1))
+			.collect();
+		assert_eq!(
+			samples,
+			[
+				"lm_sensors/k10temp-pci-00c3/temp1,40,°C",
+				"lm_sensors/k10temp-pci-00c3/temp1,41,°C",
+				"lm_sensors/k10temp-pci-00c3/temp2,50.5,°C",
+			]
+		);
+
+		let _e = std::fs::remove_dir_all(path.parent().unwrap());
+	}
+}
@@ -0,0 +1,173 @@
+//! Frame-time statistics of the dock (frame times, FPS and the 1%/0.1%
+//! lows of a client). The ingest protocol is in the
+//! `machinepmmeter-frametimeproto` crate.
+
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrametimeSummary {
+	/// Frames per second over the last second.
+	pub fps: f64,
+	/// Average frame time over the last second (ms).
+	pub frametime_ms: f64,
+	/// FPS of the average of the worst 1% frame times of the window.
+	pub low_1: f64,
+	/// FPS of the average of the worst 0.1% frame times of the window.
+	pub low_01: f64,
+}
+
+/// Frame intervals of the last `window_ns` of one client.
+#[derive(Debug, Clone)]
+pub struct FrametimeStats {
+	window_ns: u64,
+	last_timestamp_ns: Option<u64>,
+	/// (timestamp_ns of the frame, interval_ns to the previous one)
+	intervals: VecDeque<(u64, u64)>,
+}
+
+impl FrametimeStats {
+	pub const fn new(window_ns: u64) -> Self {
+		Self {
+			window_ns,
+			last_timestamp_ns: None,
+			intervals: VecDeque::new(),
+		}
+	}
+
+	#[inline]
+	pub const fn last_timestamp_ns(&self) -> Option<u64> {
+		self.last_timestamp_ns
+	}
+
+	/// Adds a frame present, returns the frame time (ns).
+	pub fn push(&mut self, timestamp_ns: u64) -> Option<u64> {
+		let last_timestamp_ns = self.last_timestamp_ns.replace(timestamp_ns);
+		let interval = match last_timestamp_ns {
+			Some(last) if timestamp_ns > last => timestamp_ns - last,
+			Some(last) if timestamp_ns < last => {
+				// another clock, the client was restarted
+				self.intervals.clear();
+
+				return None;
+			}
+			_ => return None,
+		};
+		self.intervals.push_back((timestamp_ns, interval));
+
+		let min_timestamp = timestamp_ns.saturating_sub(self.window_ns);
+		while self.intervals.front().is_some_and(|(a, _)| *a < min_timestamp) {
+			self.intervals.pop_front();
+		}
+
+		Some(interval)
+	}
+
+	pub fn summary(&self) -> Option<FrametimeSummary> {
+		let last = self.last_timestamp_ns?;
+		if self.intervals.is_empty() {
+			return None;
+		}
+
+		let min_timestamp = last.saturating_sub(1_000_000_000);
+		let (count, sum) = self
+			.intervals
+			.iter()
+			.rev()
+			.take_while(|(a, _)| *a >= min_timestamp)
+			.fold((0u64, 0u64), |(count, sum), (_, a)| (count + 1, sum + a));
+		let frametime_ms = match count {
+			0 => return None,
+			_ => sum as f64 / count as f64 / 1_000_000.0,
+		};
+
+		let mut sorted: Vec<u64> = self.intervals.iter().map(|(_, a)| *a).collect();
+		sorted.sort_unstable_by(|a, b| b.cmp(a));
+		let low = |part: f64| {
+			let len = ((sorted.len() as f64 * part).ceil() as usize).clamp(1, sorted.len());
+			let avg_ms = sorted[..len].iter().sum::<u64>() as f64 / len as f64 / 1_000_000.0;
+
+			1000.0 / avg_ms
+		};
+
+		Some(FrametimeSummary {
+			fps: 1000.0 / frametime_ms,
+			frametime_ms,
+			low_1: low(0.01),
+			low_01: low(0.001),
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const MS: u64 = 1_000_000;
+
+	/// Frames with the intervals (ms) from `start_ns`, returns the last timestamp.
+	fn push_frames(stats: &mut FrametimeStats, start_ns: u64, intervals_ms: &[u64]) -> u64 {
+		let mut timestamp_ns = start_ns;
+		stats.push(timestamp_ns);
+		for interval in intervals_ms {
+			timestamp_ns += interval * MS;
+			stats.push(timestamp_ns);
+		}
+
+		timestamp_ns
+	}
+
+	#[test]
+	fn steady_fps() {
+		let mut stats = FrametimeStats::new(30_000 * MS);
+		assert_eq!(stats.summary(), None);
+		assert_eq!(stats.push(0), None);
+		assert_eq!(stats.push(10 * MS), Some(10 * MS));
+
+		push_frames(&mut stats, 20 * MS, &[10; 200]);
+		let summary = stats.summary().unwrap();
+		assert_eq!(summary.frametime_ms, 10.0);
+		assert_eq!(summary.fps, 100.0);
+		assert_eq!(summary.low_1, 100.0);
+		assert_eq!(summary.low_01, 100.0);
+	}
+
+	#[test]
+	fn lows() {
+		let mut stats = FrametimeStats::new(30_000 * MS);
+		// 999 frames of 10 ms and a hitch of 100 ms
+		let mut intervals = vec![10; 999];
+		intervals.insert(500, 100);
+		push_frames(&mut stats, 0, &intervals);
+
+		let summary = stats.summary().unwrap();
+		// the worst frame
+		assert_eq!(summary.low_01, 10.0);
+		// the worst 10 frames: (100 + 9 * 10) / 10 = 19 ms
+		assert!((summary.low_1 - 1000.0 / 19.0).abs() < 1e-9);
+		// the last second is without the hitch
+		assert_eq!(summary.fps, 100.0);
+	}
+
+	#[test]
+	fn window() {
+		let mut stats = FrametimeStats::new(1_000 * MS);
+		let last = push_frames(&mut stats, 0, &[500, 10]);
+		assert_eq!(stats.summary().unwrap().low_01, 2.0);
+
+		// the hitch leaves the window after a second
+		push_frames(&mut stats, last + 10 * MS, &[10; 150]);
+		assert!(stats.intervals.len() <= 101);
+		assert_eq!(stats.summary().unwrap().low_01, 100.0);
+	}
+
+	#[test]
+	fn restarted_clock() {
+		let mut stats = FrametimeStats::new(30_000 * MS);
+		push_frames(&mut stats, 5_000 * MS, &[10; 10]);
+		assert!(stats.summary().is_some());
+
+		assert_eq!(stats.push(MS), None);
+		assert_eq!(stats.summary(), None);
+		assert_eq!(stats.push(11 * MS), Some(10 * MS));
+	}
+}
@@ -0,0 +1,245 @@
+//! The frame-time socket of the dock, with or without the frametime page.
+//!
+//! The frames of the clients go to the subscribers (the frametime page), the
+//! messages of the launchers and of `machinepmmeter record` (`WATCH`,
+//! `RECORD`, `RECORD-STOP`) are the commands of the dock.
+
+use log::error;
+use log::info;
+use log::trace;
+use machinepmmeter_frametimeproto::FrametimeMessage;
+use std::fs;
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::UnixListener;
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::mpsc;
+
+/// The frames of a client (by the number of its connection).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrametimeClientEvent {
+	Hello(usize, u32, String),
+	Frame(usize, u64),
+	Bye(usize),
+}
+
+/// The messages that do not depend on the frametime page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrametimeCommand {
+	/// `WATCH <pid> <exe>`, the process tree of `pid` is monitored.
+	Watch(u32, String),
+	/// The connection of a `WATCH` is closed.
+	Unwatch(u32),
+	Record(Option<PathBuf>),
+	RecordStop,
+}
+
+/// Binds the frame-time socket, a stale socket file of a previous run is
+/// replaced.
+pub fn bind_frametime_socket(path: &Path) -> io::Result<UnixListener> {
+	if let Some(dir) = path.parent() {
+		fs::create_dir_all(dir)?;
+	}
+
+	let listener = match UnixListener::bind(path) {
+		Ok(a) => a,
+		Err(e) if e.kind() == io::ErrorKind::AddrInUse => {
+			if UnixStream::connect(path).is_ok() {
+				return Err(e);
+			}
+			fs::remove_file(path)?;
+
+			UnixListener::bind(path)?
+		}
+		Err(e) => return Err(e),
+	};
+	fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+
+	Ok(listener)
+}
+
+/// The listening socket and the subscribers of the client events.
+#[derive(Debug, Clone)]
+pub struct FrametimeServer {
+	path: PathBuf,
+	subscribers: Arc<Mutex<Vec<mpsc::Sender<FrametimeClientEvent>>>>,
+}
+
+impl FrametimeServer {
+	#[inline]
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+
+	/// The events of the clients from now on.
+	pub fn subscribe(&self) -> mpsc::Receiver<FrametimeClientEvent> {
+		let (sender, recv) = mpsc::channel();
+		match self.subscribers.lock() {
+			Ok(mut a) => a.push(sender),
+			Err(e) => e.into_inner().push(sender),
+		}
+
+		recv
+	}
+
+	fn publish(&self, event: FrametimeClientEvent) {
+		let mut subscribers = match self.subscribers.lock() {
+			Ok(a) => a,
+			Err(e) => e.into_inner(),
+		};
+		subscribers.retain(|a| a.send(event.clone()).is_ok());
+	}
+}
+
+/// Binds `path` and accepts the clients in a thread, `on_command` is called
+/// from the client threads.
+pub fn spawn_frametime_server(
+	path: PathBuf,
+	on_command: impl Fn(FrametimeCommand) + Send + Sync + 'static,
+) -> io::Result<FrametimeServer> {
+	let listener = bind_frametime_socket(&path)?;
+	info!("#[frametime] Waiting for clients on {:?}", path);
+
+	let server = FrametimeServer {
+		path,
+		subscribers: Default::default(),
+	};
+	let on_command = Arc::new(on_command);
+	std::thread::spawn({
+		let server = server.clone();
+
+		move || {
+			for (id, client) in listener.incoming().enumerate() {
+				let client = match client {
+					Ok(a) => a,
+					Err(e) => {
+						error!("#[frametime] accept: {}", e);
+						continue;
+					}
+				};
+				trace!("#[frametime] client #{} connected", id);
+
+				let server = server.clone();
+				let on_command = on_command.clone();
+				std::thread::spawn(move || {
+					// the pids of the `WATCH` of this connection
+					let mut watched = Vec::new();
+					for line in BufReader::new(client).lines() {
+						let Ok(line) = line else {
+							break;
+						};
+						match FrametimeMessage::parse(&line) {
+							Some(FrametimeMessage::Frame { timestamp_ns }) => {
+								server.publish(FrametimeClientEvent::Frame(id, timestamp_ns));
+							}
+							Some(FrametimeMessage::Hello { pid, exe }) => {
+								server.publish(FrametimeClientEvent::Hello(
+									id,
+									pid,
+									exe.into_owned(),
+								));
+							}
+							Some(FrametimeMessage::Watch { pid, exe }) => {
+								watched.push(pid);
+								on_command(FrametimeCommand::Watch(pid, exe.into_owned()));
+							}
+							Some(FrametimeMessage::Record { path }) => {
+								info!("#[frametime] client #{}: start recording {:?}", id, path);
+								on_command(FrametimeCommand::Record(
+									path.map(|a| PathBuf::from(&*a)),
+								));
+							}
+							Some(FrametimeMessage::RecordStop) => {
+								info!("#[frametime] client #{}: stop recording", id);
+								on_command(FrametimeCommand::RecordStop);
+							}
+							None => {
+								trace!("#[frametime] client #{}: unknown message {:?}", id, line);
+							}
+						}
+					}
+					for pid in watched {
+						on_command(FrametimeCommand::Unwatch(pid));
+					}
+					trace!("#[frametime] client #{} disconnected", id);
+					server.publish(FrametimeClientEvent::Bye(id));
+				});
+			}
+		}
+	});
+
+	Ok(server)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io::Write;
+	use std::time::Duration;
+
+	#[test]
+	fn clients_and_commands() {
+		let dir = std::env::temp_dir().join(format!("frametimeserver-{}", std::process::id()));
+		let path = dir.join("frametime.sock");
+		let (command_sender, command_recv) = mpsc::channel();
+		let command_sender = Mutex::new(command_sender);
+		let server = spawn_frametime_server(path.clone(), move |a| {
+			command_sender.lock().unwrap().send(a).unwrap();
+		})
+		.unwrap();
+		assert_eq!(server.path(), path);
+		let events = server.subscribe();
+		let mut game = UnixStream::connect(&path).unwrap();
+		game.write_all(b"HELLO 42 glxgears\nF 1000\nBOGUS\nF 17667\n")
+			.unwrap();
+		let mut launcher = UnixStream::connect(&path).unwrap();
+		launcher
+			.write_all(b"WATCH 40 steam\nRECORD /tmp/session.csv\nRECORD-STOP\n")
+			.unwrap();
+		drop(launcher);
+
+		let timeout = Duration::from_secs(5);
+		let next = || command_recv.recv_timeout(timeout).unwrap();
+		assert_eq!(next(), FrametimeCommand::Watch(40, "steam".to_string()));
+		assert_eq!(
+			next(),
+			FrametimeCommand::Record(Some(PathBuf::from("/tmp/session.csv")))
+		);
+		assert_eq!(next(), FrametimeCommand::RecordStop);
+		assert_eq!(next(), FrametimeCommand::Unwatch(40));
+
+		drop(game);
+		let mut a_events = Vec::new();
+		while let Ok(event) = events.recv_timeout(timeout) {
+			// the launcher
+			if event == FrametimeClientEvent::Bye(1) {
+				continue;
+			}
+			let is_bye = matches!(event, FrametimeClientEvent::Bye(0));
+			a_events.push(event);
+			if is_bye {
+				break;
+			}
+		}
+		assert_eq!(
+			a_events,
+			[
+				FrametimeClientEvent::Hello(0, 42, "glxgears".to_string()),
+				FrametimeClientEvent::Frame(0, 1000),
+				FrametimeClientEvent::Frame(0, 17667),
+				FrametimeClientEvent::Bye(0),
+			]
+		);
+
+		// a running dock keeps its socket
+		assert!(bind_frametime_socket(&path).is_err());
+
+		let _e = fs::remove_dir_all(&dir);
+	}
+}
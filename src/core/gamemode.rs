@@ -0,0 +1,239 @@
+use dbus::Path as DbusPath;
+use dbus::blocking::Connection;
+use dbus::blocking::stdintf::org_freedesktop_dbus::Properties;
+use std::borrow::Cow;
+use std::time::Duration;
+
+pub const GAMEMODE_DEST: &str = "com.feralinteractive.GameMode";
+pub const GAMEMODE_PATH: &str = "/com/feralinteractive/GameMode";
+pub const GAMEMODE_INTERFACE: &str = "com.feralinteractive.GameMode";
+pub const GAMEMODE_GAME_INTERFACE: &str = "com.feralinteractive.GameMode.Game";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameModeClient {
+	pub pid: i32,
+	/// Empty if the daemon does not know it.
+	pub exe: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GameModeStatus {
+	pub client_count: i32,
+	pub clients: Vec<GameModeClient>,
+}
+
+impl GameModeStatus {
+	#[inline]
+	pub const fn is_active(&self) -> bool {
+		self.client_count > 0
+	}
+}
+
+/// Read-only access to the Feral GameMode daemon.
+///
+/// The bus and destination are not fixed, so the same code can talk to a
+/// stand-in GameMode service (`examples/gamemode_standin.rs`).
+pub struct GameMode {
+	conn: Connection,
+	dest: Cow<'static, str>,
+	timeout: Duration,
+}
+
+impl GameMode {
+	#[inline]
+	pub fn new_session() -> Result<Self, dbus::Error> {
+		Ok(Self::with_connection(
+			Connection::new_session()?,
+			GAMEMODE_DEST,
+		))
+	}
+
+	#[inline]
+	pub fn with_connection(conn: Connection, dest: impl Into<Cow<'static, str>>) -> Self {
+		Self {
+			conn,
+			dest: dest.into(),
+			timeout: Duration::from_millis(3000),
+		}
+	}
+
+	/// Without activating the daemon (it is D-Bus activatable).
+	pub fn is_running(&self) -> Result<bool, dbus::Error> {
+		let (has_owner,): (bool,) = self
+			.conn
+			.with_proxy("org.freedesktop.DBus", "/org/freedesktop/DBus", self.timeout)
+			.method_call("org.freedesktop.DBus", "NameHasOwner", (&*self.dest,))?;
+
+		Ok(has_owner)
+	}
+
+	pub fn status(&self) -> Result<GameModeStatus, dbus::Error> {
+		let proxy = self.conn.with_proxy(&*self.dest, GAMEMODE_PATH, self.timeout);
+		let client_count: i32 = proxy.get(GAMEMODE_INTERFACE, "ClientCount")?;
+		let (games,): (Vec<(i32, DbusPath<'static>)>,) =
+			proxy.method_call(GAMEMODE_INTERFACE, "ListGames", ())?;
+
+		let clients = games
+			.into_iter()
+			.map(|(pid, path)| {
+				let exe: String = self
+					.conn
+					.with_proxy(&*self.dest, path, self.timeout)
+					.get(GAMEMODE_GAME_INTERFACE, "Executable")
+					.unwrap_or_default();
+
+				GameModeClient { pid, exe }
+			})
+			.collect();
+
+		Ok(GameModeStatus {
+			client_count,
+			clients,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use dbus::MethodErr;
+	use dbus_crossroads::Crossroads;
+	use std::collections::BTreeMap;
+
+	#[derive(Debug, Default)]
+	struct Daemon {
+		/// pid -> executable
+		games: BTreeMap<i32, String>,
+	}
+
+	#[derive(Debug)]
+	struct Game {
+		exe: String,
+	}
+
+	fn game_path(pid: i32) -> DbusPath<'static> {
+		DbusPath::from(format!("{}/Games/{}", GAMEMODE_PATH, pid))
+	}
+
+	/// A GameMode stand-in on the session bus and a connection to register the
+	/// games, `None` without a bus.
+	fn standin() -> Option<(GameMode, Connection)> {
+		let server = Connection::new_session().ok()?;
+		let dest = server.unique_name().to_string();
+
+		let mut cr = Crossroads::new();
+		let game_token = cr.register(GAMEMODE_GAME_INTERFACE, |b| {
+			b.property("Executable")
+				.get(|_, game: &mut Game| Ok(game.exe.clone()));
+		});
+		let daemon_token = cr.register(GAMEMODE_INTERFACE, move |b| {
+			b.property("ClientCount")
+				.get(|_, daemon: &mut Daemon| Ok(daemon.games.len() as i32));
+			b.method("ListGames", (), ("games",), |_, daemon: &mut Daemon, ()| {
+				let games: Vec<(i32, DbusPath<'static>)> = daemon
+					.games
+					.keys()
+					.map(|pid| (*pid, game_path(*pid)))
+					.collect();
+
+				Ok((games,))
+			});
+			b.method_with_cr(
+				"RegisterGame",
+				("pid",),
+				("result",),
+				move |ctx, cr, (pid,): (i32,)| {
+					let exe = format!("/usr/games/game{}", pid);
+					let daemon: &mut Daemon = cr
+						.data_mut(ctx.path())
+						.ok_or_else(|| MethodErr::no_path(ctx.path()))?;
+					daemon.games.insert(pid, exe.clone());
+
+					cr.insert(game_path(pid), &[game_token], Game { exe });
+					Ok((0i32,))
+				},
+			);
+			b.method_with_cr(
+				"UnregisterGame",
+				("pid",),
+				("result",),
+				|ctx, cr, (pid,): (i32,)| {
+					let daemon: &mut Daemon = cr
+						.data_mut(ctx.path())
+						.ok_or_else(|| MethodErr::no_path(ctx.path()))?;
+					let result = match daemon.games.remove(&pid) {
+						Some(_) => 0i32,
+						None => -1,
+					};
+
+					let _e = cr.remove::<Game>(&game_path(pid));
+					Ok((result,))
+				},
+			);
+		});
+		cr.insert(GAMEMODE_PATH, &[daemon_token], Daemon::default());
+		let games = Connection::new_session().ok()?;
+		std::thread::spawn(move || cr.serve(&server));
+
+		Some((
+			GameMode::with_connection(Connection::new_session().ok()?, dest),
+			games,
+		))
+	}
+
+	#[test]
+	fn register_and_unregister() {
+		// no session bus
+		let Some((gamemode, games)) = standin() else {
+			return;
+		};
+		let call = |method: &str, pid: i32| -> i32 {
+			let (result,): (i32,) = games
+				.with_proxy(&*gamemode.dest, GAMEMODE_PATH, gamemode.timeout)
+				.method_call(GAMEMODE_INTERFACE, method, (pid,))
+				.unwrap();
+
+			result
+		};
+
+		assert!(gamemode.is_running().unwrap());
+		assert_eq!(gamemode.status().unwrap(), GameModeStatus::default());
+		assert!(!gamemode.status().unwrap().is_active());
+
+		assert_eq!(call("RegisterGame", 4242), 0);
+		assert_eq!(call("RegisterGame", 4343), 0);
+		let status = gamemode.status().unwrap();
+		assert!(status.is_active());
+		assert_eq!(
+			status,
+			GameModeStatus {
+				client_count: 2,
+				clients: vec![
+					GameModeClient {
+						pid: 4242,
+						exe: "/usr/games/game4242".into(),
+					},
+					GameModeClient {
+						pid: 4343,
+						exe: "/usr/games/game4343".into(),
+					},
+				],
+			}
+		);
+
+		assert_eq!(call("UnregisterGame", 4242), 0);
+		assert_eq!(call("UnregisterGame", 4242), -1);
+		let status = gamemode.status().unwrap();
+		assert_eq!(status.client_count, 1);
+		assert_eq!(status.clients[0].pid, 4343);
+
+		assert_eq!(call("UnregisterGame", 4343), 0);
+		assert!(!gamemode.status().unwrap().is_active());
+
+		// a GameMode that is not running
+		let stopped =
+			GameMode::with_connection(Connection::new_session().unwrap(), "org.example.NoGameMode");
+		assert!(!stopped.is_running().unwrap());
+		assert!(stopped.status().is_err());
+	}
+}
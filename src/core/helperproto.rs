@@ -0,0 +1,592 @@
+//! Privileged helper protocol.
+//!
+//! The helper (`machinepmmeter-helper`, root via pkexec or a systemd service)
+//! listens on the Unix stream socket at [`helper_socket_path`], checks the uid
+//! of every peer (`SO_PEERCRED`) and answers text lines:
+//!
+//! ```text
+//! READ /sys/class/powercap/intel-rapl:0/energy_uj   -> OK 123456789
+//! WRITE /sys/class/hwmon/hwmon2/pwm1_enable 1        -> OK
+//! MSR 0 0xc001029b                                   -> OK 0x1234
+//! RYZENADJ                                           -> OK stapm-limit=25.000 ...
+//! anything else                                      -> ERR <message>
+//! ```
+//!
+//! Only the paths and registers of the allow-list below are accepted. A
+//! `pwm*_enable` changed over a connection is returned to automatic mode, the
+//! cpufreq governor, EPP and boost to their original values when the
+//! connection is closed (the GUI has exited or crashed).
+//!
+//! This file depends only on `std`, it is also included by the helper.
+
+use std::borrow::Cow;
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::os::unix::net::UnixStream;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Overrides the socket path (helper and clients).
+pub const HELPER_SOCKET_ENV: &str = "MACHINEPMMETER_HELPER_SOCKET";
+pub const HELPER_SOCKET_PATH: &str = "/run/machinepmmeter/helper.sock";
+/// Longer lines are rejected by the helper.
+pub const HELPER_MAX_LINE: u64 = 4096;
+
+/// `$MACHINEPMMETER_HELPER_SOCKET` or `/run/machinepmmeter/helper.sock`.
+pub fn helper_socket_path() -> PathBuf {
+	std::env::var_os(HELPER_SOCKET_ENV)
+		.filter(|a| !a.is_empty())
+		.map_or_else(|| PathBuf::from(HELPER_SOCKET_PATH), PathBuf::from)
+}
+
+/// Readable paths (`*` - digits and `:`).
+pub const HELPER_READ_ALLOW: &[&str] = &[
+	"/sys/class/powercap/intel-rapl:*/energy_uj",
+	"/sys/class/powercap/intel-rapl:*/max_energy_range_uj",
+];
+/// Writable paths, the value must be `0..=255`.
+pub const HELPER_WRITE_ALLOW: &[&str] = &[
+	"/sys/class/hwmon/hwmon*/pwm*",
+	"/sys/class/hwmon/hwmon*/pwm*_enable",
+];
+/// Writable cpufreq paths, a governor/EPP name or `0`/`1` (boost).
+pub const HELPER_CPUFREQ_WRITE_ALLOW: &[&str] = &[
+	"/sys/devices/system/cpu/cpufreq/policy*/scaling_governor",
+	"/sys/devices/system/cpu/cpufreq/policy*/energy_performance_preference",
+	"/sys/devices/system/cpu/cpufreq/policy*/boost",
+	"/sys/devices/system/cpu/cpufreq/boost",
+	"/sys/devices/system/cpu/intel_pstate/no_turbo",
+];
+/// Readable MSRs (`/dev/cpu/<cpu>/msr`).
+pub const HELPER_MSR_ALLOW: &[u32] = &[
+	0x198,      // IA32_PERF_STATUS
+	0x19c,      // IA32_THERM_STATUS
+	0x1a2,      // MSR_TEMPERATURE_TARGET
+	0x1b1,      // IA32_PACKAGE_THERM_STATUS
+	0x606,      // MSR_RAPL_POWER_UNIT
+	0x611,      // MSR_PKG_ENERGY_STATUS
+	0x639,      // MSR_PP0_ENERGY_STATUS
+	0xc0010299, // AMD RAPL_PWR_UNIT
+	0xc001029a, // AMD CORE_ENERGY_STAT
+	0xc001029b, // AMD PKG_ENERGY_STAT
+];
+
+/// A path component of the allow-list, `*` matches one or more digits and
+/// colons (`hwmon2`, `intel-rapl:0:1`).
+fn component_matches(pattern: &str, a: &str) -> bool {
+	match pattern.split_once('*') {
+		Some((prefix, suffix)) => a
+			.strip_prefix(prefix)
+			.and_then(|a| a.strip_suffix(suffix))
+			.is_some_and(|a| !a.is_empty() && a.bytes().all(|a| a.is_ascii_digit() || a == b':')),
+		None => pattern == a,
+	}
+}
+
+/// An absolute path without `.`/`..` that matches one of `allow`.
+pub fn is_path_allowed(allow: &[&str], path: &str) -> bool {
+	let path = Path::new(path);
+	if !path.is_absolute()
+		|| path
+			.components()
+			.any(|a| !matches!(a, Component::RootDir | Component::Normal(_)))
+	{
+		return false;
+	}
+
+	allow.iter().any(|pattern| {
+		let mut pattern = Path::new(pattern).components();
+		let mut path = path.components();
+		loop {
+			match (pattern.next(), path.next()) {
+				(None, None) => return true,
+				(Some(Component::Normal(p)), Some(Component::Normal(a))) => {
+					match (p.to_str(), a.to_str()) {
+						(Some(p), Some(a)) if component_matches(p, a) => {}
+						_ => return false,
+					}
+				}
+				(Some(Component::RootDir), Some(Component::RootDir)) => {}
+				_ => return false,
+			}
+		}
+	})
+}
+
+/// `pwm*_enable` of the allow-list.
+pub fn is_pwm_enable_path(path: &str) -> bool {
+	is_path_allowed(&["/sys/class/hwmon/hwmon*/pwm*_enable"], path)
+}
+
+/// A path of the write allow-lists with a value it accepts.
+pub fn is_write_allowed(path: &str, value: &str) -> bool {
+	if is_path_allowed(HELPER_WRITE_ALLOW, path) {
+		return value.parse::<u8>().is_ok();
+	}
+	if !is_path_allowed(HELPER_CPUFREQ_WRITE_ALLOW, path) {
+		return false;
+	}
+
+	match path.ends_with("boost") || path.ends_with("no_turbo") {
+		true => matches!(value, "0" | "1"),
+		// `schedutil`, `balance_performance`
+		false => {
+			!value.is_empty()
+				&& value.len() <= 32
+				&& value
+					.bytes()
+					.all(|a| a.is_ascii_lowercase() || a.is_ascii_digit() || a == b'_')
+		}
+	}
+}
+
+/// Written values of these paths are restored by the helper when the
+/// connection is closed.
+pub fn is_restored_on_close(path: &str) -> bool {
+	is_pwm_enable_path(path) || is_path_allowed(HELPER_CPUFREQ_WRITE_ALLOW, path)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HelperRequest<'a> {
+	Read {
+		path: Cow<'a, str>,
+	},
+	Write {
+		path: Cow<'a, str>,
+		value: Cow<'a, str>,
+	},
+	Msr {
+		cpu: u32,
+		reg: u32,
+	},
+	RyzenadjInfo,
+}
+
+/// `0x1a2` or `418`.
+fn parse_u32(a: &str) -> Option<u32> {
+	match a.strip_prefix("0x") {
+		Some(hex) => u32::from_str_radix(hex, 16).ok(),
+		None => a.parse().ok(),
+	}
+}
+
+impl<'a> HelperRequest<'a> {
+	pub fn parse(line: &'a str) -> Option<Self> {
+		let line = line.trim_end_matches(['\r', '\n']);
+		let mut args = line.split(' ');
+
+		let result = match args.next()? {
+			"READ" => Self::Read {
+				path: Cow::Borrowed(args.next()?),
+			},
+			"WRITE" => Self::Write {
+				path: Cow::Borrowed(args.next()?),
+				value: Cow::Borrowed(args.next()?),
+			},
+			"MSR" => Self::Msr {
+				cpu: parse_u32(args.next()?)?,
+				reg: parse_u32(args.next()?)?,
+			},
+			"RYZENADJ" => Self::RyzenadjInfo,
+			_ => return None,
+		};
+		if args.next().is_some() {
+			return None;
+		}
+
+		Some(result)
+	}
+
+	pub fn write_to(&self, mut w: impl Write) -> io::Result<()> {
+		match self {
+			Self::Read { path } => writeln!(w, "READ {}", path),
+			Self::Write { path, value } => writeln!(w, "WRITE {} {}", path, value),
+			Self::Msr { cpu, reg } => writeln!(w, "MSR {} {:#x}", cpu, reg),
+			Self::RyzenadjInfo => writeln!(w, "RYZENADJ"),
+		}
+	}
+}
+
+/// `OK <value>` or `ERR <message>`.
+pub fn parse_helper_response(line: &str) -> io::Result<String> {
+	let line = line.trim_end_matches(['\r', '\n']);
+	let (status, value) = line.split_once(' ').unwrap_or((line, ""));
+
+	match status {
+		"OK" => Ok(value.to_string()),
+		"ERR" => Err(io::Error::other(format!("helper: {}", value))),
+		_ => Err(io::Error::new(
+			io::ErrorKind::InvalidData,
+			format!("helper: unexpected response {:?}", line),
+		)),
+	}
+}
+
+/// `key=value` pairs of the `RYZENADJ` response.
+pub fn parse_ryzenadj_pairs(a: &str) -> Vec<(String, String)> {
+	a.split(' ')
+		.filter_map(|a| {
+			let (key, value) = a.split_once('=')?;
+
+			Some((key.to_string(), value.to_string()))
+		})
+		.collect()
+}
+
+/// `| STAPM LIMIT | 25.000 | stapm-limit |` rows of `ryzenadj --info` to
+/// `stapm-limit=25.000 ...`.
+pub fn ryzenadj_info_to_pairs(info: &str) -> String {
+	info.lines()
+		.filter_map(|line| {
+			let mut columns = line.split('|').map(str::trim).skip(1);
+			let (_name, value, key) = (columns.next()?, columns.next()?, columns.next()?);
+			if key.is_empty() || key.contains(' ') || value.parse::<f64>().is_err() {
+				return None;
+			}
+
+			Some(format!("{}={}", key, value))
+		})
+		.collect::<Vec<_>>()
+		.join(" ")
+}
+
+/// A connection to the helper, reconnects once if the helper was restarted.
+#[derive(Debug)]
+pub struct HelperClient {
+	path: PathBuf,
+	stream: Option<BufReader<UnixStream>>,
+}
+
+impl HelperClient {
+	pub fn connect(path: impl Into<PathBuf>) -> io::Result<Self> {
+		let mut result = Self {
+			path: path.into(),
+			stream: None,
+		};
+		result.reconnect()?;
+
+		Ok(result)
+	}
+
+	fn reconnect(&mut self) -> io::Result<()> {
+		self.stream = None;
+
+		let stream = UnixStream::connect(&self.path)?;
+		stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+		stream.set_write_timeout(Some(Duration::from_secs(5)))?;
+		self.stream = Some(BufReader::new(stream));
+
+		Ok(())
+	}
+
+	fn try_request(&mut self, request: &HelperRequest) -> io::Result<String> {
+		let stream = match self.stream {
+			Some(ref mut a) => a,
+			None => return Err(io::ErrorKind::NotConnected.into()),
+		};
+		request.write_to(stream.get_mut())?;
+
+		let mut line = String::new();
+		if stream.read_line(&mut line)? == 0 {
+			return Err(io::ErrorKind::UnexpectedEof.into());
+		}
+
+		Ok(line)
+	}
+
+	pub fn request(&mut self, request: &HelperRequest) -> io::Result<String> {
+		let line = match self.try_request(request) {
+			Ok(a) => a,
+			Err(_) => {
+				self.reconnect()?;
+
+				self.try_request(request)?
+			}
+		};
+
+		parse_helper_response(&line)
+	}
+
+	#[inline]
+	pub fn read(&mut self, path: &str) -> io::Result<String> {
+		self.request(&HelperRequest::Read {
+			path: Cow::Borrowed(path),
+		})
+	}
+
+	#[inline]
+	pub fn write(&mut self, path: &str, value: &str) -> io::Result<()> {
+		self.request(&HelperRequest::Write {
+			path: Cow::Borrowed(path),
+			value: Cow::Borrowed(value),
+		})
+		.map(|_| ())
+	}
+
+	pub fn read_msr(&mut self, cpu: u32, reg: u32) -> io::Result<u64> {
+		let value = self.request(&HelperRequest::Msr { cpu, reg })?;
+
+		value
+			.strip_prefix("0x")
+			.and_then(|a| u64::from_str_radix(a, 16).ok())
+			.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, value))
+	}
+
+	#[inline]
+	pub fn ryzenadj_info(&mut self) -> io::Result<Vec<(String, String)>> {
+		self.request(&HelperRequest::RyzenadjInfo)
+			.map(|a| parse_ryzenadj_pairs(&a))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn read_allow_list() {
+		for path in [
+			"/sys/class/powercap/intel-rapl:0/energy_uj",
+			"/sys/class/powercap/intel-rapl:0:1/energy_uj",
+			"/sys/class/powercap/intel-rapl:1/max_energy_range_uj",
+			// the same file, `Path` drops `.` and repeated `/`
+			"/sys/class/powercap/./intel-rapl:0/energy_uj",
+			"/sys//class/powercap/intel-rapl:0/energy_uj",
+		] {
+			assert!(is_path_allowed(HELPER_READ_ALLOW, path), "{}", path);
+		}
+
+		for path in [
+			// `..`
+			"/sys/class/powercap/intel-rapl:0/../../../../etc/shadow",
+			"/sys/class/powercap/intel-rapl:0/../intel-rapl:1/energy_uj",
+			"/sys/class/powercap/intel-rapl:0/energy_uj/..",
+			// through the symlinks of sysfs and procfs
+			"/sys/class/powercap/intel-rapl:0/device/energy_uj",
+			"/sys/devices/virtual/powercap/intel-rapl/intel-rapl:0/energy_uj",
+			"/proc/self/root/sys/class/powercap/intel-rapl:0/energy_uj",
+			// `*` is one or more digits and colons
+			"/sys/class/powercap/intel-rapl:/energy_uj",
+			"/sys/class/powercap/intel-rapl:x/energy_uj",
+			"/sys/class/powercap/intel-rapl/energy_uj",
+			"/sys/class/powercap/intel-rapl:0/energy_uj/more",
+			"sys/class/powercap/intel-rapl:0/energy_uj",
+			"",
+		] {
+			assert!(!is_path_allowed(HELPER_READ_ALLOW, path), "{}", path);
+		}
+	}
+
+	#[test]
+	fn write_allow_list() {
+		assert!(is_write_allowed("/sys/class/hwmon/hwmon2/pwm1", "0"));
+		assert!(is_write_allowed("/sys/class/hwmon/hwmon2/pwm1", "255"));
+		assert!(is_write_allowed("/sys/class/hwmon/hwmon2/pwm1_enable", "2"));
+		for value in ["256", "-1", "1.5", "0x10", "", " 1"] {
+			assert!(
+				!is_write_allowed("/sys/class/hwmon/hwmon2/pwm1", value),
+				"{:?}",
+				value
+			);
+		}
+		assert!(!is_write_allowed(
+			"/sys/class/hwmon/hwmon2/../hwmon3/pwm1",
+			"128"
+		));
+		assert!(!is_write_allowed(
+			"/sys/class/hwmon/hwmon2/device/pwm1",
+			"128"
+		));
+		assert!(!is_write_allowed(
+			"/sys/class/hwmon/hwmon2/fan1_target",
+			"128"
+		));
+		assert!(!is_write_allowed(
+			"/sys/class/powercap/intel-rapl:0/energy_uj",
+			"0"
+		));
+
+		let policy = "/sys/devices/system/cpu/cpufreq/policy0";
+		assert!(is_write_allowed(
+			&format!("{}/scaling_governor", policy),
+			"schedutil"
+		));
+		assert!(is_write_allowed(
+			&format!("{}/energy_performance_preference", policy),
+			"balance_performance"
+		));
+		for value in [
+			"",
+			"Performance",
+			"power save",
+			"../powersave",
+			&"a".repeat(33),
+		] {
+			assert!(
+				!is_write_allowed(&format!("{}/scaling_governor", policy), value),
+				"{:?}",
+				value
+			);
+		}
+		assert!(is_write_allowed(&format!("{}/boost", policy), "1"));
+		assert!(!is_write_allowed(&format!("{}/boost", policy), "2"));
+		assert!(is_write_allowed(
+			"/sys/devices/system/cpu/cpufreq/boost",
+			"0"
+		));
+		assert!(is_write_allowed(
+			"/sys/devices/system/cpu/intel_pstate/no_turbo",
+			"1"
+		));
+		assert!(!is_write_allowed(
+			"/sys/devices/system/cpu/intel_pstate/no_turbo",
+			"on"
+		));
+		assert!(!is_write_allowed(
+			"/sys/devices/system/cpu/cpufreq/policy0/scaling_max_freq",
+			"4000000"
+		));
+	}
+
+	#[test]
+	fn restored_on_close() {
+		assert!(is_pwm_enable_path("/sys/class/hwmon/hwmon2/pwm1_enable"));
+		assert!(!is_pwm_enable_path("/sys/class/hwmon/hwmon2/pwm1"));
+		assert!(is_restored_on_close("/sys/class/hwmon/hwmon2/pwm1_enable"));
+		assert!(is_restored_on_close(
+			"/sys/devices/system/cpu/cpufreq/policy3/scaling_governor"
+		));
+		assert!(is_restored_on_close(
+			"/sys/devices/system/cpu/intel_pstate/no_turbo"
+		));
+		// the duty follows the mode
+		assert!(!is_restored_on_close("/sys/class/hwmon/hwmon2/pwm1"));
+	}
+
+	#[test]
+	fn requests() {
+		assert_eq!(
+			HelperRequest::parse("READ /sys/class/powercap/intel-rapl:0/energy_uj\n"),
+			Some(HelperRequest::Read {
+				path: Cow::Borrowed("/sys/class/powercap/intel-rapl:0/energy_uj")
+			})
+		);
+		assert_eq!(
+			HelperRequest::parse("WRITE /sys/class/hwmon/hwmon2/pwm1_enable 1\r\n"),
+			Some(HelperRequest::Write {
+				path: Cow::Borrowed("/sys/class/hwmon/hwmon2/pwm1_enable"),
+				value: Cow::Borrowed("1"),
+			})
+		);
+		assert_eq!(
+			HelperRequest::parse("MSR 3 0xc001029b"),
+			Some(HelperRequest::Msr {
+				cpu: 3,
+				reg: 0xc001029b
+			})
+		);
+		assert_eq!(
+			HelperRequest::parse("MSR 0 418"),
+			Some(HelperRequest::Msr { cpu: 0, reg: 0x1a2 })
+		);
+		assert_eq!(
+			HelperRequest::parse("RYZENADJ\n"),
+			Some(HelperRequest::RyzenadjInfo)
+		);
+
+		for line in [
+			"",
+			"\n",
+			"read /sys/class/powercap/intel-rapl:0/energy_uj",
+			"READ",
+			"READ /a /b",
+			"WRITE /sys/class/hwmon/hwmon2/pwm1",
+			"WRITE /sys/class/hwmon/hwmon2/pwm1 1 2",
+			"MSR 0",
+			"MSR -1 0x198",
+			"MSR 0 0xzz",
+			"MSR 0 0x1ffffffff",
+			"RYZENADJ --info",
+			"READ  /sys/class/powercap/intel-rapl:0/energy_uj",
+		] {
+			assert_eq!(HelperRequest::parse(line), None, "{:?}", line);
+		}
+
+		// the lines of the client are parsed by the helper
+		for line in [
+			"READ /sys/class/powercap/intel-rapl:0/energy_uj\n",
+			"WRITE /sys/class/hwmon/hwmon2/pwm1 128\n",
+			"MSR 1 0x611\n",
+			"RYZENADJ\n",
+		] {
+			let mut buf = Vec::new();
+			HelperRequest::parse(line)
+				.unwrap()
+				.write_to(&mut buf)
+				.unwrap();
+			assert_eq!(String::from_utf8(buf).unwrap(), line);
+		}
+	}
+
+	#[test]
+	fn responses() {
+		assert_eq!(parse_helper_response("OK\n").unwrap(), "");
+		assert_eq!(
+			parse_helper_response("OK 123456789\n").unwrap(),
+			"123456789"
+		);
+		assert_eq!(
+			parse_helper_response("OK stapm-limit=25.000 fast-limit=30.000").unwrap(),
+			"stapm-limit=25.000 fast-limit=30.000"
+		);
+
+		let e = parse_helper_response("ERR /etc/shadow is not allowed\n").unwrap_err();
+		assert_eq!(e.kind(), io::ErrorKind::Other);
+		assert_eq!(e.to_string(), "helper: /etc/shadow is not allowed");
+		assert_eq!(
+			parse_helper_response("HTTP/1.1 400").unwrap_err().kind(),
+			io::ErrorKind::InvalidData
+		);
+		assert_eq!(
+			parse_helper_response("").unwrap_err().kind(),
+			io::ErrorKind::InvalidData
+		);
+	}
+
+	#[test]
+	fn ryzenadj_info() {
+		let info = "\
+CPU Family: Rembrandt
+SMU BIOS Interface Version: 18
+Version: v0.14.0
+PM Table Version: 450005
+|        Name         |   Value   |     Parameter      |
+|---------------------|-----------|--------------------|
+| STAPM LIMIT         |    25.000 | stapm-limit        |
+| STAPM VALUE         |     3.644 |                    |
+| PPT LIMIT FAST      |    30.000 | fast-limit         |
+| THM LIMIT CORE      |   100.000 | tctl-temp          |
+| CCLK Boost SETPOINT |       nan | cclk setpoint      |
+| THM VALUE CORE      |  bad      | tctl-value         |
+";
+		let pairs = ryzenadj_info_to_pairs(info);
+		assert_eq!(
+			pairs,
+			"stapm-limit=25.000 fast-limit=30.000 tctl-temp=100.000"
+		);
+		assert_eq!(
+			parse_ryzenadj_pairs(&pairs),
+			[
+				("stapm-limit".to_string(), "25.000".to_string()),
+				("fast-limit".to_string(), "30.000".to_string()),
+				("tctl-temp".to_string(), "100.000".to_string()),
+			]
+		);
+		assert_eq!(ryzenadj_info_to_pairs("Version: v0.14.0\n"), "");
+		assert_eq!(parse_ryzenadj_pairs(""), []);
+	}
+}
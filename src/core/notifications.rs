@@ -0,0 +1,710 @@
+use crate::core::alert::ALERTS;
+use crate::core::alert::AlertEventKind;
+use crate::core::alert::AlertSeverity;
+use crate::core::alert::AlertState;
+use crate::core::alert::AlertStatus;
+use crate::core::sensorregistry::SENSOR_REGISTRY;
+use crate::core::sensorregistry::SensorEntry;
+use crate::core::sensorregistry::SensorId;
+use crate::core::sensorregistry::SensorSample;
+use crate::core::threshold::ThresholdLevel;
+use dbus::arg::PropMap;
+use dbus::arg::Variant;
+use dbus::blocking::Connection;
+use dbus::message::MatchRule;
+use log::error;
+use log::info;
+use log::trace;
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::mpsc;
+use std::time::Duration;
+use std::time::Instant;
+
+pub const NOTIFICATIONS_DEST: &str = "org.freedesktop.Notifications";
+pub const NOTIFICATIONS_PATH: &str = "/org/freedesktop/Notifications";
+pub const NOTIFICATIONS_INTERFACE: &str = "org.freedesktop.Notifications";
+/// `Mute for 10 min` of a notification.
+pub const NOTIFICATIONS_MUTE_TIME: Duration = Duration::from_secs(10 * 60);
+
+/// `[notifications]` of AppConfig.
+///
+/// The fired `[[alert]]` rules and the meters that cross their critical
+/// `[thresholds]` are notified. A meter that is the sensor of a rule is left
+/// to the rule.
+///
+/// ```toml
+/// [notifications]
+/// enabled = true            # default
+/// min_severity = "critical" # of the [[alert]] rules, default
+/// thresholds = true         # the critical crossings of the meters, default
+/// repeat_ms = 600000        # a rule or a meter is notified again after this time at the earliest
+/// ```
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct NotificationsAppConfig {
+	enabled: Option<bool>,
+	min_severity: Option<AlertSeverity>,
+	thresholds: Option<bool>,
+	repeat_ms: Option<u64>,
+}
+
+impl NotificationsAppConfig {
+	#[inline]
+	pub fn is_enabled(&self) -> bool {
+		self.enabled.unwrap_or(true)
+	}
+
+	#[inline]
+	pub fn get_min_severity(&self) -> AlertSeverity {
+		self.min_severity.unwrap_or(AlertSeverity::Critical)
+	}
+
+	#[inline]
+	pub fn is_thresholds_enabled(&self) -> bool {
+		self.thresholds.unwrap_or(true)
+	}
+
+	#[inline]
+	pub fn get_repeat_time(&self) -> Duration {
+		self.repeat_ms
+			.map_or(Duration::from_secs(10 * 60), Duration::from_millis)
+	}
+}
+
+/// The actions of an alert notification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationAction {
+	ShowDock,
+	Mute,
+}
+
+impl NotificationAction {
+	pub const ALL: [Self; 2] = [Self::ShowDock, Self::Mute];
+
+	/// The action key.
+	pub const fn as_str(&self) -> &'static str {
+		match self {
+			Self::ShowDock => "show-dock",
+			Self::Mute => "mute",
+		}
+	}
+
+	pub const fn title(&self) -> &'static str {
+		match self {
+			Self::ShowDock => "Show dock",
+			Self::Mute => "Mute for 10 min",
+		}
+	}
+
+	pub fn from_key(a: &str) -> Option<Self> {
+		Self::ALL.into_iter().find(|p| p.as_str() == a)
+	}
+}
+
+/// The notification server on the session bus.
+pub struct Notifications {
+	conn: Connection,
+	dest: Cow<'static, str>,
+	app_name: String,
+	timeout: Duration,
+}
+
+impl Notifications {
+	#[inline]
+	pub fn new_session(app_name: impl Into<String>) -> Result<Self, dbus::Error> {
+		Ok(Self::with_connection(
+			Connection::new_session()?,
+			NOTIFICATIONS_DEST,
+			app_name,
+		))
+	}
+
+	#[inline]
+	pub fn with_connection(
+		conn: Connection,
+		dest: impl Into<Cow<'static, str>>,
+		app_name: impl Into<String>,
+	) -> Self {
+		Self {
+			conn,
+			dest: dest.into(),
+			app_name: app_name.into(),
+			timeout: Duration::from_millis(3000),
+		}
+	}
+
+	/// Returns the ID of the notification, `replaces_id` (if not 0) is
+	/// replaced in place.
+	pub fn notify(
+		&self,
+		replaces_id: u32,
+		severity: AlertSeverity,
+		summary: &str,
+		body: &str,
+		actions: &[NotificationAction],
+	) -> Result<u32, dbus::Error> {
+		let (icon, urgency) = match severity {
+			AlertSeverity::Info => ("dialog-information", 0u8),
+			AlertSeverity::Warning => ("dialog-warning", 1),
+			AlertSeverity::Critical => ("dialog-error", 2),
+		};
+		let actions: Vec<&str> = actions
+			.iter()
+			.flat_map(|a| [a.as_str(), a.title()])
+			.collect();
+		let mut hints = PropMap::new();
+		hints.insert("urgency".into(), Variant(Box::new(urgency)));
+
+		let (id,): (u32,) = self
+			.conn
+			.with_proxy(&*self.dest, NOTIFICATIONS_PATH, self.timeout)
+			.method_call(
+				NOTIFICATIONS_INTERFACE,
+				"Notify",
+				(
+					self.app_name.as_str(),
+					replaces_id,
+					icon,
+					summary,
+					body,
+					actions,
+					hints,
+					-1i32,
+				),
+			)?;
+
+		Ok(id)
+	}
+
+	/// Calls `next` for the invoked actions, while [`Self::process`] runs.
+	pub fn connect_action_invoked(
+		&self,
+		mut next: impl FnMut(u32, &str) + Send + 'static,
+	) -> Result<(), dbus::Error> {
+		let rule = MatchRule::new_signal(NOTIFICATIONS_INTERFACE, "ActionInvoked");
+		self.conn
+			.add_match(rule, move |(id, action): (u32, String), _, _| {
+				next(id, &action);
+
+				true
+			})?;
+
+		Ok(())
+	}
+
+	#[inline]
+	pub fn process(&self, timeout: Duration) -> Result<bool, dbus::Error> {
+		self.conn.process(timeout)
+	}
+}
+
+/// `CPU is hot` / `Tctl (lm_sensors/k10temp-pci-00c3/temp1): 92.5 °C for 6.2s`.
+fn format_alert_body(state: &AlertState) -> String {
+	let sensor = state.sensor.as_deref().unwrap_or_default();
+	let (name, unit) = match SENSOR_REGISTRY.get(sensor) {
+		Some(a) => (a.name().to_string(), a.unit().symbol()),
+		None => (String::new(), ""),
+	};
+	let value = state
+		.value
+		.map_or_else(|| "-".to_string(), |a| format!("{:.1}", a));
+
+	format!(
+		"{} ({}): {} {} for {:.1}s\n{}",
+		name,
+		sensor,
+		value,
+		unit,
+		state.duration.as_secs_f64(),
+		state.rule
+	)
+}
+
+/// `Tctl (lm_sensors/k10temp-pci-00c3/temp1): 95.0 °C\ncritical threshold`.
+fn format_threshold_body(entry: &SensorEntry, sample: &SensorSample) -> String {
+	format!(
+		"{} ({}): {:.1} {}\ncritical threshold",
+		entry.name(),
+		entry.id(),
+		sample.value,
+		entry.unit().symbol()
+	)
+}
+
+/// The IDs of the last notifications, for the `ActionInvoked` signals (they
+/// are broadcast, the other IDs are not ours).
+const SENT_LEN: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum NotifyKey {
+	/// The index of the rule in `[[alert]]`.
+	Rule(usize),
+	/// A meter at its critical threshold.
+	Sensor(SensorId),
+}
+
+/// The notifications of the notifier thread.
+struct AlertNotifier {
+	notifications: Notifications,
+	config: NotificationsAppConfig,
+	/// The notification ID and the time of the notification.
+	notified: HashMap<NotifyKey, (u32, Instant)>,
+	sent: Arc<Mutex<VecDeque<u32>>>,
+	/// The `seq` of the last checked sample and its level.
+	levels: HashMap<SensorId, (u64, ThresholdLevel)>,
+}
+
+impl AlertNotifier {
+	fn new(notifications: Notifications, config: NotificationsAppConfig) -> Self {
+		Self {
+			notifications,
+			config,
+			notified: HashMap::new(),
+			sent: Default::default(),
+			levels: HashMap::new(),
+		}
+	}
+
+	/// `on_action` for the actions of our notifications, while
+	/// [`Notifications::process`] runs.
+	fn connect_actions(
+		&self,
+		on_action: impl Fn(NotificationAction) + Send + 'static,
+	) -> Result<(), dbus::Error> {
+		let sent = self.sent.clone();
+		self.notifications
+			.connect_action_invoked(move |id, action| {
+				if !sent.lock().is_ok_and(|a| a.contains(&id)) {
+					return;
+				}
+				match NotificationAction::from_key(action) {
+					Some(action) => on_action(action),
+					None => trace!("#[notifications] Unknown action {:?}", action),
+				}
+			})
+	}
+
+	fn notify(&mut self, key: NotifyKey, severity: AlertSeverity, summary: &str, body: &str) {
+		let replaces_id = match self.notified.get(&key) {
+			Some((_, time)) if time.elapsed() < self.config.get_repeat_time() => {
+				trace!("#[notifications] Repeat of {}", summary);
+				return;
+			}
+			Some((id, _)) => *id,
+			None => 0,
+		};
+
+		match self.notifications.notify(
+			replaces_id,
+			severity,
+			summary,
+			body,
+			&NotificationAction::ALL,
+		) {
+			Ok(id) => {
+				self.notified.insert(key, (id, Instant::now()));
+				let mut sent = match self.sent.lock() {
+					Ok(a) => a,
+					Err(e) => e.into_inner(),
+				};
+				if !sent.contains(&id) {
+					if sent.len() >= SENT_LEN {
+						sent.pop_front();
+					}
+					sent.push_back(id);
+				}
+			}
+			Err(e) => error!("#[notifications] {}: {}", summary, e),
+		}
+	}
+
+	fn notify_alert(&mut self, index: usize, state: &AlertState) {
+		if state.severity < self.config.get_min_severity() {
+			return;
+		}
+
+		self.notify(
+			NotifyKey::Rule(index),
+			state.severity,
+			&state.name,
+			&format_alert_body(state),
+		);
+	}
+
+	/// Notifies the meters with a new sample that crossed into
+	/// [`ThresholdLevel::Critical`]. The sensors of `states` are skipped, a
+	/// crossing while `muted` is only recorded.
+	fn check_thresholds(
+		&mut self,
+		entries: &[Arc<SensorEntry>],
+		states: &[AlertState],
+		muted: bool,
+	) {
+		if !self.config.is_thresholds_enabled() {
+			return;
+		}
+
+		for entry in entries {
+			let seq = entry.seq();
+			let last = self.levels.get(entry.id()).copied();
+			if last.is_some_and(|(a, _)| a == seq) {
+				continue;
+			}
+			let Some(sample) = entry.sample() else {
+				continue;
+			};
+			let level = entry
+				.thresholds()
+				.level(sample.value, sample.min, sample.max);
+			self.levels.insert(entry.id().clone(), (seq, level));
+
+			if level != ThresholdLevel::Critical
+				|| last.is_some_and(|(_, a)| a == ThresholdLevel::Critical)
+			{
+				continue;
+			}
+			if states
+				.iter()
+				.any(|a| a.sensor.as_deref() == Some(entry.id().as_str()))
+			{
+				trace!("#[notifications] {} is left to its alert rule", entry.id());
+				continue;
+			}
+			if muted {
+				trace!("#[notifications] Muted: {}", entry.id());
+				continue;
+			}
+
+			self.notify(
+				NotifyKey::Sensor(entry.id().clone()),
+				AlertSeverity::Critical,
+				&format!("{} is critical", entry.name()),
+				&format_threshold_body(entry, &sample),
+			);
+		}
+	}
+
+	/// The meters still critical are notified by the next
+	/// [`Self::check_thresholds`].
+	fn unmute(&mut self) {
+		self.levels
+			.retain(|_, (_, a)| *a != ThresholdLevel::Critical);
+	}
+}
+
+/// Mutes the notifications from the GUI (the notification action).
+#[derive(Debug, Clone)]
+pub struct NotificationsControl(mpsc::Sender<Duration>);
+
+impl NotificationsControl {
+	#[inline]
+	pub fn mute(&self, duration: Duration) {
+		let _e = self.0.send(duration);
+	}
+}
+
+/// Shows a notification for every fired alert of `min_severity` and above
+/// and for every meter that crosses its critical threshold. A rule or a
+/// meter is notified again only after `repeat_ms` (the same notification is
+/// replaced then), `on_action` is called from the notifier thread. The rules
+/// still firing and the meters still critical when a mute ends are notified
+/// then.
+pub fn spawn_alert_notifier(
+	config: NotificationsAppConfig,
+	app_name: &'static str,
+	on_action: impl Fn(NotificationAction) + Send + 'static,
+) -> NotificationsControl {
+	let (control_sender, control_recv) = mpsc::channel();
+	if !config.is_enabled() {
+		return NotificationsControl(control_sender);
+	}
+
+	let alerts = ALERTS.subscribe();
+	std::thread::spawn(move || {
+		let notifications = match Notifications::new_session(app_name) {
+			Ok(a) => a,
+			Err(e) => {
+				error!("#[notifications] I can't connect to the session bus: {}", e);
+
+				return;
+			}
+		};
+		let mut notifier = AlertNotifier::new(notifications, config);
+		if let Err(e) = notifier.connect_actions(on_action) {
+			error!("#[notifications] ActionInvoked: {}", e);
+		}
+
+		let mut muted_until: Option<Instant> = None;
+		loop {
+			if let Err(e) = notifier.notifications.process(Duration::from_millis(250)) {
+				error!("#[notifications] {}", e);
+			}
+
+			loop {
+				match control_recv.try_recv() {
+					Ok(duration) => {
+						info!("#[notifications] Muted for {:?}", duration);
+						muted_until = Some(Instant::now() + duration);
+					}
+					Err(mpsc::TryRecvError::Empty) => break,
+					Err(mpsc::TryRecvError::Disconnected) => return,
+				}
+			}
+
+			let states = ALERTS.states();
+			if muted_until.is_some_and(|a| Instant::now() >= a) {
+				info!("#[notifications] Unmuted");
+				muted_until = None;
+				notifier.unmute();
+
+				// the states are in the order of the rules, as the event indexes
+				for (index, state) in states.iter().enumerate() {
+					if state.status == AlertStatus::Firing {
+						notifier.notify_alert(index, state);
+					}
+				}
+			}
+
+			while let Ok(event) = alerts.try_recv() {
+				if event.kind != AlertEventKind::Fired {
+					continue;
+				}
+				if muted_until.is_some() {
+					trace!("#[notifications] Muted: {}", event.state.name);
+					continue;
+				}
+
+				notifier.notify_alert(event.index, &event.state);
+			}
+
+			notifier.check_thresholds(&SENSOR_REGISTRY.entries(), &states, muted_until.is_some());
+		}
+	});
+
+	NotificationsControl(control_sender)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::core::sensorregistry::SensorKind;
+	use crate::core::sensorregistry::SensorRegistry;
+	use crate::core::sensorregistry::SensorUnit;
+	use dbus_crossroads::Crossroads;
+
+	/// The ID and the summary of every `Notify`.
+	type Notified = Arc<Mutex<Vec<(u32, String)>>>;
+
+	#[derive(Debug, Default)]
+	struct Server {
+		next_id: u32,
+		notified: Notified,
+	}
+
+	/// A notification server on the session bus that invokes `mute` of every
+	/// notification, `None` without a bus.
+	fn standin(config: &str) -> Option<(AlertNotifier, Notified)> {
+		let server = Connection::new_session().ok()?;
+		let dest = server.unique_name().to_string();
+		let notified = Notified::default();
+
+		let mut cr = Crossroads::new();
+		let token = cr.register(NOTIFICATIONS_INTERFACE, |b| {
+			let action_invoked = b
+				.signal::<(u32, String), _>("ActionInvoked", ("id", "action_key"))
+				.msg_fn();
+			b.method(
+				"Notify",
+				(
+					"app_name",
+					"replaces_id",
+					"app_icon",
+					"summary",
+					"body",
+					"actions",
+					"hints",
+					"expire_timeout",
+				),
+				("id",),
+				move |ctx,
+				      server: &mut Server,
+				      (
+					_app_name,
+					replaces_id,
+					_icon,
+					summary,
+					_body,
+					_actions,
+					_hints,
+					_expire,
+				): (
+					String,
+					u32,
+					String,
+					String,
+					String,
+					Vec<String>,
+					PropMap,
+					i32,
+				)| {
+					let id = match replaces_id {
+						0 => {
+							server.next_id += 1;
+							server.next_id
+						}
+						id => id,
+					};
+					server.notified.lock().unwrap().push((id, summary));
+					let path = NOTIFICATIONS_PATH.into();
+					ctx.push_msg(action_invoked(&path, &(id, "mute".to_string())));
+
+					Ok((id,))
+				},
+			);
+		});
+		cr.insert(
+			NOTIFICATIONS_PATH,
+			&[token],
+			Server {
+				next_id: 0,
+				notified: notified.clone(),
+			},
+		);
+		std::thread::spawn(move || cr.serve(&server));
+
+		let notifications =
+			Notifications::with_connection(Connection::new_session().ok()?, dest, "test");
+		let config = toml::from_str(config).unwrap();
+
+		Some((AlertNotifier::new(notifications, config), notified))
+	}
+
+	fn alert_state(name: &str, severity: AlertSeverity, sensor: Option<&str>) -> AlertState {
+		AlertState {
+			name: name.to_string(),
+			rule: "cpu > 90 for 5s".to_string(),
+			severity,
+			status: AlertStatus::Firing,
+			sensor: sensor.map(str::to_string),
+			value: Some(92.5),
+			since: None,
+			duration: Duration::from_secs(6),
+		}
+	}
+
+	#[test]
+	fn alerts_and_actions() {
+		// no session bus
+		let Some((mut notifier, notified)) = standin("min_severity = \"warning\"") else {
+			return;
+		};
+		let (sender, recv) = mpsc::channel();
+		notifier
+			.connect_actions(move |a| sender.send(a).unwrap())
+			.unwrap();
+
+		notifier.notify_alert(0, &alert_state("CPU is hot", AlertSeverity::Warning, None));
+		notifier.notify_alert(1, &alert_state("Info", AlertSeverity::Info, None));
+		// repeat_ms
+		notifier.notify_alert(0, &alert_state("CPU is hot", AlertSeverity::Warning, None));
+		assert_eq!(*notified.lock().unwrap(), [(1, "CPU is hot".to_string())]);
+
+		let started = Instant::now();
+		while recv.try_recv().is_err() {
+			assert!(started.elapsed() < Duration::from_secs(5));
+			notifier
+				.notifications
+				.process(Duration::from_millis(100))
+				.unwrap();
+		}
+
+		// only the last IDs are ours
+		for index in 2..SENT_LEN + 7 {
+			notifier.notify_alert(index, &alert_state("Fan", AlertSeverity::Critical, None));
+		}
+		let sent = notifier.sent.lock().unwrap();
+		assert_eq!(sent.len(), SENT_LEN);
+		assert!(!sent.contains(&1));
+		assert!(sent.contains(&(SENT_LEN as u32 + 5)));
+	}
+
+	#[test]
+	fn thresholds() {
+		// no session bus
+		let Some((mut notifier, notified)) = standin("repeat_ms = 0") else {
+			return;
+		};
+		let registry = SensorRegistry::new();
+		registry.configure(
+			Default::default(),
+			toml::from_str("[temperature]\nwarning = 80.0\ncritical = 90.0").unwrap(),
+		);
+		let tctl = registry.register(
+			SensorId::new("lm_sensors", "k10temp-pci-00c3", "temp1"),
+			"Tctl",
+			SensorKind::Temperature,
+			SensorUnit::Celsius,
+		);
+		let nvme = registry.register(
+			SensorId::new("udisks2", "S4EWNX0R123456", "temperature"),
+			"temperature",
+			SensorKind::Temperature,
+			SensorUnit::Celsius,
+		);
+		// the rule notifies the drive
+		let states = [alert_state(
+			"Drive is hot",
+			AlertSeverity::Critical,
+			Some(nvme.id().as_str()),
+		)];
+		let check = |notifier: &mut AlertNotifier, muted: bool| {
+			notifier.check_thresholds(&registry.entries(), &states, muted);
+			notified.lock().unwrap().drain(..).collect::<Vec<_>>()
+		};
+
+		tctl.update(85.0, 0.0, 100.0);
+		nvme.update(95.0, 0.0, 100.0);
+		assert_eq!(check(&mut notifier, false), []);
+
+		tctl.update(95.0, 0.0, 100.0);
+		assert_eq!(
+			check(&mut notifier, false),
+			[(1, "Tctl is critical".to_string())]
+		);
+		// still critical, no new sample
+		tctl.update(96.0, 0.0, 100.0);
+		assert_eq!(check(&mut notifier, false), []);
+		assert_eq!(check(&mut notifier, false), []);
+
+		// crossed again, the notification is replaced
+		tctl.update(70.0, 0.0, 100.0);
+		check(&mut notifier, false);
+		tctl.update(92.0, 0.0, 100.0);
+		assert_eq!(
+			check(&mut notifier, false),
+			[(1, "Tctl is critical".to_string())]
+		);
+
+		// muted, notified when the mute ends
+		tctl.update(70.0, 0.0, 100.0);
+		check(&mut notifier, false);
+		tctl.update(92.0, 0.0, 100.0);
+		assert_eq!(check(&mut notifier, true), []);
+		notifier.unmute();
+		assert_eq!(
+			check(&mut notifier, false),
+			[(1, "Tctl is critical".to_string())]
+		);
+
+		// [notifications] thresholds = false
+		let Some((mut notifier, notified)) = standin("thresholds = false") else {
+			return;
+		};
+		notifier.check_thresholds(&registry.entries(), &[], false);
+		assert!(notified.lock().unwrap().is_empty());
+	}
+}
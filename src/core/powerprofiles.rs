@@ -0,0 +1,364 @@
+use dbus::arg::PropMap;
+use dbus::arg::RefArg;
+use dbus::blocking::Connection;
+use dbus::blocking::stdintf::org_freedesktop_dbus::Properties;
+use log::error;
+use log::info;
+use std::borrow::Cow;
+use std::fmt::Display;
+use std::sync::mpsc;
+use std::time::Duration;
+
+pub const POWER_PROFILES_DEST: &str = "net.hadess.PowerProfiles";
+pub const POWER_PROFILES_PATH: &str = "/net/hadess/PowerProfiles";
+pub const POWER_PROFILES_INTERFACE: &str = "net.hadess.PowerProfiles";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerProfile {
+	PowerSaver,
+	Balanced,
+	Performance,
+}
+
+impl PowerProfile {
+	pub const ALL: [Self; 3] = [Self::PowerSaver, Self::Balanced, Self::Performance];
+
+	pub const fn as_str(&self) -> &'static str {
+		match self {
+			Self::PowerSaver => "power-saver",
+			Self::Balanced => "balanced",
+			Self::Performance => "performance",
+		}
+	}
+
+	pub fn from_name(a: &str) -> Option<Self> {
+		Self::ALL.into_iter().find(|p| p.as_str() == a)
+	}
+}
+
+impl Display for PowerProfile {
+	#[inline]
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(self.as_str())
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PowerProfilesStatus {
+	pub active: Option<PowerProfile>,
+	/// `PerformanceDegraded`, e.g. `lap-detected` or
+	/// `high-operating-temperature`.
+	pub degraded: Option<String>,
+	/// Profiles supported by the hardware, in the order of the daemon.
+	pub available: Vec<PowerProfile>,
+}
+
+impl PowerProfilesStatus {
+	/// The next available profile after the active one, wraps around.
+	pub fn next_profile(&self) -> Option<PowerProfile> {
+		let i = self
+			.active
+			.and_then(|active| self.available.iter().position(|a| *a == active))
+			.map_or(0, |i| i + 1);
+
+		self.available
+			.get(i)
+			.or_else(|| self.available.first())
+			.copied()
+	}
+
+	/// `balanced`, `performance (lap-detected)`.
+	pub fn title(&self) -> String {
+		let active = self.active.map_or("unknown", |a| a.as_str());
+
+		match self.degraded {
+			Some(ref reason) => format!("{} ({})", active, reason),
+			None => active.to_string(),
+		}
+	}
+}
+
+/// power-profiles-daemon on the system bus.
+///
+/// The destination is not fixed, so the same code can talk to a stand-in
+/// service (`examples/powerprofiles_standin.rs`).
+pub struct PowerProfiles {
+	conn: Connection,
+	dest: Cow<'static, str>,
+	timeout: Duration,
+}
+
+impl PowerProfiles {
+	#[inline]
+	pub fn new_system() -> Result<Self, dbus::Error> {
+		Ok(Self::with_connection(
+			Connection::new_system()?,
+			POWER_PROFILES_DEST,
+		))
+	}
+
+	#[inline]
+	pub fn with_connection(conn: Connection, dest: impl Into<Cow<'static, str>>) -> Self {
+		Self {
+			conn,
+			dest: dest.into(),
+			timeout: Duration::from_millis(3000),
+		}
+	}
+
+	pub fn status(&self) -> Result<PowerProfilesStatus, dbus::Error> {
+		let proxy = self
+			.conn
+			.with_proxy(&*self.dest, POWER_PROFILES_PATH, self.timeout);
+		let active: String = proxy.get(POWER_PROFILES_INTERFACE, "ActiveProfile")?;
+		let degraded: String = proxy
+			.get(POWER_PROFILES_INTERFACE, "PerformanceDegraded")
+			.unwrap_or_default();
+		let profiles: Vec<PropMap> = proxy.get(POWER_PROFILES_INTERFACE, "Profiles")?;
+
+		Ok(PowerProfilesStatus {
+			active: PowerProfile::from_name(&active),
+			degraded: Some(degraded).filter(|a| !a.is_empty()),
+			available: profiles
+				.iter()
+				.filter_map(|a| PowerProfile::from_name(a.get("Profile")?.as_str()?))
+				.collect(),
+		})
+	}
+
+	/// Requires the `switch-profile` polkit action.
+	pub fn set_active_profile(&self, profile: PowerProfile) -> Result<(), dbus::Error> {
+		self.conn
+			.with_proxy(&*self.dest, POWER_PROFILES_PATH, self.timeout)
+			.set(
+				POWER_PROFILES_INTERFACE,
+				"ActiveProfile",
+				profile.as_str().to_string(),
+			)
+	}
+}
+
+/// Switches the profile from the GUI (tray, hotkey).
+#[derive(Debug, Clone)]
+pub struct PowerProfilesControl(mpsc::Sender<()>);
+
+impl PowerProfilesControl {
+	/// Cycles through the available profiles.
+	#[inline]
+	pub fn next_profile(&self) {
+		let _e = self.0.send(());
+	}
+}
+
+/// Polls the daemon every `sampling_time` (and right after a switch), sends
+/// the status on change, `None` if the daemon is not available.
+pub fn spawn_power_profiles_monitor(
+	sampling_time: Duration,
+) -> (
+	PowerProfilesControl,
+	async_channel::Receiver<Option<PowerProfilesStatus>>,
+) {
+	let (control_sender, control_recv) = mpsc::channel();
+	let (sender, recv) = async_channel::bounded(4);
+	std::thread::spawn(move || {
+		let power_profiles = match PowerProfiles::new_system() {
+			Ok(a) => a,
+			Err(e) => {
+				error!("#[powerprofiles] I can't connect to the system bus: {}", e);
+
+				let _e = sender.send_blocking(None);
+				return;
+			}
+		};
+
+		monitor_power_profiles(&power_profiles, sampling_time, &control_recv, &sender);
+	});
+
+	(PowerProfilesControl(control_sender), recv)
+}
+
+/// The loop of [`spawn_power_profiles_monitor`], until the control or the
+/// receiver is dropped.
+fn monitor_power_profiles(
+	power_profiles: &PowerProfiles,
+	sampling_time: Duration,
+	control_recv: &mpsc::Receiver<()>,
+	sender: &async_channel::Sender<Option<PowerProfilesStatus>>,
+) {
+	let mut last_status = None;
+	let mut is_first = true;
+	loop {
+		let status = match power_profiles.status() {
+			Ok(a) => Some(a),
+			Err(e) => {
+				if is_first || last_status.is_some() {
+					error!("#[powerprofiles] {}", e);
+				}
+
+				None
+			}
+		};
+		if is_first || status != last_status {
+			is_first = false;
+
+			if let Some(ref status) = status {
+				info!("#[powerprofiles] {}", status.title());
+			}
+			if sender.send_blocking(status.clone()).is_err() {
+				return;
+			}
+			last_status = status;
+		}
+
+		let profile = match control_recv.recv_timeout(sampling_time) {
+			Ok(()) => last_status
+				.as_ref()
+				.and_then(PowerProfilesStatus::next_profile),
+			Err(mpsc::RecvTimeoutError::Timeout) => None,
+			Err(mpsc::RecvTimeoutError::Disconnected) => return,
+		};
+		if let Some(profile) = profile {
+			info!("#[powerprofiles] Switch to {}", profile);
+			if let Err(e) = power_profiles.set_active_profile(profile) {
+				error!("#[powerprofiles] I can't switch to {}: {}", profile, e);
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use dbus::arg::Variant;
+	use dbus_crossroads::Crossroads;
+	use dbus_crossroads::MethodErr;
+
+	#[derive(Debug)]
+	struct Daemon {
+		active: String,
+		degraded: String,
+		profiles: Vec<PowerProfile>,
+	}
+
+	/// A power-profiles-daemon stand-in on the session bus (the dock uses
+	/// the system one), `None` without a bus.
+	fn standin(active: PowerProfile, profiles: &[PowerProfile]) -> Option<PowerProfiles> {
+		let server = Connection::new_session().ok()?;
+		let dest = server.unique_name().to_string();
+
+		let mut cr = Crossroads::new();
+		let token = cr.register(POWER_PROFILES_INTERFACE, |b| {
+			b.property("ActiveProfile")
+				.get(|_, daemon: &mut Daemon| Ok(daemon.active.clone()))
+				.set(|_, daemon: &mut Daemon, value: String| {
+					if !PowerProfile::from_name(&value)
+						.is_some_and(|a| daemon.profiles.contains(&a))
+					{
+						return Err(MethodErr::invalid_arg(&value));
+					}
+					daemon.active = value.clone();
+
+					Ok(Some(value))
+				});
+			b.property("PerformanceDegraded")
+				.get(|_, daemon: &mut Daemon| Ok(daemon.degraded.clone()));
+			b.property("Profiles").get(|_, daemon: &mut Daemon| {
+				let profiles: Vec<PropMap> = daemon
+					.profiles
+					.iter()
+					.map(|profile| {
+						let mut a = PropMap::new();
+						a.insert("Profile".into(), Variant(Box::new(profile.to_string())));
+						a.insert("Driver".into(), Variant(Box::new("standin".to_string())));
+
+						a
+					})
+					.collect();
+
+				Ok(profiles)
+			});
+		});
+		cr.insert(
+			POWER_PROFILES_PATH,
+			&[token],
+			Daemon {
+				active: active.to_string(),
+				degraded: "lap-detected".into(),
+				profiles: profiles.to_vec(),
+			},
+		);
+		std::thread::spawn(move || cr.serve(&server));
+
+		Some(PowerProfiles::with_connection(
+			Connection::new_session().ok()?,
+			dest,
+		))
+	}
+
+	#[test]
+	fn active_profile() {
+		// no session bus
+		let Some(power_profiles) = standin(PowerProfile::Balanced, &PowerProfile::ALL) else {
+			return;
+		};
+
+		let status = power_profiles.status().unwrap();
+		assert_eq!(
+			status,
+			PowerProfilesStatus {
+				active: Some(PowerProfile::Balanced),
+				degraded: Some("lap-detected".into()),
+				available: PowerProfile::ALL.to_vec(),
+			}
+		);
+		assert_eq!(status.title(), "balanced (lap-detected)");
+
+		power_profiles
+			.set_active_profile(PowerProfile::Performance)
+			.unwrap();
+		assert_eq!(
+			power_profiles.status().unwrap().active,
+			Some(PowerProfile::Performance)
+		);
+	}
+
+	#[test]
+	fn cycling() {
+		// no session bus
+		let Some(power_profiles) = standin(
+			PowerProfile::Balanced,
+			&[PowerProfile::PowerSaver, PowerProfile::Balanced],
+		) else {
+			return;
+		};
+		// no performance profile on this hardware
+		assert!(
+			power_profiles
+				.set_active_profile(PowerProfile::Performance)
+				.is_err()
+		);
+
+		let (control_sender, control_recv) = mpsc::channel();
+		let (sender, recv) = async_channel::bounded(4);
+		let monitor = std::thread::spawn(move || {
+			monitor_power_profiles(
+				&power_profiles,
+				Duration::from_secs(5),
+				&control_recv,
+				&sender,
+			);
+		});
+		let control = PowerProfilesControl(control_sender);
+		let active = || recv.recv_blocking().unwrap().unwrap().active;
+
+		assert_eq!(active(), Some(PowerProfile::Balanced));
+		// wraps around
+		control.next_profile();
+		assert_eq!(active(), Some(PowerProfile::PowerSaver));
+		control.next_profile();
+		assert_eq!(active(), Some(PowerProfile::Balanced));
+
+		drop(control);
+		monitor.join().unwrap();
+	}
+}
@@ -0,0 +1,805 @@
+//! Recording files: a header with the host and the sensors, then the samples
+//! by their stable sensor IDs. JSON Lines, one object per line:
+//!
+//! ```text
+//! {"type":"header","app":"machinepmmeter","version":"0.1.0","reason":"alert: CPU is hot","host":"box","kernel":"6.9.7","os":"Arch Linux","started":"2026-10-19T01:54:10.250Z","sensors":[{"id":"lm_sensors/k10temp-pci-00c3/temp1","name":"Tctl","kind":"temperature","unit":"°C"}]}
+//! {"type":"sample","time":"2026-10-19T01:54:10.250Z","sensor":"lm_sensors/k10temp-pci-00c3/temp1","value":92.5,"unit":"°C"}
+//! {"type":"sensor","id":"frametime/active/fps","name":"FPS","kind":"fps","unit":"FPS"}
+//! ```
+//!
+//! or CSV, the header in `#` comments:
+//!
+//! ```text
+//! # app: machinepmmeter 0.1.0
+//! # reason: hotkey
+//! # host: box
+//! # kernel: 6.9.7
+//! # os: Arch Linux
+//! # started: 2026-10-19T01:54:10.250Z
+//! # sensor,lm_sensors/k10temp-pci-00c3/temp1,Tctl,temperature,°C
+//! time,sensor,value,unit
+//! 2026-10-19T01:54:10.250Z,lm_sensors/k10temp-pci-00c3/temp1,92.5,°C
+//! # sensor,frametime/active/fps,FPS,fps,FPS
+//! ```
+//!
+//! A sensor registered after the start of a session recording is described
+//! by a `sensor` line before its first sample.
+
+use crate::core::flightrecorder::FlightRecorderConfig;
+use crate::core::sensorregistry::SENSOR_REGISTRY;
+use crate::core::sensorregistry::SensorEntry;
+use crate::core::steamgame::CurrentSteamGame;
+use log::error;
+use log::info;
+use serde::Deserialize;
+use std::fmt::Write as FmtWrite;
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+use sys_metrics::host::get_hostname;
+use sys_metrics::host::get_kernel_version;
+
+/// `[recording]` of AppConfig.
+///
+/// ```toml
+/// [recording]
+/// dir = "/home/user/recordings"  # default: the data dir of the app
+/// format = "jsonl"        # jsonl (default) or csv, `--record` follows the extension
+/// interval_ms = 1000      # of the session recordings (Shift+F12, tray, `--record`), default
+///
+/// [recording.flight_recorder]
+/// enabled = true          # default
+/// minutes = 5             # kept in memory, default
+/// interval_ms = 1000      # default
+/// on_alert = "critical"   # dumped when an alert of this severity fires, default
+/// ```
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct RecordingAppConfig {
+	dir: Option<PathBuf>,
+	#[serde(default)]
+	format: RecordingFormat,
+	interval_ms: Option<u64>,
+	#[serde(default)]
+	flight_recorder: FlightRecorderConfig,
+}
+
+impl RecordingAppConfig {
+	#[inline]
+	pub fn get_dir(&self) -> Option<&Path> {
+		self.dir.as_deref()
+	}
+
+	#[inline]
+	pub const fn get_format(&self) -> RecordingFormat {
+		self.format
+	}
+
+	#[inline]
+	pub fn get_interval(&self) -> Duration {
+		Duration::from_millis(self.interval_ms.unwrap_or(1000).max(50))
+	}
+
+	#[inline]
+	pub const fn get_flight_recorder(&self) -> &FlightRecorderConfig {
+		&self.flight_recorder
+	}
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordingFormat {
+	#[default]
+	Jsonl,
+	Csv,
+}
+
+impl RecordingFormat {
+	#[inline]
+	pub const fn extension(&self) -> &'static str {
+		match self {
+			Self::Jsonl => "jsonl",
+			Self::Csv => "csv",
+		}
+	}
+
+	/// By the extension of the file, `None` if unknown.
+	pub fn of_path(path: &Path) -> Option<Self> {
+		match path.extension()?.to_str()? {
+			a if a.eq_ignore_ascii_case("csv") => Some(Self::Csv),
+			a if a.eq_ignore_ascii_case("jsonl") || a.eq_ignore_ascii_case("json") => {
+				Some(Self::Jsonl)
+			}
+			_ => None,
+		}
+	}
+}
+
+/// `2026-10-19T01:54:10.250Z`.
+pub fn format_utc(time: SystemTime) -> String {
+	let since = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+	let (days, secs) = (since.as_secs() / 86400, since.as_secs() % 86400);
+	let (year, month, day) = civil_from_days(days as i64);
+
+	format!(
+		"{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+		year,
+		month,
+		day,
+		secs / 3600,
+		secs / 60 % 60,
+		secs % 60,
+		since.subsec_millis()
+	)
+}
+
+/// The date of the days since 1970-01-01 (proleptic Gregorian).
+const fn civil_from_days(days: i64) -> (i64, u32, u32) {
+	let z = days + 719468;
+	let era = z.div_euclid(146097);
+	let doe = z.rem_euclid(146097);
+	let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+	let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+	let mp = (5 * doy + 2) / 153;
+	let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+	let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+	let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+	(year, month, day)
+}
+
+/// `flight-20261019-015410-250-alert.jsonl` in `dir` (with milliseconds,
+/// `-2`, `-3`... if it exists anyway), `tag` is sanitized.
+pub fn recording_path(dir: &Path, prefix: &str, tag: &str, ext: &str) -> PathBuf {
+	let time = format_utc(SystemTime::now());
+	// 2026-10-19T01:54:10.250Z
+	let stamp: String = time
+		.chars()
+		.take(23)
+		.filter(|a| a.is_ascii_digit() || *a == 'T' || *a == '.')
+		.map(|a| if a.is_ascii_digit() { a } else { '-' })
+		.collect();
+	// `alert: CPU is hot` - `alert_cpu_is_hot`
+	let tag = tag
+		.split(|a: char| !a.is_ascii_alphanumeric())
+		.filter(|a| !a.is_empty())
+		.collect::<Vec<_>>()
+		.join("_")
+		.to_ascii_lowercase();
+	let tag = &tag[..tag.len().min(48)];
+
+	let name = match tag.is_empty() {
+		true => format!("{}-{}", prefix, stamp),
+		false => format!("{}-{}-{}", prefix, stamp, tag),
+	};
+	(1u32..)
+		.map(|i| match i {
+			1 => dir.join(format!("{}.{}", name, ext)),
+			i => dir.join(format!("{}-{}.{}", name, i, ext)),
+		})
+		.find(|a| !a.exists())
+		.unwrap_or_else(|| dir.join(format!("{}.{}", name, ext)))
+}
+
+/// `PRETTY_NAME` of `/etc/os-release`.
+fn os_release_name() -> Option<String> {
+	let data = std::fs::read_to_string("/etc/os-release").ok()?;
+
+	data.lines()
+		.find_map(|a| a.strip_prefix("PRETTY_NAME="))
+		.map(|a| a.trim().trim_matches('"').to_string())
+}
+
+/// A CSV field, quoted if necessary.
+fn push_csv_field(out: &mut String, a: &str) {
+	match a.contains([',', '"', '\n', '\r']) {
+		true => {
+			out.push('"');
+			out.push_str(&a.replace('"', "\"\""));
+			out.push('"');
+		}
+		false => out.push_str(a),
+	}
+}
+
+/// A JSON string with the quotes.
+fn push_json_str(out: &mut String, a: &str) {
+	out.push('"');
+	for c in a.chars() {
+		match c {
+			'"' => out.push_str("\\\""),
+			'\\' => out.push_str("\\\\"),
+			'\n' => out.push_str("\\n"),
+			'\r' => out.push_str("\\r"),
+			'\t' => out.push_str("\\t"),
+			c if (c as u32) < 0x20 => {
+				let _e = write!(out, "\\u{:04x}", c as u32);
+			}
+			c => out.push(c),
+		}
+	}
+	out.push('"');
+}
+
+impl RecordingSensor {
+	/// `{"id":...,"name":...,"kind":...,"unit":...}`, `prefix` after the `{`.
+	fn push_json(&self, out: &mut String, prefix: &str) {
+		out.push('{');
+		out.push_str(prefix);
+		out.push_str("\"id\":");
+		push_json_str(out, &self.id);
+		out.push_str(",\"name\":");
+		push_json_str(out, &self.name);
+		out.push_str(",\"kind\":");
+		push_json_str(out, self.kind);
+		out.push_str(",\"unit\":");
+		push_json_str(out, self.unit);
+		out.push('}');
+	}
+
+	/// `# sensor,<id>,<name>,<kind>,<unit>`.
+	fn push_csv(&self, out: &mut String) {
+		out.push_str("# sensor,");
+		push_csv_field(out, &self.id);
+		out.push(',');
+		push_csv_field(out, &self.name);
+		let _e = write!(out, ",{},", self.kind);
+		push_csv_field(out, self.unit);
+	}
+}
+
+/// The host and the sensors of a recording.
+#[derive(Debug, Clone)]
+pub struct RecordingHeader {
+	pub app: &'static str,
+	pub version: &'static str,
+	/// Why it was written (`hotkey`, `alert: CPU is hot`).
+	pub reason: String,
+	pub host: Option<String>,
+	pub kernel: Option<String>,
+	pub os: Option<String>,
+	/// The title of the running Steam game.
+	pub game: Option<String>,
+	pub started: SystemTime,
+	pub sensors: Vec<RecordingSensor>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RecordingSensor {
+	pub id: String,
+	pub name: String,
+	pub kind: &'static str,
+	pub unit: &'static str,
+}
+
+impl From<&SensorEntry> for RecordingSensor {
+	#[inline]
+	fn from(a: &SensorEntry) -> Self {
+		Self {
+			id: a.id().to_string(),
+			name: a.name().to_string(),
+			kind: a.kind().as_str(),
+			unit: a.unit().symbol(),
+		}
+	}
+}
+
+impl RecordingHeader {
+	/// With the current host and game.
+	pub fn new(
+		reason: impl Into<String>,
+		started: SystemTime,
+		game: &CurrentSteamGame,
+		sensors: Vec<RecordingSensor>,
+	) -> Self {
+		Self {
+			app: env!("CARGO_PKG_NAME"),
+			version: env!("CARGO_PKG_VERSION"),
+			reason: reason.into(),
+			host: get_hostname().ok(),
+			kernel: get_kernel_version().ok(),
+			os: os_release_name(),
+			game: game.get().map(|a| a.title()),
+			started,
+			sensors,
+		}
+	}
+
+	fn to_json(&self) -> String {
+		let mut out = String::with_capacity(256 + self.sensors.len() * 96);
+		out.push_str("{\"type\":\"header\",\"app\":");
+		push_json_str(&mut out, self.app);
+		out.push_str(",\"version\":");
+		push_json_str(&mut out, self.version);
+		out.push_str(",\"reason\":");
+		push_json_str(&mut out, &self.reason);
+		for (key, value) in [
+			("host", &self.host),
+			("kernel", &self.kernel),
+			("os", &self.os),
+			("game", &self.game),
+		] {
+			let _e = write!(out, ",\"{}\":", key);
+			match value {
+				Some(value) => push_json_str(&mut out, value),
+				None => out.push_str("null"),
+			}
+		}
+		out.push_str(",\"started\":");
+		push_json_str(&mut out, &format_utc(self.started));
+		out.push_str(",\"sensors\":[");
+		for (i, sensor) in self.sensors.iter().enumerate() {
+			if i != 0 {
+				out.push(',');
+			}
+			sensor.push_json(&mut out, "");
+		}
+		out.push_str("]}");
+
+		out
+	}
+
+	fn to_csv(&self) -> String {
+		let mut out = String::with_capacity(256 + self.sensors.len() * 96);
+		let _e = writeln!(out, "# app: {} {}", self.app, self.version);
+		for (key, value) in [
+			("reason", Some(&self.reason)),
+			("host", self.host.as_ref()),
+			("kernel", self.kernel.as_ref()),
+			("os", self.os.as_ref()),
+			("game", self.game.as_ref()),
+		] {
+			// one line per key
+			let _e = match value {
+				Some(a) => writeln!(out, "# {}: {}", key, a.replace(['\n', '\r'], " ")),
+				None => writeln!(out, "# {}:", key),
+			};
+		}
+		let _e = writeln!(out, "# started: {}", format_utc(self.started));
+		for sensor in &self.sensors {
+			sensor.push_csv(&mut out);
+			out.push('\n');
+		}
+		out.push_str("time,sensor,value,unit");
+
+		out
+	}
+}
+
+/// Writes a recording line by line.
+pub struct RecordingWriter<W: Write> {
+	out: W,
+	format: RecordingFormat,
+	line: String,
+}
+
+impl RecordingWriter<BufWriter<File>> {
+	/// Creates the file and its directory.
+	pub fn create(
+		path: &Path,
+		format: RecordingFormat,
+		header: &RecordingHeader,
+	) -> io::Result<Self> {
+		if let Some(dir) = path.parent() {
+			std::fs::create_dir_all(dir)?;
+		}
+
+		Self::new(BufWriter::new(File::create(path)?), format, header)
+	}
+}
+
+impl<W: Write> RecordingWriter<W> {
+	pub fn new(mut out: W, format: RecordingFormat, header: &RecordingHeader) -> io::Result<Self> {
+		let mut line = match format {
+			RecordingFormat::Jsonl => header.to_json(),
+			RecordingFormat::Csv => header.to_csv(),
+		};
+		line.push('\n');
+		out.write_all(line.as_bytes())?;
+
+		Ok(Self { out, format, line })
+	}
+
+	/// The non-finite values are skipped.
+	pub fn write_sample(
+		&mut self,
+		time: SystemTime,
+		sensor: &RecordingSensor,
+		value: f64,
+	) -> io::Result<()> {
+		if !value.is_finite() {
+			return Ok(());
+		}
+		let line = &mut self.line;
+		line.clear();
+		match self.format {
+			RecordingFormat::Jsonl => {
+				line.push_str("{\"type\":\"sample\",\"time\":");
+				push_json_str(line, &format_utc(time));
+				line.push_str(",\"sensor\":");
+				push_json_str(line, &sensor.id);
+				let _e = write!(line, ",\"value\":{}", value);
+				line.push_str(",\"unit\":");
+				push_json_str(line, sensor.unit);
+				line.push_str("}\n");
+			}
+			RecordingFormat::Csv => {
+				line.push_str(&format_utc(time));
+				line.push(',');
+				push_csv_field(line, &sensor.id);
+				let _e = write!(line, ",{},", value);
+				push_csv_field(line, sensor.unit);
+				line.push('\n');
+			}
+		}
+
+		self.out.write_all(line.as_bytes())
+	}
+
+	/// Describes a sensor that is not in the header.
+	pub fn write_sensor(&mut self, sensor: &RecordingSensor) -> io::Result<()> {
+		let line = &mut self.line;
+		line.clear();
+		match self.format {
+			RecordingFormat::Jsonl => sensor.push_json(line, "\"type\":\"sensor\","),
+			RecordingFormat::Csv => sensor.push_csv(line),
+		}
+		line.push('\n');
+
+		self.out.write_all(line.as_bytes())
+	}
+
+	#[inline]
+	pub fn flush(&mut self) -> io::Result<()> {
+		self.out.flush()
+	}
+}
+
+enum RecordingCommand {
+	/// The default file in the recordings dir if `None`.
+	Start(Option<PathBuf>),
+	Stop,
+	Toggle,
+}
+
+/// Starts and stops the session recording (the hotkey, the tray, `--record`).
+#[derive(Debug, Clone)]
+pub struct RecordingControl(mpsc::Sender<RecordingCommand>);
+
+impl RecordingControl {
+	#[inline]
+	pub fn start(&self, path: Option<PathBuf>) {
+		let _e = self.0.send(RecordingCommand::Start(path));
+	}
+
+	#[inline]
+	pub fn stop(&self) {
+		let _e = self.0.send(RecordingCommand::Stop);
+	}
+
+	#[inline]
+	pub fn toggle(&self) {
+		let _e = self.0.send(RecordingCommand::Toggle);
+	}
+}
+
+/// The running session recording.
+struct Session {
+	path: PathBuf,
+	writer: RecordingWriter<BufWriter<File>>,
+	/// By the registry index, the sensors registered later are added (and
+	/// described in the file).
+	sensors: Vec<(RecordingSensor, u64)>,
+}
+
+impl Session {
+	fn start(path: PathBuf, format: RecordingFormat, game: &CurrentSteamGame) -> io::Result<Self> {
+		let entries = SENSOR_REGISTRY.entries();
+		let sensors: Vec<_> = entries
+			.iter()
+			.map(|a| (RecordingSensor::from(&**a), 0))
+			.collect();
+		let header = RecordingHeader::new(
+			"session",
+			SystemTime::now(),
+			game,
+			sensors.iter().map(|(a, _)| a.clone()).collect(),
+		);
+		let writer = RecordingWriter::create(&path, format, &header)?;
+
+		Ok(Self {
+			path,
+			writer,
+			sensors,
+		})
+	}
+
+	/// The new samples of all sensors.
+	fn write_next(&mut self) -> io::Result<()> {
+		let entries = SENSOR_REGISTRY.entries();
+		for entry in &entries[self.sensors.len().min(entries.len())..] {
+			let sensor = RecordingSensor::from(&**entry);
+			self.writer.write_sensor(&sensor)?;
+			self.sensors.push((sensor, 0));
+		}
+		for (entry, (sensor, last_seq)) in entries.iter().zip(&mut self.sensors) {
+			let seq = entry.seq();
+			if seq == *last_seq {
+				continue;
+			}
+			*last_seq = seq;
+			if let Some(sample) = entry.sample() {
+				self.writer
+					.write_sample(sample.time, sensor, sample.value)?;
+			}
+		}
+
+		// a crash loses one interval at most
+		self.writer.flush()
+	}
+}
+
+/// Writes the samples of all sensors every `[recording] interval_ms` while
+/// started, sends the path of the recording on start and `None` on stop.
+pub fn spawn_session_recorder(
+	config: RecordingAppConfig,
+	dir: PathBuf,
+	game: CurrentSteamGame,
+) -> (RecordingControl, async_channel::Receiver<Option<PathBuf>>) {
+	let (control_sender, control_recv) = mpsc::channel();
+	let (sender, recv) = async_channel::bounded(4);
+	std::thread::spawn(move || {
+		let interval = config.get_interval();
+		let mut session: Option<Session> = None;
+		let mut next_tick = Instant::now();
+		loop {
+			let command = match session {
+				Some(_) => {
+					match control_recv
+						.recv_timeout(next_tick.saturating_duration_since(Instant::now()))
+					{
+						Ok(a) => Some(a),
+						Err(mpsc::RecvTimeoutError::Timeout) => None,
+						Err(mpsc::RecvTimeoutError::Disconnected) => return,
+					}
+				}
+				None => match control_recv.recv() {
+					Ok(a) => Some(a),
+					Err(_) => return,
+				},
+			};
+
+			let command = match (command, session.is_some()) {
+				(Some(RecordingCommand::Toggle), true) => Some(RecordingCommand::Stop),
+				(Some(RecordingCommand::Toggle), false) => Some(RecordingCommand::Start(None)),
+				(command, _) => command,
+			};
+			match command {
+				Some(RecordingCommand::Start(path)) => {
+					if let Some(old) = session.take() {
+						info!("#[recording] Stopped {:?}", old.path);
+					}
+					let (path, format) = match path {
+						Some(path) => {
+							let format =
+								RecordingFormat::of_path(&path).unwrap_or(config.get_format());
+
+							(path, format)
+						}
+						None => {
+							let format = config.get_format();
+
+							(
+								recording_path(&dir, "session", "", format.extension()),
+								format,
+							)
+						}
+					};
+					match Session::start(path.clone(), format, &game) {
+						Ok(a) => {
+							info!("#[recording] Started {:?}", path);
+							session = Some(a);
+							next_tick = Instant::now();
+							let _e = sender.send_blocking(Some(path));
+						}
+						Err(e) => {
+							error!("#[recording] I can't create {:?}: {}", path, e);
+							let _e = sender.send_blocking(None);
+						}
+					}
+				}
+				Some(RecordingCommand::Stop) => {
+					if let Some(mut old) = session.take() {
+						let _e = old.writer.flush();
+						info!("#[recording] Stopped {:?}", old.path);
+					}
+					let _e = sender.send_blocking(None);
+				}
+				Some(RecordingCommand::Toggle) | None => {}
+			}
+
+			if let Some(ref mut a) = session
+				&& Instant::now() >= next_tick
+			{
+				if let Err(e) = a.write_next() {
+					error!("#[recording] I can't write {:?}: {}", a.path, e);
+					session = None;
+					let _e = sender.send_blocking(None);
+					continue;
+				}
+				next_tick += interval;
+				if next_tick < Instant::now() {
+					next_tick = Instant::now() + interval;
+				}
+			}
+		}
+	});
+
+	(RecordingControl(control_sender), recv)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn header() -> RecordingHeader {
+		RecordingHeader {
+			app: "machinepmmeter",
+			version: "0.1.0",
+			reason: "alert: CPU is \"hot\"".into(),
+			host: Some("box".into()),
+			kernel: Some("6.9.7".into()),
+			os: None,
+			game: Some("Cyberpunk 2077 (Proton)".into()),
+			started: UNIX_EPOCH + Duration::from_millis(1792374850250),
+			sensors: vec![sensor()],
+		}
+	}
+
+	fn sensor() -> RecordingSensor {
+		RecordingSensor {
+			id: "lm_sensors/k10temp-pci-00c3/temp1".into(),
+			name: "Tctl, \"die\"".into(),
+			kind: "temperature",
+			unit: "°C",
+		}
+	}
+
+	fn write(format: RecordingFormat) -> String {
+		let time = UNIX_EPOCH + Duration::from_millis(1792374851250);
+		let mut writer = RecordingWriter::new(Vec::new(), format, &header()).unwrap();
+		writer.write_sample(time, &sensor(), 92.5).unwrap();
+		writer.write_sample(time, &sensor(), f64::NAN).unwrap();
+		writer
+			.write_sensor(&RecordingSensor {
+				id: "frametime/active/fps".into(),
+				name: "FPS".into(),
+				kind: "fps",
+				unit: "FPS",
+			})
+			.unwrap();
+
+		String::from_utf8(writer.out).unwrap()
+	}
+
+	#[test]
+	fn dates() {
+		assert_eq!(format_utc(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+		assert_eq!(
+			format_utc(UNIX_EPOCH + Duration::from_millis(1709251199999)),
+			"2024-02-29T23:59:59.999Z"
+		);
+		assert_eq!(
+			format_utc(UNIX_EPOCH + Duration::from_millis(1792374850250)),
+			"2026-10-19T01:54:10.250Z"
+		);
+		assert_eq!(civil_from_days(0), (1970, 1, 1));
+		assert_eq!(civil_from_days(-1), (1969, 12, 31));
+		assert_eq!(civil_from_days(11016), (2000, 2, 29));
+		// not a leap year
+		assert_eq!(civil_from_days(47540), (2100, 2, 28));
+		assert_eq!(civil_from_days(47541), (2100, 3, 1));
+	}
+
+	#[test]
+	fn escaping() {
+		let mut out = String::new();
+		push_csv_field(&mut out, "plain");
+		out.push(',');
+		push_csv_field(&mut out, "a, \"b\"");
+		out.push(',');
+		push_csv_field(&mut out, "two\nlines");
+		assert_eq!(out, "plain,\"a, \"\"b\"\"\",\"two\nlines\"");
+
+		let mut out = String::new();
+		push_json_str(&mut out, "a \"b\" \\ \n\r\t\u{1} °C");
+		assert_eq!(out, r#""a \"b\" \\ \n\r\t\u0001 °C""#);
+	}
+
+	#[test]
+	fn jsonl() {
+		assert_eq!(
+			write(RecordingFormat::Jsonl),
+			concat!(
+				r#"{"type":"header","app":"machinepmmeter","version":"0.1.0","reason":"alert: CPU is \"hot\"","host":"box","kernel":"6.9.7","os":null,"game":"Cyberpunk 2077 (Proton)","started":"2026-10-19T01:54:10.250Z","sensors":[{"id":"lm_sensors/k10temp-pci-00c3/temp1","name":"Tctl, \"die\"","kind":"temperature","unit":"°C"}]}"#,
+				"\n",
+				r#"{"type":"sample","time":"2026-10-19T01:54:11.250Z","sensor":"lm_sensors/k10temp-pci-00c3/temp1","value":92.5,"unit":"°C"}"#,
+				"\n",
+				r#"{"type":"sensor","id":"frametime/active/fps","name":"FPS","kind":"fps","unit":"FPS"}"#,
+				"\n",
+			)
+		);
+	}
+
+	#[test]
+	fn csv() {
+		assert_eq!(
+			write(RecordingFormat::Csv),
+			"\
+# app: machinepmmeter 0.1.0
+# reason: alert: CPU is \"hot\"
+# host: box
+# kernel: 6.9.7
+# os:
+# game: Cyberpunk 2077 (Proton)
+# started: 2026-10-19T01:54:10.250Z
+# sensor,lm_sensors/k10temp-pci-00c3/temp1,\"Tctl, \"\"die\"\"\",temperature,°C
+time,sensor,value,unit
+2026-10-19T01:54:11.250Z,lm_sensors/k10temp-pci-00c3/temp1,92.5,°C
+# sensor,frametime/active/fps,FPS,fps,FPS
+"
+		);
+	}
+
+	#[test]
+	fn formats_and_paths() {
+		for (path, format) in [
+			("a.csv", Some(RecordingFormat::Csv)),
+			("a.CSV", Some(RecordingFormat::Csv)),
+			("/tmp/a.jsonl", Some(RecordingFormat::Jsonl)),
+			("a.json", Some(RecordingFormat::Jsonl)),
+			("a.txt", None),
+			("a", None),
+		] {
+			assert_eq!(
+				RecordingFormat::of_path(Path::new(path)),
+				format,
+				"{}",
+				path
+			);
+		}
+
+		let dir = Path::new("/nonexistent");
+		let path = recording_path(dir, "flight", "alert: CPU is hot!", "jsonl");
+		let name = path.file_name().unwrap().to_str().unwrap();
+		assert_eq!(path.parent(), Some(dir));
+		// flight-20261019-015410-250-alert_cpu_is_hot.jsonl
+		assert_eq!(
+			name.len(),
+			"flight-20261019-015410-250-alert_cpu_is_hot.jsonl".len()
+		);
+		assert!(name.starts_with("flight-"));
+		assert!(name.ends_with("-alert_cpu_is_hot.jsonl"));
+		assert!(name[7..26].bytes().all(|a| a.is_ascii_digit() || a == b'-'));
+
+		let name = recording_path(dir, "session", "", "csv");
+		assert!(name.to_str().unwrap().ends_with(".csv"));
+		assert_eq!(
+			name.file_name().unwrap().len(),
+			"session-20261019-015410-250.csv".len()
+		);
+		let name = recording_path(dir, "flight", &"x".repeat(100), "csv");
+		assert!(
+			name.to_str()
+				.unwrap()
+				.ends_with(&format!("-{}.csv", "x".repeat(48)))
+		);
+	}
+}
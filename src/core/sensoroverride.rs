@@ -0,0 +1,229 @@
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::ops::Deref;
+
+/// A regex of `[sensors]`, compiled when AppConfig is loaded.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(try_from = "String")]
+pub struct SensorRegex(Regex);
+
+impl TryFrom<String> for SensorRegex {
+	type Error = regex::Error;
+
+	#[inline]
+	fn try_from(a: String) -> Result<Self, Self::Error> {
+		Regex::new(&a).map(Self)
+	}
+}
+
+impl Deref for SensorRegex {
+	type Target = Regex;
+
+	#[inline]
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
+
+/// `[sensors]` of AppConfig, keyed by sensor ID
+/// (`<backend>/<device>/<feature>`, see [`crate::core::sensorregistry`] and
+/// the trace log for the IDs), applied by the registry to the meters of all
+/// pages.
+///
+/// ```toml
+/// [sensors]
+/// include = ["^lm_sensors/(k10temp|amdgpu)"] # all if not set
+/// exclude = ["/in[0-9]+$"]
+///
+/// [sensors.override."lm_sensors/k10temp-pci-00c3/temp1"]
+/// name = "CPU Tdie"
+/// offset = -10.0    # value * scale + offset, Tctl to Tdie
+/// max = 95.0        # in calibrated units
+///
+/// [sensors.override."lm_sensors/nvme-pci-0100/temp2"]
+/// hide = true
+///
+/// [sensors.override."fans/nct6798-platform-nct6775.656/fan2"]
+/// name = "Pump"
+/// crit = 800.0      # the critical threshold of the meter
+///
+/// [sensors.alias] # short names for the alert rules
+/// "cpu.tctl" = "lm_sensors/k10temp-pci-00c3/temp1"
+/// ```
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct SensorsAppConfig {
+	#[serde(default)]
+	include: Vec<SensorRegex>,
+	#[serde(default)]
+	exclude: Vec<SensorRegex>,
+	#[serde(default)]
+	r#override: HashMap<String, SensorOverride>,
+	#[serde(default)]
+	alias: HashMap<String, String>,
+}
+
+impl SensorsAppConfig {
+	#[inline]
+	pub fn get_override(&self, id: &str) -> Option<&SensorOverride> {
+		self.r#override.get(id)
+	}
+
+	/// Not hidden, matches `include` (if set) and does not match `exclude`.
+	pub fn is_visible(&self, id: &str) -> bool {
+		if self.get_override(id).is_some_and(SensorOverride::is_hidden) {
+			return false;
+		}
+		if !self.include.is_empty() && !self.include.iter().any(|a| a.is_match(id)) {
+			return false;
+		}
+
+		!self.exclude.iter().any(|a| a.is_match(id))
+	}
+
+	/// `[sensors.alias]`, alias to sensor ID.
+	#[inline]
+	pub const fn get_aliases(&self) -> &HashMap<String, String> {
+		&self.alias
+	}
+
+	/// IDs of `[sensors.override]`, to report the ones that do not exist.
+	#[inline]
+	pub fn override_ids(&self) -> impl Iterator<Item = &str> {
+		self.r#override.keys().map(String::as_str)
+	}
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct SensorOverride {
+	name: Option<String>,
+	hide: Option<bool>,
+	min: Option<f64>,
+	max: Option<f64>,
+	crit: Option<f64>,
+	scale: Option<f64>,
+	offset: Option<f64>,
+}
+
+impl SensorOverride {
+	#[inline]
+	pub fn get_name(&self) -> Option<&str> {
+		self.name.as_deref()
+	}
+
+	#[inline]
+	pub fn is_hidden(&self) -> bool {
+		self.hide.unwrap_or(false)
+	}
+
+	#[inline]
+	pub const fn get_min(&self) -> Option<f64> {
+		self.min
+	}
+
+	#[inline]
+	pub const fn get_max(&self) -> Option<f64> {
+		self.max
+	}
+
+	#[inline]
+	pub const fn get_crit(&self) -> Option<f64> {
+		self.crit
+	}
+
+	/// `value * scale + offset`.
+	#[inline]
+	pub fn calibrate(&self, value: f64) -> f64 {
+		value * self.scale.unwrap_or(1.0) + self.offset.unwrap_or(0.0)
+	}
+
+	/// The min/max of the backend, replaced by the set ones.
+	#[inline]
+	pub fn range(&self, min: f64, max: f64) -> (f64, f64) {
+		(self.min.unwrap_or(min), self.max.unwrap_or(max))
+	}
+
+	/// A calibrated value within a span of the range on both sides (true
+	/// without a full range), values further out are errors of the sensor.
+	pub fn is_in_range(&self, value: f64) -> bool {
+		match (self.min, self.max) {
+			(Some(min), Some(max)) => {
+				let span = (max - min).abs();
+
+				value >= min.min(max) - span && value <= min.max(max) + span
+			}
+			_ => true,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn sensors_config() -> SensorsAppConfig {
+		toml::from_str(
+			r#"
+			include = ["^(lm_sensors|fans)/"]
+			exclude = ["/in[0-9]+$"]
+
+			[override."lm_sensors/k10temp-pci-00c3/temp1"]
+			name = "CPU Tdie"
+			offset = -10.0
+			min = 20.0
+			max = 95.0
+
+			[override."fans/nct6798-isa-0290/fan1"]
+			hide = true
+			crit = 800.0
+			"#,
+		)
+		.unwrap()
+	}
+
+	#[test]
+	fn visibility() {
+		let config = sensors_config();
+		assert!(config.is_visible("lm_sensors/k10temp-pci-00c3/temp1"));
+		assert!(config.is_visible("fans/nct6798-isa-0290/fan2"));
+		assert!(!config.is_visible("fans/nct6798-isa-0290/fan1"));
+		assert!(!config.is_visible("lm_sensors/nct6798-isa-0290/in0"));
+		assert!(!config.is_visible("rapl/intel-rapl:0/package-0"));
+
+		let config = SensorsAppConfig::default();
+		assert!(config.is_visible("rapl/intel-rapl:0/package-0"));
+	}
+
+	#[test]
+	fn overrides() {
+		let config = sensors_config();
+		let over = config
+			.get_override("lm_sensors/k10temp-pci-00c3/temp1")
+			.unwrap();
+		assert_eq!(over.get_name(), Some("CPU Tdie"));
+		assert_eq!(over.calibrate(60.0), 50.0);
+		assert_eq!(over.range(0.0, 100.0), (20.0, 95.0));
+		assert_eq!(over.get_crit(), None);
+
+		let over = config.get_override("fans/nct6798-isa-0290/fan1").unwrap();
+		assert_eq!(over.calibrate(1200.0), 1200.0);
+		assert_eq!(over.range(0.0, 2000.0), (0.0, 2000.0));
+		assert_eq!(over.get_crit(), Some(800.0));
+	}
+
+	#[test]
+	fn in_range() {
+		let config = sensors_config();
+		// 20..=95, a span of 75
+		let over = config
+			.get_override("lm_sensors/k10temp-pci-00c3/temp1")
+			.unwrap();
+		assert!(over.is_in_range(0.0));
+		assert!(over.is_in_range(-55.0));
+		assert!(over.is_in_range(170.0));
+		assert!(!over.is_in_range(-55.1));
+		assert!(!over.is_in_range(over.calibrate(65261.0)));
+
+		assert!(SensorOverride::default().is_in_range(65261.0));
+	}
+}
@@ -0,0 +1,532 @@
+//! Stable sensor IDs and the global sensor registry.
+//!
+//! Every meter of a backend is registered as `<backend>/<device>/<feature>`,
+//! where the device is a name that does not change across reboots:
+//!
+//! ```text
+//! lm_sensors/k10temp-pci-00c3/temp1        libsensors chip name (prefix, bus, address)
+//! fans/nct6798-platform-nct6775.656/fan2   hwmon chip name, bus and device
+//! rapl/intel-rapl:0/package-0              powercap zone
+//! udisks2/S4EWNX0R123456/temperature       drive serial
+//! wireless/wlan0/signal                    interface name
+//! frametime/active/fps                     the presenting client
+//! edac/mc0/total_ce                        memory controller
+//! interrupts/softirq/NET_RX                /proc/interrupts, /proc/softirqs
+//! throttle/cpu/package_throttle            all CPUs
+//! schedstat/system/loadavg1                the whole system
+//! process/target/cpu                       the tree of `machinepmmeter run`
+//! ```
+//!
+//! The samplers update the latest value, range and time of their entries, the
+//! config overrides, alerts and recordings refer to the same IDs. `[sensors]`
+//! and `[thresholds]` of AppConfig are applied here, the same for all pages.
+
+use crate::core::sensoroverride::SensorOverride;
+use crate::core::sensoroverride::SensorsAppConfig;
+use crate::core::threshold::Thresholds;
+use crate::core::threshold::ThresholdsAppConfig;
+use log::warn;
+use std::fmt;
+use std::fmt::Display;
+use std::ops::Deref;
+use std::sync::Arc;
+use std::sync::OnceLock;
+use std::sync::RwLock;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Instant;
+use std::time::SystemTime;
+
+/// `<backend>/<device>/<feature>`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SensorId(String);
+
+impl SensorId {
+	/// `/` and whitespace of the parts are replaced with `_`.
+	pub fn new(backend: &str, device: &str, feature: &str) -> Self {
+		let mut result = String::with_capacity(backend.len() + device.len() + feature.len() + 2);
+		for (i, part) in [backend, device, feature].into_iter().enumerate() {
+			if i != 0 {
+				result.push('/');
+			}
+			result.extend(part.trim().chars().map(|a| match a {
+				'/' => '_',
+				a if a.is_whitespace() => '_',
+				a => a,
+			}));
+		}
+
+		Self(result)
+	}
+
+	#[inline]
+	pub fn as_str(&self) -> &str {
+		&self.0
+	}
+}
+
+impl Deref for SensorId {
+	type Target = str;
+
+	#[inline]
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
+
+impl Display for SensorId {
+	#[inline]
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(&self.0)
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SensorKind {
+	Temperature,
+	Power,
+	Current,
+	Fan,
+	Signal,
+	Bitrate,
+	Frametime,
+	Fps,
+	Other,
+}
+
+impl SensorKind {
+	pub const fn as_str(&self) -> &'static str {
+		match self {
+			Self::Temperature => "temperature",
+			Self::Power => "power",
+			Self::Current => "current",
+			Self::Fan => "fan",
+			Self::Signal => "signal",
+			Self::Bitrate => "bitrate",
+			Self::Frametime => "frametime",
+			Self::Fps => "fps",
+			Self::Other => "other",
+		}
+	}
+}
+
+impl Display for SensorKind {
+	#[inline]
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.as_str())
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SensorUnit {
+	Celsius,
+	Watt,
+	Ampere,
+	Rpm,
+	Dbm,
+	Percent,
+	Mbps,
+	Millisecond,
+	Fps,
+	PerSecond,
+	MillisecondPerSecond,
+	Mebibyte,
+	None,
+}
+
+impl SensorUnit {
+	pub const fn symbol(&self) -> &'static str {
+		match self {
+			Self::Celsius => "°C",
+			Self::Watt => "W",
+			Self::Ampere => "A",
+			Self::Rpm => "RPM",
+			Self::Dbm => "dBm",
+			Self::Percent => "%",
+			Self::Mbps => "Mbit/s",
+			Self::Millisecond => "ms",
+			Self::Fps => "FPS",
+			Self::PerSecond => "/s",
+			Self::MillisecondPerSecond => "ms/s",
+			Self::Mebibyte => "MiB",
+			Self::None => "",
+		}
+	}
+}
+
+impl Display for SensorUnit {
+	#[inline]
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(self.symbol())
+	}
+}
+
+/// The last sample of an entry.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SensorSample {
+	pub value: f64,
+	pub min: f64,
+	pub max: f64,
+	pub time: SystemTime,
+	/// For the durations of the alert rules (monotonic).
+	pub instant: Instant,
+}
+
+impl SensorSample {
+	/// The position of the value in the graph, `0.0..=1.0` (`0.0` for an
+	/// empty range).
+	pub fn fraction(&self) -> f64 {
+		match self.max - self.min {
+			range if range > 0.0 => ((self.value - self.min) / range).clamp(0.0, 1.0),
+			_ => 0.0,
+		}
+	}
+}
+
+#[derive(Debug)]
+pub struct SensorEntry {
+	id: SensorId,
+	name: String,
+	kind: SensorKind,
+	unit: SensorUnit,
+	over: SensorOverride,
+	thresholds: Thresholds,
+
+	/// Bumped on every update, `0` - no sample yet.
+	seq: AtomicU64,
+	sample: RwLock<Option<SensorSample>>,
+}
+
+impl SensorEntry {
+	#[inline]
+	pub const fn id(&self) -> &SensorId {
+		&self.id
+	}
+
+	/// The display name (after `[sensors.override]`).
+	#[inline]
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	#[inline]
+	pub const fn kind(&self) -> SensorKind {
+		self.kind
+	}
+
+	#[inline]
+	pub const fn unit(&self) -> SensorUnit {
+		self.unit
+	}
+
+	/// `[sensors.override]` of the ID, the default one without it.
+	#[inline]
+	pub const fn sensor_override(&self) -> &SensorOverride {
+		&self.over
+	}
+
+	/// The head of the meter, `# <name> (<unit>)` with a name of
+	/// `[sensors.override]`.
+	pub fn head_or(&self, head: &str) -> String {
+		match (self.over.get_name(), self.unit) {
+			(Some(name), SensorUnit::None) => format!("# {}", name),
+			(Some(name), unit) => format!("# {} ({})", name, unit),
+			(None, _) => head.to_string(),
+		}
+	}
+
+	/// `[thresholds]` with the `crit` of `[sensors.override]`.
+	#[inline]
+	pub const fn thresholds(&self) -> Thresholds {
+		self.thresholds
+	}
+
+	/// From the sampler thread, with the raw value and the range of the
+	/// backend. The stored sample is calibrated and has the range of
+	/// `[sensors.override]`, the meters draw the returned one.
+	pub fn update(&self, value: f64, min: f64, max: f64) -> SensorSample {
+		let (min, max) = self.over.range(min, max);
+		let sample = SensorSample {
+			value: self.over.calibrate(value),
+			min,
+			max,
+			time: SystemTime::now(),
+			instant: Instant::now(),
+		};
+		match self.sample.write() {
+			Ok(mut a) => *a = Some(sample),
+			Err(e) => *e.into_inner() = Some(sample),
+		}
+		self.seq.fetch_add(1, Ordering::Release);
+
+		sample
+	}
+
+	#[inline]
+	pub fn sample(&self) -> Option<SensorSample> {
+		match self.sample.read() {
+			Ok(a) => *a,
+			Err(e) => *e.into_inner(),
+		}
+	}
+
+	/// Changes on every update, to skip the entries without a new sample.
+	#[inline]
+	pub fn seq(&self) -> u64 {
+		self.seq.load(Ordering::Acquire)
+	}
+}
+
+/// All meters of all pages, in the order of registration.
+#[derive(Debug)]
+pub struct SensorRegistry {
+	sensors: RwLock<Vec<Arc<SensorEntry>>>,
+	config: OnceLock<(SensorsAppConfig, ThresholdsAppConfig)>,
+}
+
+pub static SENSOR_REGISTRY: SensorRegistry = SensorRegistry::new();
+
+impl Default for SensorRegistry {
+	#[inline]
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl SensorRegistry {
+	pub const fn new() -> Self {
+		Self {
+			sensors: RwLock::new(Vec::new()),
+			config: OnceLock::new(),
+		}
+	}
+
+	/// `[sensors]` and `[thresholds]` of AppConfig, once, before the pages
+	/// register their meters.
+	pub fn configure(&self, sensors: SensorsAppConfig, thresholds: ThresholdsAppConfig) {
+		if self.config.set((sensors, thresholds)).is_err() {
+			warn!(
+				"#[sensorregistry] The registry is already configured, the new config is ignored."
+			);
+		}
+	}
+
+	/// `include`, `exclude` and `hide` of `[sensors]`, the backends do not
+	/// create the meters of the hidden IDs.
+	pub fn is_visible(&self, id: &str) -> bool {
+		self.config
+			.get()
+			.is_none_or(|(sensors, _)| sensors.is_visible(id))
+	}
+
+	/// The entry of an already registered ID is returned as is, a different
+	/// name, kind or unit is logged. The name of `[sensors.override]`
+	/// replaces the one of the backend.
+	pub fn register(
+		&self,
+		id: SensorId,
+		name: impl Into<String>,
+		kind: SensorKind,
+		unit: SensorUnit,
+	) -> Arc<SensorEntry> {
+		let (over, thresholds) = match self.config.get() {
+			Some((sensors, thresholds)) => {
+				let over = sensors.get_override(&id).cloned().unwrap_or_default();
+				let thresholds = thresholds.get_with_crit(&id, kind, over.get_crit());
+
+				(over, thresholds)
+			}
+			None => (SensorOverride::default(), Thresholds::of_kind(kind)),
+		};
+		let name = match over.get_name() {
+			Some(a) => a.to_string(),
+			None => name.into(),
+		};
+
+		let mut sensors = match self.sensors.write() {
+			Ok(a) => a,
+			Err(e) => e.into_inner(),
+		};
+		if let Some(a) = sensors.iter().find(|a| a.id == id) {
+			if a.name != name || a.kind != kind || a.unit != unit {
+				warn!(
+					"#[sensorregistry] {} is already registered as {:?} ({}, {:?}), {:?} ({}, {:?}) shares its entry.",
+					id, a.name, a.kind, a.unit, name, kind, unit
+				);
+			}
+
+			return a.clone();
+		}
+
+		let entry = Arc::new(SensorEntry {
+			id,
+			name,
+			kind,
+			unit,
+			over,
+			thresholds,
+			seq: AtomicU64::new(0),
+			sample: RwLock::new(None),
+		});
+		sensors.push(entry.clone());
+
+		entry
+	}
+
+	pub fn get(&self, id: &str) -> Option<Arc<SensorEntry>> {
+		let sensors = match self.sensors.read() {
+			Ok(a) => a,
+			Err(e) => e.into_inner(),
+		};
+
+		sensors.iter().find(|a| a.id.as_str() == id).cloned()
+	}
+
+	pub fn entries(&self) -> Vec<Arc<SensorEntry>> {
+		match self.sensors.read() {
+			Ok(a) => a.clone(),
+			Err(e) => e.into_inner().clone(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn sensor_ids() {
+		assert_eq!(
+			SensorId::new("lm_sensors", "k10temp-pci-00c3", "temp1").as_str(),
+			"lm_sensors/k10temp-pci-00c3/temp1"
+		);
+		assert_eq!(
+			SensorId::new("interrupts", " irq ", "IRQ 16: ehci/hcd").to_string(),
+			"interrupts/irq/IRQ_16:_ehci_hcd"
+		);
+		assert_eq!(
+			&*SensorId::new("wireless", "wlan0", "signal"),
+			"wireless/wlan0/signal"
+		);
+	}
+
+	#[test]
+	fn register_once() {
+		let registry = SensorRegistry::new();
+		let id = SensorId::new("fans", "nct6798-isa-0290", "fan1");
+		let a = registry.register(id.clone(), "fan1", SensorKind::Fan, SensorUnit::Rpm);
+		let b = registry.register(id.clone(), "CPU fan", SensorKind::Fan, SensorUnit::Rpm);
+		registry.register(
+			SensorId::new("fans", "nct6798-isa-0290", "fan2"),
+			"fan2",
+			SensorKind::Fan,
+			SensorUnit::Rpm,
+		);
+
+		assert!(Arc::ptr_eq(&a, &b));
+		assert_eq!(b.name(), "fan1");
+		assert_eq!(registry.entries().len(), 2);
+		assert!(Arc::ptr_eq(
+			&registry.get("fans/nct6798-isa-0290/fan1").unwrap(),
+			&a
+		));
+		assert!(registry.get("fans/nct6798-isa-0290/fan3").is_none());
+	}
+
+	#[test]
+	fn samples() {
+		let registry = SensorRegistry::new();
+		let entry = registry.register(
+			SensorId::new("rapl", "intel-rapl:0", "package-0"),
+			"package-0",
+			SensorKind::Power,
+			SensorUnit::Watt,
+		);
+		assert_eq!(entry.seq(), 0);
+		assert_eq!(entry.sample(), None);
+
+		entry.update(15.5, 0.0, 65.0);
+		let seq = entry.seq();
+		let sample = entry.sample().unwrap();
+		assert_ne!(seq, 0);
+		assert_eq!((sample.value, sample.min, sample.max), (15.5, 0.0, 65.0));
+
+		entry.update(20.0, 0.0, 65.0);
+		assert_ne!(entry.seq(), seq);
+		assert_eq!(entry.sample().unwrap().value, 20.0);
+	}
+
+	#[test]
+	fn config() {
+		use crate::core::threshold::ThresholdLevel;
+		use crate::core::threshold::ThresholdValue;
+
+		let registry = SensorRegistry::new();
+		assert!(registry.is_visible("fans/nct6798-isa-0290/fan1"));
+		registry.configure(
+			toml::from_str(
+				r#"
+				exclude = ["^rapl/"]
+
+				[override."fans/nct6798-isa-0290/fan1"]
+				name = "Pump"
+				min = 500.0
+				max = 3000.0
+				crit = 800.0
+
+				[override."fans/nct6798-isa-0290/fan2"]
+				hide = true
+
+				[override."udisks2/S4EWNX0R123456/temperature"]
+				offset = -5.0
+				"#,
+			)
+			.unwrap(),
+			toml::from_str("[fan]\nwarning = 1000.0").unwrap(),
+		);
+		assert!(registry.is_visible("fans/nct6798-isa-0290/fan1"));
+		assert!(!registry.is_visible("fans/nct6798-isa-0290/fan2"));
+		assert!(!registry.is_visible("rapl/intel-rapl:0/package-0"));
+
+		// name, range and crit
+		let entry = registry.register(
+			SensorId::new("fans", "nct6798-isa-0290", "fan1"),
+			"fan1",
+			SensorKind::Fan,
+			SensorUnit::Rpm,
+		);
+		assert_eq!(entry.name(), "Pump");
+		assert_eq!(entry.head_or("# fan1 (RPM)"), "# Pump (RPM)");
+		let thresholds = entry.thresholds();
+		assert_eq!(
+			thresholds,
+			Thresholds::new(ThresholdValue::Value(1000.0), ThresholdValue::Value(800.0))
+		);
+		let sample = entry.update(700.0, 0.0, 1800.0);
+		assert_eq!(
+			(sample.value, sample.min, sample.max),
+			(700.0, 500.0, 3000.0)
+		);
+		assert_eq!(sample.fraction(), 0.08);
+		assert_eq!(
+			thresholds.level(sample.value, sample.min, sample.max),
+			ThresholdLevel::Critical
+		);
+
+		// calibration, the thresholds of the kind
+		let entry = registry.register(
+			SensorId::new("udisks2", "S4EWNX0R123456", "temperature"),
+			"temperature",
+			SensorKind::Temperature,
+			SensorUnit::Celsius,
+		);
+		assert_eq!(entry.name(), "temperature");
+		assert_eq!(entry.head_or("# temperature"), "# temperature");
+		assert_eq!(entry.thresholds(), Thresholds::default());
+		let sample = entry.update(45.0, 0.0, 70.0);
+		assert_eq!((sample.value, sample.min, sample.max), (40.0, 0.0, 70.0));
+		assert_eq!(entry.update(90.0, 0.0, 70.0).fraction(), 1.0);
+		assert_eq!(entry.update(45.0, 70.0, 70.0).fraction(), 0.0);
+		assert_eq!(entry.sample().unwrap().value, 40.0);
+	}
+}
@@ -0,0 +1,220 @@
+use dbus::arg::PropMap;
+use dbus::blocking::Connection;
+use dbus::blocking::stdintf::org_freedesktop_dbus::Properties;
+use dbus_udisks2::smart::SmartStatus;
+use std::borrow::Cow;
+use std::time::Duration;
+
+pub const UDISKS2_DEST: &str = "org.freedesktop.UDisks2";
+const DRIVE_ATA: &str = "org.freedesktop.UDisks2.Drive.Ata";
+/// The state is polled only while a self-test runs, the tests take minutes.
+pub const SMART_SELFTEST_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmartSelftestKind {
+	Short,
+	Extended,
+}
+
+impl SmartSelftestKind {
+	#[inline]
+	pub const fn as_str(self) -> &'static str {
+		match self {
+			Self::Short => "short",
+			Self::Extended => "extended",
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SmartSelftestState {
+	pub status: SmartStatus,
+	pub percent_remaining: i32,
+}
+
+impl SmartSelftestState {
+	#[inline]
+	pub fn is_in_progress(&self) -> bool {
+		self.status == SmartStatus::InProgress
+	}
+
+	pub const fn status_as_str(&self) -> &'static str {
+		match self.status {
+			SmartStatus::Success => "success",
+			SmartStatus::Aborted => "aborted",
+			SmartStatus::Interrupted => "interrupted",
+			SmartStatus::Fatal => "fatal error",
+			SmartStatus::UnknownError => "failed (unknown)",
+			SmartStatus::ElectricalError => "failed (electrical)",
+			SmartStatus::ServoError => "failed (servo)",
+			SmartStatus::ReadError => "failed (read)",
+			SmartStatus::HandlingError => "failed (handling)",
+			SmartStatus::InProgress => "in progress",
+			_ => "unknown",
+		}
+	}
+}
+
+/// Access to `org.freedesktop.UDisks2.Drive.Ata` self-test methods, which
+/// `dbus_udisks2` does not expose.
+pub struct SmartSelftest {
+	conn: Connection,
+	dest: Cow<'static, str>,
+	timeout: Duration,
+}
+
+impl SmartSelftest {
+	#[inline]
+	pub fn new_system() -> Result<Self, dbus::Error> {
+		Ok(Self::with_connection(
+			Connection::new_system()?,
+			UDISKS2_DEST,
+		))
+	}
+
+	#[inline]
+	pub fn with_connection(conn: Connection, dest: impl Into<Cow<'static, str>>) -> Self {
+		Self {
+			conn,
+			dest: dest.into(),
+			timeout: Duration::from_millis(3000),
+		}
+	}
+
+	/// The drive has `org.freedesktop.UDisks2.Drive.Ata` with SMART, NVMe
+	/// drives do not.
+	pub fn is_supported(&self, drive_path: &str) -> bool {
+		self.conn
+			.with_proxy(&*self.dest, drive_path, self.timeout)
+			.get::<bool>(DRIVE_ATA, "SmartSupported")
+			.unwrap_or(false)
+	}
+
+	pub fn start(&self, drive_path: &str, kind: SmartSelftestKind) -> Result<(), dbus::Error> {
+		self.conn
+			.with_proxy(&*self.dest, drive_path, self.timeout)
+			.method_call(
+				DRIVE_ATA,
+				"SmartSelftestStart",
+				(kind.as_str(), PropMap::new()),
+			)
+	}
+
+	pub fn abort(&self, drive_path: &str) -> Result<(), dbus::Error> {
+		self.conn
+			.with_proxy(&*self.dest, drive_path, self.timeout)
+			.method_call(DRIVE_ATA, "SmartSelftestAbort", (PropMap::new(),))
+	}
+
+	pub fn state(&self, drive_path: &str) -> Result<SmartSelftestState, dbus::Error> {
+		let proxy = self.conn.with_proxy(&*self.dest, drive_path, self.timeout);
+		let status: String = proxy.get(DRIVE_ATA, "SmartSelftestStatus")?;
+		let percent_remaining: i32 = proxy.get(DRIVE_ATA, "SmartSelftestPercentRemaining")?;
+
+		Ok(SmartSelftestState {
+			status: status.parse().unwrap_or(SmartStatus::Unknown),
+			percent_remaining,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use dbus_crossroads::Crossroads;
+	use dbus_crossroads::MethodErr;
+
+	const ATA_PATH: &str = "/org/freedesktop/UDisks2/drives/Standin_ATA";
+	const NVME_PATH: &str = "/org/freedesktop/UDisks2/drives/Standin_NVMe";
+
+	#[derive(Debug, Default)]
+	struct Drive {
+		status: String,
+		percent_remaining: i32,
+	}
+
+	/// A `Drive.Ata` stand-in on the session bus, `None` without a bus.
+	fn standin() -> Option<SmartSelftest> {
+		let server = Connection::new_session().ok()?;
+		let dest = server.unique_name().to_string();
+
+		let mut cr = Crossroads::new();
+		let ata = cr.register(DRIVE_ATA, |b| {
+			b.property("SmartSupported")
+				.get(|_, _drive: &mut Drive| Ok(true));
+			b.property("SmartSelftestStatus")
+				.get(|_, drive: &mut Drive| Ok(drive.status.clone()));
+			b.property("SmartSelftestPercentRemaining")
+				.get(|_, drive: &mut Drive| Ok(drive.percent_remaining));
+			b.method(
+				"SmartSelftestStart",
+				("type", "options"),
+				(),
+				|_, drive: &mut Drive, (kind, _options): (String, PropMap)| {
+					if kind != "short" && kind != "extended" {
+						return Err(MethodErr::invalid_arg(&kind));
+					}
+					drive.status = "inprogress".into();
+					drive.percent_remaining = 90;
+
+					Ok(())
+				},
+			);
+			b.method(
+				"SmartSelftestAbort",
+				("options",),
+				(),
+				|_, drive: &mut Drive, _: (PropMap,)| {
+					drive.status = "aborted".into();
+					drive.percent_remaining = 0;
+
+					Ok(())
+				},
+			);
+		});
+		cr.insert(
+			ATA_PATH,
+			&[ata],
+			Drive {
+				status: "success".into(),
+				percent_remaining: 0,
+			},
+		);
+		cr.insert(NVME_PATH, &[], Drive::default());
+		std::thread::spawn(move || cr.serve(&server));
+
+		Some(SmartSelftest::with_connection(
+			Connection::new_session().ok()?,
+			dest,
+		))
+	}
+
+	#[test]
+	fn selftest_standin() {
+		// no session bus
+		let Some(selftest) = standin() else {
+			return;
+		};
+
+		assert!(selftest.is_supported(ATA_PATH));
+		assert!(!selftest.is_supported(NVME_PATH));
+		assert_eq!(
+			selftest.state(ATA_PATH).unwrap().status,
+			SmartStatus::Success
+		);
+
+		selftest.start(ATA_PATH, SmartSelftestKind::Short).unwrap();
+		let state = selftest.state(ATA_PATH).unwrap();
+		assert!(state.is_in_progress());
+		assert_eq!(state.percent_remaining, 90);
+
+		selftest.abort(ATA_PATH).unwrap();
+		assert_eq!(
+			selftest.state(ATA_PATH).unwrap().status,
+			SmartStatus::Aborted
+		);
+
+		assert!(selftest.start(NVME_PATH, SmartSelftestKind::Short).is_err());
+		assert!(selftest.state(NVME_PATH).is_err());
+	}
+}
@@ -0,0 +1,390 @@
+use log::info;
+use log::trace;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+pub const PROC_PATH: &str = "/proc";
+
+/// Steam/Proton environment of a process (`/proc/<pid>/environ`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SteamEnv {
+	pub app_id: u64,
+	pub is_proton: bool,
+}
+
+/// `SteamAppId`, `STEAM_COMPAT_APP_ID`, `SteamGameId` or the `compatdata/<id>`
+/// of `STEAM_COMPAT_DATA_PATH` of a NUL-separated environment.
+pub fn parse_steam_environ(data: &[u8]) -> Option<SteamEnv> {
+	let vars: HashMap<&[u8], &[u8]> = data
+		.split(|a| *a == 0)
+		.filter_map(|a| {
+			let i = a.iter().position(|a| *a == b'=')?;
+
+			Some((&a[..i], &a[i + 1..]))
+		})
+		.collect();
+	let var = |key: &str| {
+		vars.get(key.as_bytes())
+			.and_then(|a| std::str::from_utf8(a).ok())
+			.filter(|a| !a.is_empty())
+	};
+
+	let compat_data_id = var("STEAM_COMPAT_DATA_PATH").and_then(|a| {
+		Path::new(a)
+			.file_name()?
+			.to_str()?
+			.parse::<u64>()
+			.ok()
+	});
+	let app_id = ["SteamAppId", "STEAM_COMPAT_APP_ID", "SteamGameId"]
+		.into_iter()
+		.filter_map(|a| var(a)?.parse::<u64>().ok())
+		.chain(compat_data_id)
+		// 0 - the Steam client itself
+		.find(|a| *a != 0)?;
+
+	let is_proton = var("STEAM_COMPAT_DATA_PATH").is_some()
+		|| var("STEAM_COMPAT_TOOL_PATHS").is_some_and(|a| a.to_ascii_lowercase().contains("proton"))
+		|| vars.keys().any(|a| a.starts_with(b"PROTON_"));
+
+	Some(SteamEnv { app_id, is_proton })
+}
+
+/// Quoted `"key" "value"` pairs of a VDF/ACF file (nesting is ignored).
+pub fn parse_vdf_pairs(data: &str) -> Vec<(&str, &str)> {
+	data.lines()
+		.filter_map(|line| {
+			let mut parts = line.split('"').skip(1).step_by(2);
+
+			Some((parts.next()?, parts.next()?))
+		})
+		.collect()
+}
+
+/// (`appid`, `name`) of an `appmanifest_<appid>.acf`.
+pub fn parse_appmanifest(data: &str) -> Option<(u64, String)> {
+	let pairs = parse_vdf_pairs(data);
+	let value = |key: &str| {
+		pairs
+			.iter()
+			.find(|(a, _)| a.eq_ignore_ascii_case(key))
+			.map(|(_, a)| *a)
+	};
+
+	Some((value("appid")?.parse().ok()?, value("name")?.to_string()))
+}
+
+/// `steamapps` directories of the local Steam installations and their
+/// libraries (`libraryfolders.vdf`).
+pub fn steam_library_dirs(home: impl AsRef<Path>) -> Vec<PathBuf> {
+	let home = home.as_ref();
+	let mut result: Vec<PathBuf> = Vec::new();
+	for root in [
+		".steam/steam",
+		".local/share/Steam",
+		".var/app/com.valvesoftware.Steam/.local/share/Steam",
+	] {
+		let steamapps = home.join(root).join("steamapps");
+		if !steamapps.is_dir() {
+			continue;
+		}
+
+		let libraries = fs::read_to_string(steamapps.join("libraryfolders.vdf")).unwrap_or_default();
+		for dir in std::iter::once(steamapps.clone()).chain(
+			parse_vdf_pairs(&libraries)
+				.into_iter()
+				.filter(|(key, _)| *key == "path")
+				.map(|(_, path)| Path::new(path).join("steamapps")),
+		) {
+			// `~/.steam/steam` is usually a symlink to `~/.local/share/Steam`
+			let dir = fs::canonicalize(&dir).unwrap_or(dir);
+			if !result.contains(&dir) {
+				result.push(dir);
+			}
+		}
+	}
+
+	result
+}
+
+pub fn resolve_steam_app_name(app_id: u64, library_dirs: &[PathBuf]) -> Option<String> {
+	library_dirs.iter().find_map(|dir| {
+		let data = fs::read_to_string(dir.join(format!("appmanifest_{}.acf", app_id))).ok()?;
+
+		parse_appmanifest(&data)
+			.filter(|(a, _)| *a == app_id)
+			.map(|(_, name)| name)
+	})
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SteamGame {
+	pub app_id: u64,
+	/// From the local `appmanifest_<appid>.acf`, `None` for non-Steam shortcuts.
+	pub name: Option<String>,
+	/// The first (lowest) process of the game.
+	pub pid: u32,
+	pub is_proton: bool,
+}
+
+impl SteamGame {
+	pub fn title(&self) -> String {
+		let name = match self.name {
+			Some(ref a) => a.clone(),
+			None => format!("Steam app {}", self.app_id),
+		};
+
+		match self.is_proton {
+			true => format!("{} (Proton)", name),
+			false => name,
+		}
+	}
+}
+
+/// Running Steam games, one per app id.
+pub fn detect_steam_games(proc_root: impl AsRef<Path>, library_dirs: &[PathBuf]) -> Vec<SteamGame> {
+	let Ok(dir) = fs::read_dir(proc_root.as_ref()) else {
+		return Vec::new();
+	};
+
+	let mut envs: Vec<(u32, SteamEnv)> = dir
+		.filter_map(|a| a.ok())
+		.filter_map(|a| {
+			let pid = a.file_name().to_str()?.parse::<u32>().ok()?;
+			// started by `machinepmmeter run` from Steam
+			if pid == std::process::id() {
+				return None;
+			}
+			// only our own processes are readable
+			let environ = fs::read(a.path().join("environ")).ok()?;
+
+			Some((pid, parse_steam_environ(&environ)?))
+		})
+		.collect();
+	envs.sort_by_key(|(pid, _)| *pid);
+
+	let mut result: Vec<SteamGame> = Vec::new();
+	for (pid, env) in envs {
+		match result.iter_mut().find(|a| a.app_id == env.app_id) {
+			Some(game) => game.is_proton |= env.is_proton,
+			None => result.push(SteamGame {
+				app_id: env.app_id,
+				name: resolve_steam_app_name(env.app_id, library_dirs),
+				pid,
+				is_proton: env.is_proton,
+			}),
+		}
+	}
+
+	result
+}
+
+/// The detected game, shared with the dock head and recordings.
+#[derive(Debug, Clone, Default)]
+pub struct CurrentSteamGame(Arc<Mutex<Option<SteamGame>>>);
+
+impl CurrentSteamGame {
+	/// Returns `true` if the game has changed.
+	pub fn set(&self, game: Option<SteamGame>) -> bool {
+		let Ok(mut current) = self.0.lock() else {
+			return false;
+		};
+		if current.as_ref().map(|a| a.app_id) == game.as_ref().map(|a| a.app_id) {
+			return false;
+		}
+
+		match game {
+			Some(ref game) => info!("#[steamgame] {} ({}, pid: {})", game.title(), game.app_id, game.pid),
+			None => info!("#[steamgame] No game is running"),
+		}
+		*current = game;
+
+		true
+	}
+
+	pub fn get(&self) -> Option<SteamGame> {
+		self.0.lock().ok()?.clone()
+	}
+}
+
+/// Rescans the running games every `sampling_time`, sends the title of the
+/// current game on change.
+pub fn spawn_steam_game_detector(
+	current: CurrentSteamGame,
+	sampling_time: Duration,
+) -> async_channel::Receiver<Option<String>> {
+	let (sender, recv) = async_channel::bounded(4);
+	std::thread::spawn(move || {
+		let library_dirs = std::env::var_os("HOME").map_or_else(Vec::new, steam_library_dirs);
+		trace!("#[steamgame] library dirs: {:?}", library_dirs);
+
+		loop {
+			let game = detect_steam_games(PROC_PATH, &library_dirs).into_iter().next();
+			let title = game.as_ref().map(SteamGame::title);
+			if current.set(game) && sender.send_blocking(title).is_err() {
+				return;
+			}
+
+			std::thread::sleep(sampling_time);
+		}
+	});
+
+	recv
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const APPMANIFEST: &str = r#""AppState"
+{
+	"appid"		"1091500"
+	"universe"		"1"
+	"name"		"Cyberpunk 2077"
+	"StateFlags"		"4"
+	"installdir"		"Cyberpunk 2077"
+	"InstalledDepots"
+	{
+		"1091501"
+		{
+			"manifest"		"5443211654324654560"
+			"size"		"65061453279"
+		}
+	}
+	"UserConfig"
+	{
+		"language"		"english"
+	}
+}
+"#;
+
+	fn environ(vars: &[&str]) -> Vec<u8> {
+		vars.iter().flat_map(|a| a.bytes().chain([0])).collect()
+	}
+
+	#[test]
+	fn steam_environ() {
+		assert_eq!(
+			parse_steam_environ(&environ(&["HOME=/home/a", "SteamAppId=1091500"])),
+			Some(SteamEnv {
+				app_id: 1091500,
+				is_proton: false,
+			})
+		);
+		// 0 - the Steam client, the next variable decides
+		assert_eq!(
+			parse_steam_environ(&environ(&["SteamAppId=0", "SteamGameId=570"])),
+			Some(SteamEnv {
+				app_id: 570,
+				is_proton: false,
+			})
+		);
+		assert_eq!(
+			parse_steam_environ(&environ(&[
+				"SteamAppId=0",
+				"STEAM_COMPAT_DATA_PATH=/home/a/.local/share/Steam/steamapps/compatdata/1245620",
+			])),
+			Some(SteamEnv {
+				app_id: 1245620,
+				is_proton: true,
+			})
+		);
+		assert_eq!(parse_steam_environ(&environ(&["SteamAppId=0"])), None);
+		assert_eq!(
+			parse_steam_environ(&environ(&["SteamAppId=", "PATH=/usr/bin"])),
+			None
+		);
+	}
+
+	#[test]
+	fn proton() {
+		for vars in [
+			&[
+				"SteamAppId=1091500",
+				"STEAM_COMPAT_TOOL_PATHS=/home/a/.steam/steam/steamapps/common/Proton 9.0",
+			][..],
+			&["SteamAppId=1091500", "PROTON_LOG=1"],
+		] {
+			assert!(
+				parse_steam_environ(&environ(vars)).unwrap().is_proton,
+				"{:?}",
+				vars
+			);
+		}
+		assert!(
+			!parse_steam_environ(&environ(&[
+				"SteamAppId=1091500",
+				"STEAM_COMPAT_TOOL_PATHS=/opt/steam-runtime"
+			]))
+			.unwrap()
+			.is_proton
+		);
+	}
+
+	#[test]
+	fn appmanifest() {
+		assert_eq!(
+			parse_appmanifest(APPMANIFEST),
+			Some((1091500, "Cyberpunk 2077".into()))
+		);
+		assert!(parse_vdf_pairs(APPMANIFEST).contains(&("manifest", "5443211654324654560")));
+		assert_eq!(
+			parse_appmanifest("\"AppState\"\n{\n\t\"appid\"\t\t\"1\"\n}\n"),
+			None
+		);
+	}
+
+	#[test]
+	fn running_games() {
+		let root = std::env::temp_dir().join(format!("steamgame-{}", std::process::id()));
+		let _e = fs::remove_dir_all(&root);
+		let library = root.join("steamapps");
+		fs::create_dir_all(&library).unwrap();
+		fs::write(library.join("appmanifest_1091500.acf"), APPMANIFEST).unwrap();
+		for (pid, vars) in [
+			// the launcher and the game of one app id
+			(
+				"4100",
+				&[
+					"SteamAppId=1091500",
+					"STEAM_COMPAT_DATA_PATH=/compatdata/1091500",
+				][..],
+			),
+			("4000", &["SteamAppId=1091500"]),
+			("4200", &["SteamGameId=12345678901"]),
+			("4300", &["HOME=/home/a"]),
+			("self", &["SteamAppId=570"]),
+		] {
+			fs::create_dir_all(root.join("proc").join(pid)).unwrap();
+			fs::write(root.join("proc").join(pid).join("environ"), environ(vars)).unwrap();
+		}
+
+		let games = detect_steam_games(root.join("proc"), std::slice::from_ref(&library));
+		assert_eq!(
+			games,
+			[
+				SteamGame {
+					app_id: 1091500,
+					name: Some("Cyberpunk 2077".into()),
+					pid: 4000,
+					is_proton: true,
+				},
+				SteamGame {
+					app_id: 12345678901,
+					name: None,
+					pid: 4200,
+					is_proton: false,
+				},
+			]
+		);
+		assert_eq!(games[0].title(), "Cyberpunk 2077 (Proton)");
+		assert_eq!(games[1].title(), "Steam app 12345678901");
+
+		let _e = fs::remove_dir_all(&root);
+	}
+}
@@ -0,0 +1,264 @@
+use crate::core::sensorregistry::SensorKind;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// A threshold in the units of the sensor (`75.0`) or in percent of its
+/// range (`"75%"`).
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(try_from = "ThresholdValueRepr")]
+pub enum ThresholdValue {
+	Value(f64),
+	Percent(f64),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ThresholdValueRepr {
+	Value(f64),
+	Percent(String),
+}
+
+impl TryFrom<ThresholdValueRepr> for ThresholdValue {
+	type Error = String;
+
+	fn try_from(a: ThresholdValueRepr) -> Result<Self, Self::Error> {
+		match a {
+			ThresholdValueRepr::Value(a) => Ok(Self::Value(a)),
+			ThresholdValueRepr::Percent(a) => a
+				.trim()
+				.strip_suffix('%')
+				.and_then(|a| a.trim().parse().ok())
+				.map(Self::Percent)
+				.ok_or_else(|| format!("invalid threshold {:?}, expected a number or \"75%\"", a)),
+		}
+	}
+}
+
+impl ThresholdValue {
+	/// In the units of the sensor.
+	#[inline]
+	pub fn resolve(&self, min: f64, max: f64) -> f64 {
+		match *self {
+			Self::Value(a) => a,
+			Self::Percent(a) => min + (max - min) * a / 100.0,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum ThresholdLevel {
+	#[default]
+	Normal,
+	Warning,
+	Critical,
+}
+
+/// The resolved warning and critical thresholds of a meter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Thresholds {
+	warning: ThresholdValue,
+	critical: ThresholdValue,
+}
+
+impl Default for Thresholds {
+	/// The former hardcoded levels of the graph (75%, 85% of the range).
+	#[inline]
+	fn default() -> Self {
+		Self {
+			warning: ThresholdValue::Percent(75.0),
+			critical: ThresholdValue::Percent(85.0),
+		}
+	}
+}
+
+impl Thresholds {
+	#[inline]
+	pub const fn new(warning: ThresholdValue, critical: ThresholdValue) -> Self {
+		Self { warning, critical }
+	}
+
+	/// Defaults of a sensor kind, inverted for signal and FPS (the lower the
+	/// worse).
+	pub fn of_kind(kind: SensorKind) -> Self {
+		match kind {
+			SensorKind::Signal => {
+				Self::new(ThresholdValue::Percent(30.0), ThresholdValue::Percent(15.0))
+			}
+			SensorKind::Fps => Self::new(ThresholdValue::Value(45.0), ThresholdValue::Value(30.0)),
+			_ => Self::default(),
+		}
+	}
+
+	/// A critical threshold below the warning one inverts the comparison.
+	pub fn level(&self, value: f64, min: f64, max: f64) -> ThresholdLevel {
+		if !value.is_finite() {
+			return ThresholdLevel::Normal;
+		}
+		let warning = self.warning.resolve(min, max);
+		let critical = self.critical.resolve(min, max);
+
+		let (is_critical, is_warning) = match critical < warning {
+			false => (value >= critical, value >= warning),
+			true => (value <= critical, value <= warning),
+		};
+		match (is_critical, is_warning) {
+			(true, _) => ThresholdLevel::Critical,
+			(false, true) => ThresholdLevel::Warning,
+			(false, false) => ThresholdLevel::Normal,
+		}
+	}
+
+	/// For the normalized values of the graph (`0.0..=1.0`).
+	#[inline]
+	pub fn level_normalized(&self, value: f64) -> ThresholdLevel {
+		self.level(value, 0.0, 1.0)
+	}
+}
+
+/// A `[thresholds.*]` table, the unset fields fall back to the sensor kind.
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+pub struct ThresholdConfig {
+	warning: Option<ThresholdValue>,
+	critical: Option<ThresholdValue>,
+}
+
+/// `[thresholds]` of AppConfig, keyed by sensor ID or by sensor kind
+/// (temperature, power, current, fan, signal, bitrate, frametime, fps,
+/// other).
+///
+/// ```toml
+/// [thresholds.temperature]
+/// warning = "75%"   # percent of the range of the meter
+/// critical = "85%"
+///
+/// [thresholds."udisks2/S4EWNX0R123456/temperature"]
+/// warning = 55.0    # in the units of the sensor
+/// critical = 65.0
+///
+/// [thresholds.signal]
+/// warning = -67.0   # critical below warning - the lower the worse
+/// critical = -75.0
+/// ```
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(transparent)]
+pub struct ThresholdsAppConfig(HashMap<String, ThresholdConfig>);
+
+impl ThresholdsAppConfig {
+	/// The sensor ID, then the kind, then the defaults of the kind.
+	#[inline]
+	pub fn get(&self, id: &str, kind: SensorKind) -> Thresholds {
+		self.get_with_crit(id, kind, None)
+	}
+
+	/// [`Self::get`] with the `crit` of `[sensors.override]`, after the
+	/// critical threshold of the sensor ID and before the one of the kind.
+	pub fn get_with_crit(&self, id: &str, kind: SensorKind, crit: Option<f64>) -> Thresholds {
+		let default = Thresholds::of_kind(kind);
+		let by_id = self.0.get(id);
+		let by_kind = self.0.get(kind.as_str());
+
+		Thresholds {
+			warning: by_id
+				.and_then(|a| a.warning)
+				.or_else(|| by_kind.and_then(|a| a.warning))
+				.unwrap_or(default.warning),
+			critical: by_id
+				.and_then(|a| a.critical)
+				.or_else(|| crit.map(ThresholdValue::Value))
+				.or_else(|| by_kind.and_then(|a| a.critical))
+				.unwrap_or(default.critical),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn levels() {
+		let thresholds = Thresholds::new(ThresholdValue::Value(75.0), ThresholdValue::Value(85.0));
+		assert_eq!(thresholds.level(74.9, 0.0, 100.0), ThresholdLevel::Normal);
+		assert_eq!(thresholds.level(75.0, 0.0, 100.0), ThresholdLevel::Warning);
+		assert_eq!(thresholds.level(84.9, 0.0, 100.0), ThresholdLevel::Warning);
+		assert_eq!(thresholds.level(85.0, 0.0, 100.0), ThresholdLevel::Critical);
+		assert_eq!(
+			thresholds.level(f64::NAN, 0.0, 100.0),
+			ThresholdLevel::Normal
+		);
+	}
+
+	#[test]
+	fn inverted_levels() {
+		// FPS
+		let thresholds = Thresholds::of_kind(SensorKind::Fps);
+		assert_eq!(thresholds.level(60.0, 0.0, 144.0), ThresholdLevel::Normal);
+		assert_eq!(thresholds.level(45.1, 0.0, 144.0), ThresholdLevel::Normal);
+		assert_eq!(thresholds.level(45.0, 0.0, 144.0), ThresholdLevel::Warning);
+		assert_eq!(thresholds.level(30.1, 0.0, 144.0), ThresholdLevel::Warning);
+		assert_eq!(thresholds.level(30.0, 0.0, 144.0), ThresholdLevel::Critical);
+		assert_eq!(thresholds.level(0.0, 0.0, 144.0), ThresholdLevel::Critical);
+
+		// signal, 30% and 15% of -100..=-30 dBm
+		let thresholds = Thresholds::of_kind(SensorKind::Signal);
+		assert_eq!(
+			thresholds.level(-50.0, -100.0, -30.0),
+			ThresholdLevel::Normal
+		);
+		assert_eq!(
+			thresholds.level(-79.0, -100.0, -30.0),
+			ThresholdLevel::Warning
+		);
+		assert_eq!(
+			thresholds.level(-89.5, -100.0, -30.0),
+			ThresholdLevel::Critical
+		);
+	}
+
+	#[test]
+	fn percent_and_config() {
+		let thresholds = Thresholds::default();
+		assert_eq!(thresholds.level_normalized(0.74), ThresholdLevel::Normal);
+		assert_eq!(thresholds.level_normalized(0.75), ThresholdLevel::Warning);
+		assert_eq!(thresholds.level_normalized(0.85), ThresholdLevel::Critical);
+		assert_eq!(ThresholdValue::Percent(50.0).resolve(20.0, 120.0), 70.0);
+
+		let config: ThresholdsAppConfig = toml::from_str(
+			r#"
+			[temperature]
+			warning = "50%"
+
+			["lm_sensors/k10temp-pci-00c3/temp1"]
+			critical = 90.0
+			"#,
+		)
+		.unwrap();
+		assert_eq!(
+			config.get("lm_sensors/k10temp-pci-00c3/temp1", SensorKind::Temperature),
+			Thresholds::new(ThresholdValue::Percent(50.0), ThresholdValue::Value(90.0))
+		);
+		assert_eq!(
+			config.get("fans/nct6798-isa-0290/fan1", SensorKind::Fan),
+			Thresholds::default()
+		);
+		assert!(toml::from_str::<ThresholdsAppConfig>("[fan]\nwarning = \"75\"").is_err());
+
+		// the crit of [sensors.override], below the critical of the ID
+		assert_eq!(
+			config.get_with_crit(
+				"lm_sensors/k10temp-pci-00c3/temp1",
+				SensorKind::Temperature,
+				Some(95.0)
+			),
+			Thresholds::new(ThresholdValue::Percent(50.0), ThresholdValue::Value(90.0))
+		);
+		assert_eq!(
+			config.get_with_crit(
+				"lm_sensors/k10temp-pci-00c3/temp2",
+				SensorKind::Temperature,
+				Some(95.0)
+			),
+			Thresholds::new(ThresholdValue::Percent(50.0), ThresholdValue::Value(95.0))
+		);
+	}
+}
@@ -2,18 +2,23 @@
 // Copyright (C) 2025 Denis Kotlyarov (Денис Котляров) <denis2005991@gmail.com>
 
 use crate::app::aboutdialog::AppAboutDialog;
-use crate::app::cli::AppCli;
-use crate::app::config::AppConfig;
-use crate::app::consts::{APP_ID, UPPERCASE_APP_PKG_VERSION};
+use crate::app::cli::{AppCli, AppCommand};
+use crate::app::config::{AppConfig, PAGE_NAMES};
+use crate::app::consts::{APP_ID, APP_PKG_NAME, UPPERCASE_APP_PKG_VERSION};
 use crate::app::dockwindow::{AppViDockWindow, PosINScreen};
 use crate::app::events::{AppEventSender, AppEvents};
 use crate::app::keyboard::{AppKeyboardEvents, spawn_keyboard_thread};
 use crate::app::traymenu::app_traymenu;
+use crate::core::cpufreq::{CpuFreqWriter, SysfsCpuFreqWriter};
 use crate::core::display::ViGraphDisplayInfo;
+use crate::core::fancurve::{PwmWriter, SysfsPwmWriter};
+use crate::core::frametimeserver::FrametimeCommand;
+use crate::core::notifications::{NOTIFICATIONS_MUTE_TIME, NotificationAction};
 use crate::widgets::dockhead::ViDockHead;
 use crate::widgets::hotkeys::ViHotkeyItems;
 use crate::widgets::notebook::ViNotebook;
 use crate::widgets::primitives::graph::background::ViGraphBackgroundSurface;
+use crate::widgets::primitives::label::ViLabel;
 use anyhow::{Context, Result as anyhowResult};
 use async_channel::Receiver;
 use clap::Parser;
@@ -28,23 +33,41 @@ use gtk::traits::{
 };
 use gtk::{Application, ScrolledWindow};
 use gtk::{Box as GtkBox, CssProvider};
-use log::{info, trace, warn};
+use log::{error, info, trace, warn};
 use std::cell::RefCell;
 use std::io::{Write, stderr};
 use std::num::NonZero;
+use std::path::PathBuf;
 use std::rc::Rc;
 use std::time::Duration;
 use std::{fs, panic};
 
 mod widgets;
 mod core {
+	pub mod alert;
+	pub mod alerthook;
 	pub mod constuppercase;
+	pub mod cpufreq;
 	pub mod display;
 	pub mod eightbitcolor;
 	pub mod f64sbuff;
+	pub mod fancurve;
+	pub mod flightrecorder;
+	pub mod frametime;
+	pub mod frametimeserver;
+	pub mod gamemode;
 	pub mod gtkcodegen;
+	pub mod helperproto;
 	pub mod keyboard;
 	pub mod maybe;
+	pub mod notifications;
+	pub mod powerprofiles;
+	pub mod recording;
+	pub mod sensoroverride;
+	pub mod sensorregistry;
+	pub mod smartselftest;
+	pub mod steamgame;
+	pub mod threshold;
 	pub mod traymenu;
 }
 
@@ -55,7 +78,9 @@ pub mod app {
 	pub mod consts;
 	pub mod dockwindow;
 	pub mod events;
+	pub mod helper;
 	pub mod keyboard;
+	pub mod run;
 	pub mod traymenu;
 }
 
@@ -63,9 +88,20 @@ pub mod metrics {
 	#[cfg(feature = "demo_mode")]
 	#[cfg_attr(docsrs, doc(cfg(feature = "demo_mode")))]
 	pub mod demo;
+	pub mod edac;
+	pub mod fans;
+	pub mod frametime;
+	pub mod gamemode;
+	pub mod interrupts;
+	pub mod kmsg;
 	pub mod lm_sensors;
+	pub mod process;
+	pub mod rapl;
+	pub mod schedstat;
 	pub mod sysinfo;
+	pub mod throttle;
 	pub mod udisks2;
+	pub mod wireless;
 }
 
 #[cfg(all(not(target_env = "msvc"), feature = "demo_mode"))]
@@ -90,6 +126,8 @@ fn main() -> anyhowResult<()> {
 			);
 			let _e = lock.flush();
 		}
+		crate::core::fancurve::restore_auto_fan_control();
+		crate::core::cpufreq::restore_cpufreq();
 
 		std::process::exit(-1);
 	}));
@@ -102,6 +140,20 @@ fn main() -> anyhowResult<()> {
 
 	env_logger::try_init()?;
 	let cli = AppCli::parse();
+	if let Some(AppCommand::Run { command, record }) = cli.get_command() {
+		let record = record.as_ref().map(|a| a.as_deref());
+		let code = crate::app::run::run_command(&cli, command, record)?;
+
+		std::process::exit(code);
+	}
+	if let Some(AppCommand::Record { path, stop }) = cli.get_command() {
+		return crate::app::run::record_command(path.as_deref(), *stop);
+	}
+	if let Some(path) = cli.get_record()
+		&& crate::app::run::forward_record(path)
+	{
+		return Ok(());
+	}
 
 	let app_config = cli.search_default_appconfigpath(|app_config_path| {
 		let allow_save_default_app_config = cli.get_allow_save_default_app_config();
@@ -129,6 +181,13 @@ fn main() -> anyhowResult<()> {
 		Ok(app_config)
 	})?;
 	trace!("#[AppConfig file] current: {:?}", app_config);
+	for name in app_config.get_invalid_page_names() {
+		error!(
+			"#[AppConfig file] [[page]] {:?} is unknown or repeated (skipped), expected one of: {}",
+			name,
+			PAGE_NAMES.join(", ")
+		);
+	}
 
 	gtk::init()?;
 	let c_display = Rc::new(ViGraphDisplayInfo::new(
@@ -154,10 +213,14 @@ fn main() -> anyhowResult<()> {
 		);
 
 		let name_window = app_config.get_name_or_default();
-		build_ui(app, name_window, &app_config, &c_display, tx_appevents.clone(), rx_appevents.clone());
+		let record = cli.get_record().map(|a| a.to_path_buf());
+		build_ui(app, name_window, &app_config, &c_display, record, tx_appevents.clone(), rx_appevents.clone());
 	}));
 
-	application.run();
+	// the arguments are parsed by clap, GApplication rejects the unknown ones
+	application.run_with_args::<&str>(&[]);
+	crate::core::fancurve::restore_auto_fan_control();
+	crate::core::cpufreq::restore_cpufreq();
 	drop(app_traymenu);
 	Ok(())
 }
@@ -168,6 +231,7 @@ fn build_ui(
 	name_window: &str,
 	app_config: &Rc<AppConfig>,
 	c_display: &Rc<ViGraphDisplayInfo>,
+	record: Option<PathBuf>,
 
 	esender: AppEventSender,
 	receiver: Rc<Receiver<AppEvents>>,
@@ -182,51 +246,237 @@ fn build_ui(
 	vbox.set_halign(gtk::Align::Baseline);
 
 	let vinotebook = ViNotebook::new(c_display, &dock_window, &pos_inscreen);
-	#[cfg(feature = "demo_mode")]
-	#[cfg_attr(docsrs, doc(cfg(feature = "demo_mode")))]
-	{
-		// demo
-		crate::metrics::demo::vinotebook_append_page(
-			app_config,
-			&vigraph_surface,
-			(),
-			(),
-			200,
-			&vinotebook,
-		);
-	}
-
-	{
-		// lm_sensors
-		crate::metrics::lm_sensors::vinotebook_append_page(
-			app_config,
-			&vigraph_surface,
-			(),
-			(),
-			1200,
-			Duration::from_millis(16),
-			unsafe { NonZero::new_unchecked(5) },
-			Duration::from_millis(1),
-			&vinotebook,
-		);
-	}
-	{
-		// udisks2
-		crate::metrics::udisks2::vinotebook_append_page(
-			app_config,
-			&vigraph_surface,
-			(),
-			(),
-			1200,
-			Duration::from_millis(16),
-			unsafe { NonZero::new_unchecked(5) },
-			Duration::from_millis(1),
-			&vinotebook,
-		);
-	}
-	{
-		// sysinfo
-		crate::metrics::sysinfo::vinotebook_append_page(app_config, &vinotebook);
+	// [sensors] and [thresholds] of the meters of all pages
+	crate::core::sensorregistry::SENSOR_REGISTRY.configure(
+		app_config.get_sensors_app_config().clone(),
+		app_config.get_thresholds_app_config().clone(),
+	);
+	let process_target = crate::metrics::process::ProcessTarget::default();
+	let current_steam_game = crate::core::steamgame::CurrentSteamGame::default();
+	let recording_config = app_config.get_recording_app_config();
+	let recordings_dir = recording_config
+		.get_dir()
+		.map(|a| a.to_path_buf())
+		.or_else(AppCli::default_recordings_dir)
+		.unwrap_or_else(|| std::env::temp_dir().join(APP_PKG_NAME));
+	// session recording, before the pages (`run --record` on the frametime socket)
+	let (recording, recording_recv) = crate::core::recording::spawn_session_recorder(
+		recording_config.clone(),
+		recordings_dir.clone(),
+		current_steam_game.clone(),
+	);
+	// the frametime socket, with or without the frametime page (`run`, `record`)
+	let frametime_server = {
+		let process_target = process_target.clone();
+		let recording = recording.clone();
+		let path = machinepmmeter_frametimeproto::frametime_socket_path();
+		match crate::core::frametimeserver::spawn_frametime_server(path.clone(), move |a| match a {
+			FrametimeCommand::Watch(pid, exe) => process_target.set(pid, exe),
+			FrametimeCommand::Unwatch(pid) => process_target.clear(pid),
+			FrametimeCommand::Record(path) => recording.start(path),
+			FrametimeCommand::RecordStop => recording.stop(),
+		}) {
+			Ok(a) => Some(a),
+			Err(e) => {
+				error!("#[frametime] I can't listen on {:?}: {}", path, e);
+
+				None
+			}
+		}
+	};
+	// fan curves, with or without the fans page
+	let fan_curves = {
+		let is_pkexec = app_config.get_helper_app_config().get_pkexec();
+		crate::core::fancurve::spawn_fan_control(
+			crate::core::fancurve::SYS_PATH,
+			app_config.get_fan_curves().to_vec(),
+			crate::core::fancurve::FAN_CURVE_INTERVAL,
+			move || -> Box<dyn PwmWriter> {
+				// the GUI process can not write the PWM attributes (no-gui-root)
+				match crate::app::helper::connect_helper(is_pkexec) {
+					Some(a) => {
+						info!("#[fancurve] The fan curves are written through the helper.");
+
+						Box::new(a)
+					}
+					None => Box::new(SysfsPwmWriter),
+				}
+			},
+		)
+	};
+	for page in app_config.get_pages() {
+		match page.get_name() {
+			#[cfg(feature = "demo_mode")]
+			#[cfg_attr(docsrs, doc(cfg(feature = "demo_mode")))]
+			"demo" => {
+				crate::metrics::demo::vinotebook_append_page(
+					app_config,
+					&vigraph_surface,
+					(),
+					(),
+					page.get_len_or(200),
+					&vinotebook,
+				);
+			}
+			#[cfg(not(feature = "demo_mode"))]
+			"demo" => {
+				warn!("#[gui] The demo page requires the demo_mode feature");
+			}
+			"lm_sensors" => {
+				crate::metrics::lm_sensors::vinotebook_append_page(
+					app_config,
+					&vigraph_surface,
+					(),
+					(),
+					page.get_len_or(1200),
+					page.get_redraw_time_or(Duration::from_millis(16)),
+					page.get_step_len_or(unsafe { NonZero::new_unchecked(5) }),
+					page.get_step_time_or(Duration::from_millis(1)),
+					&vinotebook,
+				);
+			}
+			"fans" => {
+				crate::metrics::fans::vinotebook_append_page(
+					app_config,
+					&vigraph_surface,
+					(),
+					(),
+					page.get_len_or(120),
+					page.get_sampling_time_or(Duration::from_secs(1)),
+					&fan_curves,
+					&vinotebook,
+				);
+			}
+			"rapl" => {
+				crate::metrics::rapl::vinotebook_append_page(
+					app_config,
+					&vigraph_surface,
+					(),
+					(),
+					page.get_len_or(120),
+					page.get_sampling_time_or(Duration::from_secs(1)),
+					&vinotebook,
+				);
+			}
+			"frametime" => {
+				if let Some(server) = &frametime_server {
+					crate::metrics::frametime::vinotebook_append_page(
+						app_config,
+						&vigraph_surface,
+						(),
+						(),
+						page.get_len_or(240),
+						page.get_sampling_time_or(Duration::from_millis(100)),
+						server,
+						&vinotebook,
+					);
+				}
+			}
+			"process" => {
+				crate::metrics::process::vinotebook_append_page(
+					app_config,
+					&vigraph_surface,
+					(),
+					(),
+					page.get_len_or(120),
+					page.get_sampling_time_or(Duration::from_secs(1)),
+					&process_target,
+					&vinotebook,
+				);
+			}
+			"gamemode" => {
+				crate::metrics::gamemode::vinotebook_append_page(
+					app_config,
+					page.get_sampling_time_or(Duration::from_secs(1)),
+					&esender,
+					&vinotebook,
+				);
+			}
+			"wireless" => {
+				crate::metrics::wireless::vinotebook_append_page(
+					app_config,
+					&vigraph_surface,
+					(),
+					(),
+					page.get_len_or(120),
+					page.get_sampling_time_or(Duration::from_secs(1)),
+					&vinotebook,
+				);
+			}
+			"edac" => {
+				crate::metrics::edac::vinotebook_append_page(
+					app_config,
+					&vigraph_surface,
+					(),
+					(),
+					page.get_len_or(120),
+					page.get_sampling_time_or(Duration::from_secs(1)),
+					&vinotebook,
+				);
+			}
+			"throttle" => {
+				crate::metrics::throttle::vinotebook_append_page(
+					app_config,
+					&vigraph_surface,
+					(),
+					(),
+					page.get_len_or(120),
+					page.get_sampling_time_or(Duration::from_secs(1)),
+					&vinotebook,
+				);
+			}
+			"interrupts" => {
+				crate::metrics::interrupts::vinotebook_append_page(
+					app_config,
+					&vigraph_surface,
+					(),
+					(),
+					page.get_len_or(120),
+					page.get_sampling_time_or(Duration::from_secs(1)),
+					page.get_irq_count_or(6),
+					page.get_softirq_count_or(4),
+					page.get_heat_row_or(true),
+					&vinotebook,
+				);
+			}
+			"schedstat" => {
+				crate::metrics::schedstat::vinotebook_append_page(
+					app_config,
+					&vigraph_surface,
+					(),
+					(),
+					page.get_len_or(120),
+					page.get_sampling_time_or(Duration::from_secs(1)),
+					page.get_heat_row_or(true),
+					&vinotebook,
+				);
+			}
+			"kmsg" => {
+				crate::metrics::kmsg::vinotebook_append_page(
+					app_config,
+					page.get_events_or(50),
+					&vinotebook,
+				);
+			}
+			"udisks2" => {
+				crate::metrics::udisks2::vinotebook_append_page(
+					app_config,
+					&vigraph_surface,
+					(),
+					(),
+					page.get_len_or(1200),
+					page.get_redraw_time_or(Duration::from_millis(16)),
+					page.get_step_len_or(unsafe { NonZero::new_unchecked(5) }),
+					page.get_step_time_or(Duration::from_millis(1)),
+					&vinotebook,
+				);
+			}
+			"sysinfo" => {
+				crate::metrics::sysinfo::vinotebook_append_page(app_config, &vinotebook);
+			}
+			// reported when AppConfig is loaded
+			_ => {}
+		}
 	}
 
 	if let Some(level) = app_config.get_window_app_config().get_transparent() {
@@ -258,9 +508,148 @@ fn build_ui(
 		}
 	}
 
+	let game_label = ViLabel::new("gamehead_vilabel", &**app_config, "", ())
+		.set_align(gtk::Align::Start)
+		.set_margin_start(6)
+		.set_margin_top(2)
+		.set_visible(false);
+	{
+		// steamgame
+		let recv = crate::core::steamgame::spawn_steam_game_detector(
+			current_steam_game.clone(),
+			Duration::from_secs(5),
+		);
+		glib::MainContext::default().spawn_local(enc!((game_label) async move {
+			while let Ok(title) = recv.recv().await {
+				game_label.set_text(title.as_deref().unwrap_or_default());
+				game_label.set_visible2(title.is_some());
+			}
+		}));
+	}
+
+	let power_label = ViLabel::new("powerhead_vilabel", &**app_config, "", ())
+		.set_align(gtk::Align::Start)
+		.set_margin_start(6)
+		.set_margin_top(2)
+		.set_visible(false);
+	let power_profiles = {
+		// powerprofiles
+		let (control, recv) =
+			crate::core::powerprofiles::spawn_power_profiles_monitor(Duration::from_secs(2));
+		glib::MainContext::default().spawn_local(enc!((power_label) async move {
+			while let Ok(status) = recv.recv().await {
+				let title = status.as_ref().map(|a| format!("power: {}", a.title()));
+				power_label.set_text(title.as_deref().unwrap_or_default());
+				power_label.set_visible2(title.is_some());
+			}
+		}));
+
+		control
+	};
+
+	let cpu_label = ViLabel::new("cpuhead_vilabel", &**app_config, "", ())
+		.set_align(gtk::Align::Start)
+		.set_margin_start(6)
+		.set_margin_top(2)
+		.set_visible(false);
+	let cpu_presets = {
+		// cpufreq
+		let is_pkexec = app_config.get_helper_app_config().get_pkexec();
+		let (control, recv) = crate::core::cpufreq::spawn_cpu_preset_control(
+			crate::core::fancurve::SYS_PATH,
+			app_config.get_cpu_presets().to_vec(),
+			move || -> Box<dyn CpuFreqWriter> {
+				// the GUI process can not write the cpufreq attributes (no-gui-root)
+				match crate::app::helper::connect_helper(is_pkexec) {
+					Some(a) => Box::new(a),
+					None => Box::new(SysfsCpuFreqWriter),
+				}
+			},
+		);
+		glib::MainContext::default().spawn_local(enc!((cpu_label) async move {
+			while let Ok(name) = recv.recv().await {
+				let title = name.map(|a| format!("cpu: {}", a));
+				cpu_label.set_text(title.as_deref().unwrap_or_default());
+				cpu_label.set_visible2(title.is_some());
+			}
+		}));
+
+		control
+	};
+
+	let alert_label = ViLabel::new("alerthead_vilabel", &**app_config, "", ())
+		.set_align(gtk::Align::Start)
+		.set_margin_start(6)
+		.set_margin_top(2)
+		.set_visible(false);
+	// before the alert engine, the alerts dump the ring
+	let flight_recorder = crate::core::flightrecorder::spawn_flight_recorder(
+		recording_config.get_flight_recorder().clone(),
+		recordings_dir,
+		recording_config.get_format(),
+		current_steam_game.clone(),
+	);
+
+	let record_label = ViLabel::new("recordhead_vilabel", &**app_config, "", ())
+		.set_align(gtk::Align::Start)
+		.set_margin_start(6)
+		.set_margin_top(2)
+		.set_visible(false);
+	// session recording
+	glib::MainContext::default().spawn_local(enc!((record_label) async move {
+		while let Ok(path) = recording_recv.recv().await {
+			let title = path
+				.as_deref()
+				.and_then(|a| a.file_name())
+				.map(|a| format!("rec: {}", a.to_string_lossy()));
+			record_label.set_text(title.as_deref().unwrap_or_default());
+			record_label.set_visible2(title.is_some());
+		}
+	}));
+	if let Some(path) = record {
+		recording.start(Some(path));
+	}
+
+	let notifications = {
+		// alert, the sensors are registered by the pages above
+		let recv = crate::core::alert::ALERTS.subscribe();
+		let notifications = crate::core::notifications::spawn_alert_notifier(
+			app_config.get_notifications_app_config().clone(),
+			APP_PKG_NAME,
+			enc!((esender) move |action| match action {
+				NotificationAction::ShowDock => esender.show_window(),
+				NotificationAction::Mute => esender.mute_notifications(),
+			}),
+		);
+		crate::core::alerthook::spawn_alert_hooks(app_config.get_alerts());
+		crate::core::alert::spawn_alert_engine(
+			app_config.get_alerts().to_vec(),
+			app_config.get_sensors_app_config().get_aliases().clone(),
+			crate::core::alert::ALERT_EVAL_INTERVAL,
+		);
+		glib::MainContext::default().spawn_local(enc!((alert_label) async move {
+			while let Ok(_event) = recv.recv().await {
+				let firing = crate::core::alert::ALERTS.firing();
+				let title = firing.first().map(|a| match firing.len() {
+					1 => format!("{}: {}", a.severity, a.name),
+					len => format!("{}: {} (+{})", a.severity, a.name, len - 1),
+				});
+				alert_label.set_text(title.as_deref().unwrap_or_default());
+				alert_label.set_visible2(title.is_some());
+			}
+		}));
+
+		notifications
+	};
+
 	vbox.pack_start(&vinotebook, true, true, 0);
 	vbox.pack_end(
-		&ViDockHead::new(app_config, name_window, UPPERCASE_APP_PKG_VERSION, 1.0),
+		&ViDockHead::new(app_config, name_window, UPPERCASE_APP_PKG_VERSION, 1.0)
+			.pack_label(&game_label)
+			.pack_label(&power_label)
+			.pack_label(&cpu_label)
+			.pack_label(&alert_label)
+			.pack_label(&record_label),
 		true,
 		true,
 		0,
@@ -269,6 +658,17 @@ fn build_ui(
 	dock_window.set_child(Some(&vbox));
 	vbox.set_visible(true);
 
+	// the fan curves are returned to automatic mode on exit
+	for signum in [libc::SIGINT, libc::SIGTERM] {
+		glib::unix_signal_add_local(
+			signum,
+			enc!((esender) move || {
+				esender.exit();
+
+				glib::ControlFlow::Break
+			}),
+		);
+	}
 	spawn_keyboard_thread(esender);
 
 	dock_window.connect_show(
@@ -361,7 +761,15 @@ fn build_ui(
 					AppEvents::ToggleDockWindowVisibility | AppEvents::Keyboard(AppKeyboardEvents::ShiftF8) => {
 						dock_window.show();
 					},
+					AppEvents::ShowDockWindow => {
+						dock_window.show();
+					},
+					AppEvents::HideDockWindow => {
+						dock_window.hide();
+					},
 					AppEvents::Exit | AppEvents::Keyboard(AppKeyboardEvents::Escape) => {
+						crate::core::fancurve::restore_auto_fan_control();
+						crate::core::cpufreq::restore_cpufreq();
 						dock_window.close();
 						gtk::main_quit();
 					},
@@ -381,6 +789,12 @@ fn build_ui(
 							Some(ref a) => a.present(),
 						}
 					},
+					AppEvents::NextPowerProfile | AppEvents::Keyboard(AppKeyboardEvents::ShiftF9) => {
+						power_profiles.next_profile();
+					},
+					AppEvents::NextCpuPreset | AppEvents::Keyboard(AppKeyboardEvents::ShiftF10) => {
+						cpu_presets.next_preset();
+					},
 					AppEvents::Keyboard(AppKeyboardEvents::KeyPlus) => {},
 					AppEvents::Keyboard(AppKeyboardEvents::KeyMinus) => {},
 					AppEvents::MoveDockWindowToNextPosition | AppEvents::Keyboard(AppKeyboardEvents::KeyP) => {
@@ -393,11 +807,24 @@ fn build_ui(
 						};
 						dock_window.set_pos_inscreen(&*c_display, (), (), new_pos);
 					},
+					AppEvents::MuteNotifications => {
+						notifications.mute(NOTIFICATIONS_MUTE_TIME);
+					},
+					AppEvents::SaveFlightRecording | AppEvents::Keyboard(AppKeyboardEvents::ShiftF11) => {
+						flight_recorder.dump("hotkey");
+					},
+					AppEvents::ToggleRecording | AppEvents::Keyboard(AppKeyboardEvents::ShiftF12) => {
+						recording.toggle();
+					},
 					AppEvents::KeyboardListenerEnabled(true) => {
 						if wdock_vihotkey.is_none() {
 							let arr = match vinotebook.n_pages() {
 								0 | 1 => &[
 									("view-conceal-symbolic", "Hide | Show", "(Shift and F8)"),
+									("power-profile-balanced-symbolic", "Next power profile", "(Shift and F9)"),
+									("power-profile-performance-symbolic", "Next CPU preset", "(Shift and F10)"),
+									("document-save-symbolic", "Save last minutes", "(Shift and F11)"),
+									("media-record-symbolic", "Start | Stop recording", "(Shift and F12)"),
 									(
 										"sidebar-show-right-symbolic-rtl",
 										"Next position", "(Shift and P)",
@@ -407,6 +834,10 @@ fn build_ui(
 
 								_ => &[
 									("view-conceal-symbolic", "Hide | Show", "(Shift and F8)"),
+									("power-profile-balanced-symbolic", "Next power profile", "(Shift and F9)"),
+									("power-profile-performance-symbolic", "Next CPU preset", "(Shift and F10)"),
+									("document-save-symbolic", "Save last minutes", "(Shift and F11)"),
+									("media-record-symbolic", "Start | Stop recording", "(Shift and F12)"),
 									("zoom-original-symbolic", "Selecting a tab", "(Shift and 1 | ..)"),
 									("go-next-symbolic", "Next tab", "(Shift and D)"),
 									("go-previous-symbolic", "Previous tab", "(Shift and A)"),
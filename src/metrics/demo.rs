@@ -1,5 +1,6 @@
 use crate::app::config::AppConfig;
 use crate::core::maybe::Maybe;
+use crate::core::threshold::Thresholds;
 use crate::widgets::ViMeter;
 use crate::widgets::notebook::ViNotebook;
 use crate::widgets::primitives::graph::ViGraphBackgroundSurface;
@@ -38,7 +39,12 @@ pub fn vinotebook_append_page(
 		vimetr.set_visible_graph(true);
 		vimetr.set_visible_limit(true);
 		glib::timeout_add_local(std::time::Duration::from_millis(80), move || {
-			vimetr.push_next_and_queue_draw(0.7, 0.7, 1.0, 0.0, 0.0);
+			vimetr.push_next_and_queue_draw(
+				0.7,
+				0.7,
+				1.0,
+				Thresholds::default().level_normalized(0.7),
+			);
 
 			ControlFlow::Continue
 		});
@@ -59,7 +65,12 @@ pub fn vinotebook_append_page(
 		vbox.pack_start(&*vimetr, false, false, 0);
 		glib::timeout_add_local(std::time::Duration::from_millis(10), move || {
 			let mut w = RefCell::borrow_mut(&data);
-			vimetr.push_next_and_queue_draw(*w, *w, 1.0, 0.0, 0.0);
+			vimetr.push_next_and_queue_draw(
+				*w,
+				*w,
+				1.0,
+				Thresholds::default().level_normalized(*w),
+			);
 
 			*w += 0.1;
 			if *w >= 1.0 {
@@ -85,7 +96,12 @@ pub fn vinotebook_append_page(
 		vbox.pack_start(&*vimetr, false, false, 0);
 		glib::timeout_add_local(std::time::Duration::from_millis(1), move || {
 			let mut w = RefCell::borrow_mut(&data);
-			vimetr.push_next_and_queue_draw(*w, *w, 1.0, 0.0, 0.0);
+			vimetr.push_next_and_queue_draw(
+				*w,
+				*w,
+				1.0,
+				Thresholds::default().level_normalized(*w),
+			);
 
 			*w += 0.01;
 			if *w >= 1.0 {
@@ -111,7 +127,12 @@ pub fn vinotebook_append_page(
 		vbox.pack_start(&*vimetr, false, false, 0);
 		glib::timeout_add_local(std::time::Duration::from_millis(1), move || {
 			let mut w = RefCell::borrow_mut(&data);
-			vimetr.push_next_and_queue_draw(*w, *w, 1.0, 0.0, 0.0);
+			vimetr.push_next_and_queue_draw(
+				*w,
+				*w,
+				1.0,
+				Thresholds::default().level_normalized(*w),
+			);
 
 			*w += 0.001;
 			if *w >= 1.0 {
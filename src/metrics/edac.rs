@@ -0,0 +1,313 @@
+use crate::app::config::AppConfig;
+use crate::core::f64sbuff::F64SBuff;
+use crate::core::maybe::Maybe;
+use crate::core::sensorregistry::SENSOR_REGISTRY;
+use crate::core::sensorregistry::SensorId;
+use crate::core::sensorregistry::SensorKind;
+use crate::core::sensorregistry::SensorUnit;
+use crate::widgets::ViMeter;
+use crate::widgets::notebook::ViNotebook;
+use crate::widgets::primitives::graph::background::ViGraphBackgroundSurface;
+use crate::widgets::primitives::graph::stream::ViGraphArcSyncStream;
+use crate::widgets::primitives::graph::stream::ViGraphStream;
+use crate::widgets::primitives::label::ViLabel;
+use enclose::enc;
+use gtk::Align;
+use gtk::pango::Weight;
+use gtk::traits::BoxExt;
+use log::error;
+use log::trace;
+use log::warn;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+use std::time::Instant;
+
+pub const EDAC_MC_SYSFS_PATH: &str = "/sys/devices/system/edac/mc";
+/// How long the meter stays red after the last increase.
+const EDAC_HIGHLIGHT_TIME: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdacErrorKind {
+	/// Corrected error.
+	Ce,
+	/// Uncorrected error.
+	Ue,
+}
+
+impl EdacErrorKind {
+	#[inline]
+	pub const fn as_str(self) -> &'static str {
+		match self {
+			Self::Ce => "CE",
+			Self::Ue => "UE",
+		}
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct EdacCounter {
+	pub mc: String,
+	/// `None` for the memory controller total.
+	pub dimm: Option<String>,
+	pub kind: EdacErrorKind,
+	path: PathBuf,
+}
+
+impl EdacCounter {
+	#[inline]
+	pub fn read(&self) -> Option<u64> {
+		read_u64(&self.path)
+	}
+
+	/// `edac/mc0/DIMM_A1_ce`, `edac/mc0/total_ue`.
+	pub fn sensor_id(&self) -> SensorId {
+		let feature = format!(
+			"{}_{}",
+			self.dimm.as_deref().unwrap_or("total"),
+			self.kind.as_str().to_ascii_lowercase()
+		);
+
+		SensorId::new("edac", &self.mc, &feature)
+	}
+
+	pub fn head(&self) -> String {
+		match self.dimm {
+			Some(ref dimm) => format!("{} ({})", dimm, self.kind.as_str()),
+			None => format!("# {} total ({})", self.mc, self.kind.as_str()),
+		}
+	}
+}
+
+/// All `ce_count`/`ue_count` counters found under `root` (normally
+/// [`EDAC_MC_SYSFS_PATH`]), controller totals first, then per-DIMM.
+pub fn edac_counters(root: impl AsRef<Path>) -> io::Result<Vec<EdacCounter>> {
+	let mut result = Vec::new();
+	for (mc, mc_path) in sorted_dir_entries(root.as_ref(), "mc")? {
+		for (kind, file) in [
+			(EdacErrorKind::Ce, "ce_count"),
+			(EdacErrorKind::Ue, "ue_count"),
+		] {
+			let path = mc_path.join(file);
+			if path.is_file() {
+				result.push(EdacCounter {
+					mc: mc.clone(),
+					dimm: None,
+					kind,
+					path,
+				});
+			}
+		}
+
+		// newer kernels: dimm*, some drivers: rank*
+		let mut dimms = sorted_dir_entries(&mc_path, "dimm").unwrap_or_default();
+		dimms.extend(sorted_dir_entries(&mc_path, "rank").unwrap_or_default());
+		for (dimm, dimm_path) in dimms {
+			let label = fs::read_to_string(dimm_path.join("dimm_label"))
+				.ok()
+				.map(|a| a.trim().to_string())
+				.filter(|a| !a.is_empty())
+				.unwrap_or_else(|| dimm.clone());
+
+			for (kind, file) in [
+				(EdacErrorKind::Ce, "dimm_ce_count"),
+				(EdacErrorKind::Ue, "dimm_ue_count"),
+			] {
+				let path = dimm_path.join(file);
+				if path.is_file() {
+					result.push(EdacCounter {
+						mc: mc.clone(),
+						dimm: Some(label.clone()),
+						kind,
+						path,
+					});
+				}
+			}
+		}
+	}
+
+	Ok(result)
+}
+
+fn sorted_dir_entries(root: &Path, prefix: &str) -> io::Result<Vec<(String, PathBuf)>> {
+	let mut result = Vec::new();
+	for entry in fs::read_dir(root)? {
+		let entry = entry?;
+		if let Some(name) = entry.file_name().to_str()
+			&& let Some(num) = name.strip_prefix(prefix)
+			&& let Ok(num) = num.parse::<u32>()
+		{
+			result.push((num, name.to_string(), entry.path()));
+		}
+	}
+	result.sort_by_key(|(num, ..)| *num);
+
+	Ok(result.into_iter().map(|(_, name, path)| (name, path)).collect())
+}
+
+fn read_u64(path: impl AsRef<Path>) -> Option<u64> {
+	fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+enum EdacEvents {
+	QueueDraw(u64, bool),
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn vinotebook_append_page(
+	app_config: &Rc<AppConfig>,
+	vigraph_surface: &ViGraphBackgroundSurface,
+	width: impl Maybe<i32> + Copy,
+	height: impl Maybe<i32> + Copy,
+	len: usize,
+	sampling_time: Duration,
+
+	vinotebook: &ViNotebook,
+) {
+	let counters = match edac_counters(EDAC_MC_SYSFS_PATH) {
+		Ok(a) if !a.is_empty() => a,
+		Ok(_) => {
+			error!("#[edac] No EDAC memory controllers were found in the system, there is nothing to do on this platform.");
+
+			return;
+		}
+		Err(e) => {
+			error!("#[edac] I can't read {:?}: {}", EDAC_MC_SYSFS_PATH, e);
+
+			return;
+		}
+	};
+
+	let rvbox = vinotebook.append_page(
+		&**app_config,
+		"edac",
+		Some("Any increase of the error counters is highlighted."),
+	);
+	let mut a_counters = Vec::with_capacity(counters.len());
+	let mut last_mc = None;
+	for counter in counters {
+		trace!("#[edac] {:?}", counter);
+		let id = counter.sensor_id();
+		if !SENSOR_REGISTRY.is_visible(&id) {
+			continue;
+		}
+		let entry = SENSOR_REGISTRY.register(
+			id,
+			format!(
+				"{} {} ({})",
+				counter.mc,
+				counter.dimm.as_deref().unwrap_or("total"),
+				counter.kind.as_str()
+			),
+			SensorKind::Other,
+			SensorUnit::None,
+		);
+		let head = entry.head_or(&counter.head());
+		if last_mc.as_ref() != Some(&counter.mc) {
+			rvbox.pack_start(
+				&ViLabel::new("info_vitextmeter", &**app_config, &counter.mc, Weight::Bold)
+					.set_margin_top(4)
+					.set_margin_start(4)
+					.set_margin_bottom(2)
+					.set_align(Align::Start),
+				false,
+				false,
+				0,
+			);
+			last_mc = Some(counter.mc.clone());
+		}
+
+		let stream = ViGraphArcSyncStream::with_len(len);
+		let (sender, recv) = async_channel::bounded(32);
+
+		let vimetr = ViMeter::new_visender(
+			app_config.clone(),
+			head.as_str(),
+			width,
+			height,
+			stream.clone(),
+			Some(vigraph_surface.clone()),
+			1.0,
+		);
+		vimetr.set_visible_graph(true);
+		rvbox.pack_start(&*vimetr, false, false, 0);
+
+		glib::MainContext::default().spawn_local(enc!((app_config) async move {
+			let mut f64sbuff = F64SBuff::new();
+			let color_config = app_config.get_color_app_config();
+
+			let mut old_current = None;
+			let mut last_increase: Option<Instant> = None;
+			while let Ok(event) = recv.recv().await {
+				match event {
+					EdacEvents::QueueDraw(current, is_increased) => {
+						if old_current != Some(current) {
+							vimetr.set_current_and_queue_draw(&f64sbuff.format_and_get(current as f64));
+							old_current = Some(current);
+						}
+						vimetr.queue_draw();
+
+						match (is_increased, last_increase) {
+							(true, _) => {
+								last_increase = Some(Instant::now());
+
+								vimetr.flash_and_queue_draw().await;
+							}
+							(false, Some(time)) if time.elapsed() >= EDAC_HIGHLIGHT_TIME => {
+								last_increase = None;
+
+								vimetr.set_color_and_queue_draw(color_config.green());
+							}
+							_ => {}
+						}
+					},
+				}
+			}
+		}));
+
+		let last_value = counter.read();
+		a_counters.push((counter, last_value, entry, stream, sender));
+	}
+
+	std::thread::spawn(move || {
+		loop {
+			for (counter, last_value, entry, stream, sender) in &mut a_counters {
+				let value = counter.read();
+				let increase = match (value, *last_value) {
+					(Some(value), Some(last_value)) if value > last_value => value - last_value,
+					_ => 0,
+				};
+				*last_value = value;
+
+				if increase != 0 {
+					warn!(
+						"#[edac] {}: {} +{} (total: {:?})",
+						counter.mc,
+						counter.head(),
+						increase,
+						value
+					);
+				}
+				stream.push_next(match increase {
+					0 => 0.0,
+					_ => 1.0,
+				});
+				if let Some(value) = value {
+					entry.update(value as f64, 0.0, (value as f64).max(1.0));
+				}
+
+				if sender
+					.send_blocking(EdacEvents::QueueDraw(value.unwrap_or_default(), increase != 0))
+					.is_err()
+				{
+					return;
+				}
+			}
+
+			std::thread::sleep(sampling_time);
+		}
+	});
+}
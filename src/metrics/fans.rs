@@ -0,0 +1,245 @@
+use crate::app::config::AppConfig;
+use crate::core::f64sbuff::F64SBuff;
+use crate::core::fancurve::FanCurvePwms;
+use crate::core::fancurve::PWM_ENABLE_AUTO;
+use crate::core::fancurve::PWM_ENABLE_MANUAL;
+use crate::core::fancurve::SYS_PATH;
+use crate::core::fancurve::hwmon_dirs;
+use crate::core::fancurve::pwm_to_duty;
+use crate::core::fancurve::read_sysfs_value;
+use crate::core::maybe::Maybe;
+use crate::core::sensorregistry::SENSOR_REGISTRY;
+use crate::core::sensorregistry::SensorId;
+use crate::core::sensorregistry::SensorKind;
+use crate::core::sensorregistry::SensorUnit;
+use crate::core::threshold::ThresholdLevel;
+use crate::widgets::ViMeter;
+use crate::widgets::notebook::ViNotebook;
+use crate::widgets::primitives::graph::background::ViGraphBackgroundSurface;
+use crate::widgets::primitives::graph::stream::ViGraphArcSyncStream;
+use crate::widgets::primitives::graph::stream::ViGraphStream;
+use crate::widgets::primitives::label::ViLabel;
+use gtk::Align;
+use gtk::traits::BoxExt;
+use log::error;
+use log::trace;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+
+/// Top of the RPM graph until a faster fan is seen (without `fan*_max`).
+pub const FAN_DEFAULT_MAX_RPM: f64 = 2000.0;
+
+/// `fan*_input` of a hwmon chip and the `pwm*` of the same number.
+#[derive(Debug, Clone)]
+pub struct HwmonFan {
+	pub chip: String,
+	/// `nct6798-platform-nct6775.656`, unique for the chips of the same name.
+	pub device: String,
+	pub index: u32,
+	pub input_path: PathBuf,
+	pub max_path: PathBuf,
+	pub pwm_path: PathBuf,
+	pub enable_path: PathBuf,
+}
+
+/// `<chip>-<bus>-<device>` of the `device` link like the libsensors chip
+/// names, `<chip>-<hwmonN>` for a chip without a device.
+fn hwmon_device_name(chip: &str, dir: &Path) -> String {
+	let link_name = |path: &Path| {
+		fs::read_link(path)
+			.ok()
+			.and_then(|a| a.file_name().map(|a| a.to_string_lossy().into_owned()))
+	};
+	match (
+		link_name(&dir.join("device/subsystem")),
+		link_name(&dir.join("device")),
+	) {
+		(Some(bus), Some(device)) => format!("{}-{}-{}", chip, bus, device),
+		_ => format!(
+			"{}-{}",
+			chip,
+			dir.file_name().unwrap_or_default().to_string_lossy()
+		),
+	}
+}
+
+pub fn hwmon_fans(sys_root: impl AsRef<Path>) -> Vec<HwmonFan> {
+	let mut result = Vec::new();
+	for (chip, dir) in hwmon_dirs(sys_root) {
+		let Ok(entries) = fs::read_dir(&dir) else {
+			continue;
+		};
+		let device = hwmon_device_name(&chip, &dir);
+		let mut indexes: Vec<u32> = entries
+			.filter_map(|a| a.ok())
+			.filter_map(|a| {
+				a.file_name()
+					.to_str()?
+					.strip_prefix("fan")?
+					.strip_suffix("_input")?
+					.parse()
+					.ok()
+			})
+			.collect();
+		indexes.sort_unstable();
+
+		result.extend(indexes.into_iter().map(|index| HwmonFan {
+			chip: chip.clone(),
+			device: device.clone(),
+			index,
+			input_path: dir.join(format!("fan{}_input", index)),
+			max_path: dir.join(format!("fan{}_max", index)),
+			pwm_path: dir.join(format!("pwm{}", index)),
+			enable_path: dir.join(format!("pwm{}_enable", index)),
+		}));
+	}
+
+	result
+}
+
+/// `duty: 45% (curve)`, `None` without a PWM.
+fn read_fan_duty(fan: &HwmonFan, is_curve: bool) -> Option<String> {
+	let pwm = read_sysfs_value::<u8>(&fan.pwm_path)?;
+	let mode = match read_sysfs_value::<u8>(&fan.enable_path) {
+		Some(PWM_ENABLE_MANUAL) if is_curve => "curve",
+		Some(PWM_ENABLE_MANUAL) => "manual",
+		Some(0) => "full speed",
+		Some(PWM_ENABLE_AUTO..) => "auto",
+		None => "unknown",
+	};
+
+	Some(format!("duty: {:.0}% ({})", pwm_to_duty(pwm), mode))
+}
+
+enum FansEvents {
+	QueueDraw(f64, f64, ThresholdLevel, Option<String>),
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn vinotebook_append_page(
+	app_config: &Rc<AppConfig>,
+	vigraph_surface: &ViGraphBackgroundSurface,
+	width: impl Maybe<i32> + Copy,
+	height: impl Maybe<i32> + Copy,
+	len: usize,
+	sampling_time: Duration,
+	fan_curves: &FanCurvePwms,
+
+	vinotebook: &ViNotebook,
+) {
+	let fans = hwmon_fans(SYS_PATH);
+	if fans.is_empty() {
+		error!(
+			"#[fans] No fans were found in the system, there is nothing to do on this platform."
+		);
+
+		return;
+	}
+
+	let mut a_fans = Vec::with_capacity(fans.len());
+	let rvbox = vinotebook.append_page(
+		&**app_config,
+		"fans",
+		Some("The duty of pwmN is shown under fanN of the same chip."),
+	);
+	for fan in fans {
+		trace!("#[fans] {:?}", fan);
+		let id = SensorId::new("fans", &fan.device, &format!("fan{}", fan.index));
+		if !SENSOR_REGISTRY.is_visible(&id) {
+			continue;
+		}
+		let entry = SENSOR_REGISTRY.register(
+			id,
+			format!("{} fan{}", fan.chip, fan.index),
+			SensorKind::Fan,
+			SensorUnit::Rpm,
+		);
+		let stream = ViGraphArcSyncStream::with_len(len);
+		let (sender, recv) = async_channel::bounded(32);
+
+		let vimetr = ViMeter::new_visender(
+			app_config.clone(),
+			&*entry.head_or(&format!("# {} fan{} (RPM)", fan.chip, fan.index)),
+			width,
+			height,
+			stream.clone(),
+			Some(vigraph_surface.clone()),
+			1.0,
+		);
+		vimetr.set_visible_graph(true);
+		vimetr.set_visible_limit(true);
+		rvbox.pack_start(&*vimetr, false, false, 0);
+		let duty_label = ViLabel::new("value", &**app_config, "", ())
+			.set_margin_start(4)
+			.set_margin_bottom(4)
+			.set_align(Align::Start)
+			.set_visible(false);
+		rvbox.pack_start(&duty_label, false, false, 0);
+
+		glib::MainContext::default().spawn_local(async move {
+			let mut f64sbuff = F64SBuff::new();
+
+			let mut old_max = Default::default();
+			let mut old_duty = None;
+			while let Ok(event) = recv.recv().await {
+				match event {
+					FansEvents::QueueDraw(current, max, level, duty) => {
+						vimetr.set_current_and_queue_draw(&f64sbuff.format_and_get(current));
+						if max != old_max {
+							vimetr.set_limit_and_queue_draw(&f64sbuff.format_and_get(max));
+							old_max = max;
+						}
+						vimetr.set_level_and_queue_draw(level);
+						if duty != old_duty {
+							duty_label.set_text(duty.as_deref().unwrap_or_default());
+							duty_label.set_visible2(duty.is_some());
+							old_duty = duty;
+						}
+						vimetr.queue_draw();
+					}
+				}
+			}
+		});
+
+		let max = read_sysfs_value::<f64>(&fan.max_path).filter(|a| *a > 0.0);
+		a_fans.push((
+			fan,
+			entry,
+			max.is_some(),
+			max.unwrap_or(FAN_DEFAULT_MAX_RPM),
+			stream,
+			sender,
+		));
+	}
+
+	let fan_curves = fan_curves.clone();
+	std::thread::spawn(move || {
+		loop {
+			for (fan, entry, is_fixed_max, max, stream, sender) in a_fans.iter_mut() {
+				let rpm = read_sysfs_value::<f64>(&fan.input_path).unwrap_or_default();
+				if !*is_fixed_max && rpm > *max {
+					*max = rpm;
+				}
+				let sample = entry.update(rpm, 0.0, *max);
+				stream.push_next(sample.fraction());
+
+				let is_curve = fan_curves.contains(&fan.pwm_path);
+				let duty = read_fan_duty(fan, is_curve);
+				let level = entry
+					.thresholds()
+					.level(sample.value, sample.min, sample.max);
+				if sender
+					.send_blocking(FansEvents::QueueDraw(sample.value, sample.max, level, duty))
+					.is_err()
+				{
+					return;
+				}
+			}
+
+			std::thread::sleep(sampling_time);
+		}
+	});
+}
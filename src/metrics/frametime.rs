@@ -0,0 +1,289 @@
+use crate::app::config::AppConfig;
+use crate::core::f64sbuff::F64SBuff;
+use crate::core::frametime::FrametimeStats;
+use crate::core::frametime::FrametimeSummary;
+use crate::core::frametimeserver::FrametimeClientEvent;
+use crate::core::frametimeserver::FrametimeServer;
+use crate::core::maybe::Maybe;
+use crate::core::sensorregistry::SENSOR_REGISTRY;
+use crate::core::sensorregistry::SensorEntry;
+use crate::core::sensorregistry::SensorId;
+use crate::core::sensorregistry::SensorKind;
+use crate::core::sensorregistry::SensorUnit;
+use crate::core::threshold::ThresholdLevel;
+use crate::widgets::ViMeter;
+use crate::widgets::notebook::ViNotebook;
+use crate::widgets::primitives::graph::background::ViGraphBackgroundSurface;
+use crate::widgets::primitives::graph::stream::ViGraphArcSyncStream;
+use crate::widgets::primitives::graph::stream::ViGraphStream;
+use crate::widgets::primitives::label::ViLabel;
+use gtk::Align;
+use gtk::pango::Weight;
+use gtk::traits::BoxExt;
+use log::info;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::mpsc;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Top of the frame time graph (ms), 20 FPS.
+pub const FRAMETIME_GRAPH_MAX_MS: f64 = 50.0;
+/// Window of the 1%/0.1% lows.
+pub const FRAMETIME_LOWS_WINDOW: Duration = Duration::from_secs(30);
+/// A client without frames for this long is no longer shown.
+pub const FRAMETIME_IDLE_TIMEOUT: Duration = Duration::from_secs(2);
+/// Heads and sensor ID features of the meters. The range of the FPS ones
+/// (for the percent thresholds) is up to the highest FPS seen.
+const FRAMETIME_METERS: [(&str, &str, SensorKind, SensorUnit); 4] = [
+	("# Frame time (ms)", "frametime", SensorKind::Frametime, SensorUnit::Millisecond),
+	("# FPS", "fps", SensorKind::Fps, SensorUnit::Fps),
+	("# 1% low (FPS)", "low_1", SensorKind::Fps, SensorUnit::Fps),
+	("# 0.1% low (FPS)", "low_01", SensorKind::Fps, SensorUnit::Fps),
+];
+
+#[derive(Debug)]
+struct FrametimeClient {
+	id: usize,
+	pid: Option<u32>,
+	exe: String,
+	stats: FrametimeStats,
+	last_frame: Instant,
+}
+
+impl FrametimeClient {
+	fn head(&self) -> String {
+		match self.pid {
+			Some(pid) => format!("{} ({})", self.exe, pid),
+			None => format!("client #{}", self.id),
+		}
+	}
+}
+
+enum FrametimeEvents {
+	Client(Option<String>),
+	QueueDraw([(f64, ThresholdLevel); 4]),
+}
+
+/// Rounded values and levels of the meters, the registry is updated (the
+/// hidden meters have no entry). Without frames the meters are zero and not
+/// highlighted.
+fn frametime_values(
+	summary: Option<FrametimeSummary>,
+	fps_max: &mut f64,
+	a_entries: &[Option<Arc<SensorEntry>>],
+) -> [(f64, ThresholdLevel); 4] {
+	let mut result = [(0.0, ThresholdLevel::Normal); 4];
+	let Some(summary) = summary else {
+		return result;
+	};
+	let values = [
+		(summary.frametime_ms * 100.0).round() / 100.0,
+		summary.fps.round(),
+		summary.low_1.round(),
+		summary.low_01.round(),
+	];
+	*fps_max = fps_max.max(values[1]);
+
+	for (i, (entry, value)) in a_entries.iter().zip(values).enumerate() {
+		let Some(entry) = entry else {
+			continue;
+		};
+		let max = match entry.kind() {
+			SensorKind::Fps => *fps_max,
+			_ => FRAMETIME_GRAPH_MAX_MS,
+		};
+		let sample = entry.update(value, 0.0, max);
+		result[i] = (
+			sample.value,
+			entry.thresholds().level(sample.value, sample.min, sample.max),
+		);
+	}
+
+	result
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn vinotebook_append_page(
+	app_config: &Rc<AppConfig>,
+	vigraph_surface: &ViGraphBackgroundSurface,
+	width: impl Maybe<i32> + Copy,
+	height: impl Maybe<i32> + Copy,
+	len: usize,
+	sampling_time: Duration,
+	server: &FrametimeServer,
+
+	vinotebook: &ViNotebook,
+) {
+	let rvbox = vinotebook.append_page(
+		&**app_config,
+		"fps",
+		Some(&format!("Frame timestamps are accepted on {:?}.", server.path())),
+	);
+	let client_label = ViLabel::new(
+		"info_vitextmeter",
+		&**app_config,
+		"No frames, waiting for a client.",
+		Weight::Bold,
+	)
+	.set_margin_top(4)
+	.set_margin_start(4)
+	.set_margin_bottom(2)
+	.set_align(Align::Start);
+	rvbox.pack_start(&client_label, false, false, 0);
+
+	let stream = ViGraphArcSyncStream::with_len(len);
+	let mut a_entries = Vec::with_capacity(FRAMETIME_METERS.len());
+	let vimeters: Vec<_> = FRAMETIME_METERS
+	.into_iter()
+	.enumerate()
+	.map(|(i, (head, feature, kind, unit))| {
+		let id = SensorId::new("frametime", "active", feature);
+		let is_visible = SENSOR_REGISTRY.is_visible(&id);
+		let entry = is_visible.then(|| SENSOR_REGISTRY.register(id, head.trim_start_matches("# "), kind, unit));
+		let head = entry.as_ref().map_or_else(|| head.to_string(), |a| a.head_or(head));
+		a_entries.push(entry);
+
+		let vimetr = ViMeter::new_visender(
+			app_config.clone(),
+			&*head,
+			width,
+			height,
+			stream.clone(),
+			Some(vigraph_surface.clone()),
+			1.0,
+		);
+		vimetr.set_visible_graph(i == 0);
+		vimetr.set_visible_limit(i == 0);
+		if is_visible {
+			rvbox.pack_start(&*vimetr, false, false, 0);
+		}
+
+		vimetr
+	})
+	.collect();
+
+	let (sender, recv) = async_channel::bounded(32);
+	glib::MainContext::default().spawn_local(async move {
+		let mut f64sbuff = F64SBuff::new();
+		vimeters[0].set_limit_and_queue_draw(&f64sbuff.format_and_get(FRAMETIME_GRAPH_MAX_MS));
+
+		while let Ok(event) = recv.recv().await {
+			match event {
+				FrametimeEvents::Client(head) => {
+					client_label.set_text(head.as_deref().unwrap_or("No frames, waiting for a client."));
+				},
+				FrametimeEvents::QueueDraw(values) => {
+					for (vimetr, (value, level)) in vimeters.iter().zip(values) {
+						vimetr.set_current_and_queue_draw(&f64sbuff.format_and_get(value));
+						vimetr.set_level_and_queue_draw(level);
+						vimetr.queue_draw();
+					}
+				},
+			}
+		}
+	});
+
+	let client_recv = server.subscribe();
+	std::thread::spawn(move || {
+		let mut clients: Vec<FrametimeClient> = Vec::new();
+		let mut active: Option<usize> = None;
+		let mut fps_max: f64 = 0.0;
+		let mut next_draw = Instant::now() + sampling_time;
+		loop {
+			match client_recv.recv_timeout(next_draw.saturating_duration_since(Instant::now())) {
+				Ok(FrametimeClientEvent::Hello(id, pid, exe)) => {
+					info!("#[frametime] client #{}: {} ({})", id, exe, pid);
+					match clients.iter_mut().find(|a| a.id == id) {
+						Some(client) => {
+							client.pid = Some(pid);
+							client.exe = exe;
+						}
+						None => clients.push(FrametimeClient {
+							id,
+							pid: Some(pid),
+							exe,
+							stats: FrametimeStats::new(FRAMETIME_LOWS_WINDOW.as_nanos() as u64),
+							last_frame: Instant::now(),
+						}),
+					}
+					if active == Some(id)
+						&& let Some(client) = clients.iter().find(|a| a.id == id)
+						&& sender.send_blocking(FrametimeEvents::Client(Some(client.head()))).is_err()
+					{
+						return;
+					}
+				}
+				Ok(FrametimeClientEvent::Frame(id, timestamp_ns)) => {
+					// the shown client stays until it is idle or gone
+					let is_new_active = match active {
+						Some(active) if active == id => false,
+						Some(active) => clients
+							.iter()
+							.find(|a| a.id == active)
+							.is_none_or(|a| a.last_frame.elapsed() >= FRAMETIME_IDLE_TIMEOUT),
+						None => true,
+					};
+					let index = match clients.iter().position(|a| a.id == id) {
+						Some(a) => a,
+						None => {
+							clients.push(FrametimeClient {
+								id,
+								pid: None,
+								exe: String::new(),
+								stats: FrametimeStats::new(FRAMETIME_LOWS_WINDOW.as_nanos() as u64),
+								last_frame: Instant::now(),
+							});
+
+							clients.len() - 1
+						}
+					};
+					let client = &mut clients[index];
+					client.last_frame = Instant::now();
+					let frametime = client.stats.push(timestamp_ns);
+
+					if is_new_active {
+						active = Some(id);
+						if sender.send_blocking(FrametimeEvents::Client(Some(client.head()))).is_err() {
+							return;
+						}
+					}
+					// only the frames of the shown client are in the graph
+					if active == Some(id)
+						&& let Some(frametime) = frametime
+					{
+						let frametime_ms = frametime as f64 / 1_000_000.0;
+						stream.push_next((frametime_ms / FRAMETIME_GRAPH_MAX_MS).min(1.0));
+					}
+				}
+				Ok(FrametimeClientEvent::Bye(id)) => {
+					clients.retain(|a| a.id != id);
+				}
+				Err(mpsc::RecvTimeoutError::Timeout) => {}
+				Err(mpsc::RecvTimeoutError::Disconnected) => return,
+			}
+
+			if Instant::now() >= next_draw {
+				next_draw = Instant::now() + sampling_time;
+
+				let client = active.and_then(|id| clients.iter().find(|a| a.id == id));
+				let summary = match client {
+					Some(client) if client.last_frame.elapsed() < FRAMETIME_IDLE_TIMEOUT => client.stats.summary(),
+					_ => {
+						if active.take().is_some()
+							&& sender.send_blocking(FrametimeEvents::Client(None)).is_err()
+						{
+							return;
+						}
+
+						None
+					}
+				};
+				let values = frametime_values(summary, &mut fps_max, &a_entries);
+				if sender.send_blocking(FrametimeEvents::QueueDraw(values)).is_err() {
+					return;
+				}
+			}
+		}
+	});
+}
@@ -0,0 +1,124 @@
+use crate::app::config::AppConfig;
+use crate::app::events::AppEventSender;
+use crate::core::gamemode::GameMode;
+use crate::core::gamemode::GameModeStatus;
+use crate::widgets::notebook::ViNotebook;
+use crate::widgets::primitives::label::ViLabel;
+use gtk::Align;
+use gtk::pango::Weight;
+use gtk::traits::BoxExt;
+use log::error;
+use log::info;
+use std::rc::Rc;
+use std::time::Duration;
+
+enum GameModeEvents {
+	Status(Option<GameModeStatus>),
+}
+
+pub fn vinotebook_append_page(
+	app_config: &Rc<AppConfig>,
+	sampling_time: Duration,
+	esender: &AppEventSender,
+
+	vinotebook: &ViNotebook,
+) {
+	let gamemode = match GameMode::new_session() {
+		Ok(a) => a,
+		Err(e) => {
+			error!("#[gamemode] I can't connect to the session bus: {}", e);
+
+			return;
+		}
+	};
+	let auto_show_hide = app_config.get_gamemode_app_config().get_auto_show_hide();
+
+	let rvbox = vinotebook.append_page(&**app_config, "gamemode", None);
+	let status_label = ViLabel::new(
+		"info_vitextmeter",
+		&**app_config,
+		"GameMode is not running.",
+		Weight::Bold,
+	)
+	.set_margin_top(4)
+	.set_margin_start(4)
+	.set_margin_bottom(2)
+	.set_align(Align::Start);
+	rvbox.pack_start(&status_label, false, false, 0);
+	let clients_label = ViLabel::new("value", &**app_config, "", ())
+		.set_margin_start(4)
+		.set_margin_bottom(4)
+		.set_align(Align::Start)
+		.set_visible(false);
+	rvbox.pack_start(&clients_label, false, false, 0);
+
+	let (sender, recv) = async_channel::bounded(8);
+	glib::MainContext::default().spawn_local(async move {
+		while let Ok(event) = recv.recv().await {
+			match event {
+				GameModeEvents::Status(None) => {
+					status_label.set_text("GameMode is not running.");
+					clients_label.set_visible2(false);
+				},
+				GameModeEvents::Status(Some(status)) => {
+					status_label.set_text(&match status.is_active() {
+						true => format!("GameMode: active ({} clients)", status.client_count),
+						false => "GameMode: inactive".to_string(),
+					});
+
+					let clients: Vec<String> = status
+						.clients
+						.iter()
+						.map(|a| match a.exe.is_empty() {
+							true => format!("{}", a.pid),
+							false => format!("{} {}", a.pid, a.exe),
+						})
+						.collect();
+					clients_label.set_text(&clients.join("\n"));
+					clients_label.set_visible2(!clients.is_empty());
+				},
+			}
+		}
+	});
+
+	let esender = esender.clone();
+	std::thread::spawn(move || {
+		let mut last_status: Option<GameModeStatus> = None;
+		let mut is_first = true;
+		loop {
+			let status = match gamemode.is_running() {
+				Ok(true) => gamemode.status().map_err(|e| error!("#[gamemode] {}", e)).ok(),
+				Ok(false) => None,
+				Err(e) => {
+					error!("#[gamemode] NameHasOwner: {}", e);
+
+					None
+				}
+			};
+
+			if is_first || status != last_status {
+				is_first = false;
+
+				let last_count = last_status.as_ref().map_or(0, |a| a.client_count);
+				let count = status.as_ref().map_or(0, |a| a.client_count);
+				if last_count != count {
+					info!("#[gamemode] clients: {} -> {}", last_count, count);
+				}
+				if auto_show_hide {
+					match (last_count, count) {
+						(0, 1..) => esender.show_window(),
+						(1.., 0) => esender.hide_window(),
+						_ => {}
+					}
+				}
+
+				if sender.send_blocking(GameModeEvents::Status(status.clone())).is_err() {
+					return;
+				}
+				last_status = status;
+			}
+
+			std::thread::sleep(sampling_time);
+		}
+	});
+}
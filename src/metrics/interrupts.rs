@@ -0,0 +1,459 @@
+use crate::app::config::AppConfig;
+use crate::core::f64sbuff::F64SBuff;
+use crate::core::maybe::Maybe;
+use crate::core::sensorregistry::SENSOR_REGISTRY;
+use crate::core::sensorregistry::SensorId;
+use crate::core::sensorregistry::SensorKind;
+use crate::core::sensorregistry::SensorUnit;
+use crate::widgets::ViMeter;
+use crate::widgets::notebook::ViNotebook;
+use crate::widgets::primitives::graph::background::ViGraphBackgroundSurface;
+use crate::widgets::primitives::graph::stream::ViGraphArcSyncStream;
+use crate::widgets::primitives::graph::stream::ViGraphStream;
+use crate::widgets::primitives::heatrow::ViHeatRow;
+use crate::widgets::primitives::heatrow::normalize_by_max;
+use crate::widgets::primitives::label::ViLabel;
+use gtk::Align;
+use gtk::pango::Weight;
+use gtk::traits::BoxExt;
+use gtk::traits::WidgetExt;
+use log::error;
+use log::trace;
+use std::cell::RefCell;
+use std::fs;
+use std::rc::Rc;
+use std::time::Duration;
+use std::time::Instant;
+
+pub const PROC_INTERRUPTS_PATH: &str = "/proc/interrupts";
+pub const PROC_SOFTIRQS_PATH: &str = "/proc/softirqs";
+
+/// One line of `/proc/interrupts` or `/proc/softirqs`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IrqLine {
+	pub name: String,
+	/// Chip, hwirq, type and devices, empty for softirqs.
+	pub desc: String,
+	/// Per-CPU counters, may be shorter than the number of CPUs (`ERR`, `MIS`).
+	pub counts: Vec<u64>,
+}
+
+impl IrqLine {
+	#[inline]
+	pub fn total(&self) -> u64 {
+		self.counts.iter().sum()
+	}
+
+	/// Device IRQ lines have a numeric name, the rest are arch-specific
+	/// (`LOC`, `RES`, `NMI`...).
+	#[inline]
+	pub fn is_device(&self) -> bool {
+		!self.name.is_empty() && self.name.bytes().all(|a| a.is_ascii_digit())
+	}
+
+	pub fn head(&self) -> String {
+		match (self.is_device(), self.desc.is_empty()) {
+			(true, _) => format!("# IRQ {}: {}", self.name, self.desc),
+			(false, true) => format!("# {}", self.name),
+			(false, false) => format!("# {}: {}", self.name, self.desc),
+		}
+	}
+}
+
+/// Parses the contents of `/proc/interrupts` or `/proc/softirqs`, returns the
+/// number of CPUs from the header and all lines.
+pub fn parse_proc_interrupts(data: &str) -> (usize, Vec<IrqLine>) {
+	let mut lines = data.lines();
+	let num_cpus = lines
+		.next()
+		.map_or(0, |head| head.split_whitespace().filter(|a| a.starts_with("CPU")).count());
+
+	let mut result = Vec::new();
+	for line in lines {
+		let Some((name, rest)) = line.split_once(':') else {
+			continue;
+		};
+		let name = name.trim();
+		if name.is_empty() {
+			continue;
+		}
+
+		let mut counts = Vec::with_capacity(num_cpus);
+		let mut rest = rest.trim_start();
+		while counts.len() < num_cpus {
+			let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+			match rest[..end].parse::<u64>() {
+				Ok(a) => counts.push(a),
+				Err(_) => break,
+			}
+			rest = rest[end..].trim_start();
+		}
+
+		result.push(IrqLine {
+			name: name.to_string(),
+			desc: rest.split_whitespace().collect::<Vec<_>>().join(" "),
+			counts,
+		});
+	}
+
+	(num_cpus, result)
+}
+
+/// Per-CPU rates (events/s) of a line between two snapshots.
+pub fn irq_rates(old: &IrqLine, new: &IrqLine, elapsed: Duration) -> Vec<f64> {
+	let elapsed = elapsed.as_secs_f64();
+	new.counts
+		.iter()
+		.enumerate()
+		.map(|(i, new)| match old.counts.get(i) {
+			Some(old) if new >= old && elapsed > 0.0 => (new - old) as f64 / elapsed,
+			_ => 0.0,
+		})
+		.collect()
+}
+
+/// The `count` busiest lines since boot.
+pub fn busiest_irq_lines(
+	lines: &[IrqLine],
+	count: usize,
+	filter: impl Fn(&IrqLine) -> bool,
+) -> Vec<IrqLine> {
+	let mut lines: Vec<_> = lines.iter().filter(|a| filter(a) && a.total() != 0).collect();
+	lines.sort_by_key(|a| std::cmp::Reverse(a.total()));
+
+	lines.into_iter().take(count).cloned().collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IrqSource {
+	Interrupts,
+	Softirqs,
+}
+
+impl IrqSource {
+	/// The device of the sensor IDs.
+	#[inline]
+	const fn as_str(self) -> &'static str {
+		match self {
+			Self::Interrupts => "irq",
+			Self::Softirqs => "softirq",
+		}
+	}
+
+	#[inline]
+	const fn path(self) -> &'static str {
+		match self {
+			Self::Interrupts => PROC_INTERRUPTS_PATH,
+			Self::Softirqs => PROC_SOFTIRQS_PATH,
+		}
+	}
+
+	/// A failure is logged once until the next successful read.
+	fn read(self, is_error_logged: &mut bool) -> Option<Vec<IrqLine>> {
+		match fs::read_to_string(self.path()) {
+			Ok(a) => {
+				*is_error_logged = false;
+
+				Some(parse_proc_interrupts(&a).1)
+			}
+			Err(e) => {
+				if !*is_error_logged {
+					error!("#[interrupts] I can't read {:?}: {}", self.path(), e);
+					*is_error_logged = true;
+				}
+
+				None
+			}
+		}
+	}
+}
+
+enum IrqEvents {
+	QueueDraw(f64, f64, Vec<f64>),
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn vinotebook_append_page(
+	app_config: &Rc<AppConfig>,
+	vigraph_surface: &ViGraphBackgroundSurface,
+	width: impl Maybe<i32> + Copy,
+	height: impl Maybe<i32> + Copy,
+	len: usize,
+	sampling_time: Duration,
+	irq_count: usize,
+	softirq_count: usize,
+	heat_row: bool,
+
+	vinotebook: &ViNotebook,
+) {
+	let mut a_lines = Vec::with_capacity(irq_count + softirq_count);
+	for (source, count, head, filter) in [
+		(
+			IrqSource::Interrupts,
+			irq_count,
+			"Busiest IRQ lines",
+			IrqLine::is_device as fn(&IrqLine) -> bool,
+		),
+		(IrqSource::Softirqs, softirq_count, "Busiest softirqs", |_: &IrqLine| true),
+	] {
+		if count == 0 {
+			continue;
+		}
+		if let Some(lines) = source.read(&mut false) {
+			let lines = busiest_irq_lines(&lines, count, filter);
+			if !lines.is_empty() {
+				a_lines.push((source, head, lines));
+			}
+		}
+	}
+	if a_lines.is_empty() {
+		error!("#[interrupts] No interrupts were found in the system, there is nothing to do on this platform.");
+
+		return;
+	}
+
+	let rvbox = vinotebook.append_page(&**app_config, "irq", None);
+	let mut a_sensors = Vec::with_capacity(irq_count + softirq_count);
+	for (source, head, lines) in a_lines {
+		rvbox.pack_start(
+			&ViLabel::new("info_vitextmeter", &**app_config, head, Weight::Bold)
+				.set_margin_top(4)
+				.set_margin_start(4)
+				.set_margin_bottom(2)
+				.set_align(Align::Start),
+			false,
+			false,
+			0,
+		);
+
+		for line in lines {
+			trace!("#[interrupts] {:?}: {:?}", source, line);
+			let id = SensorId::new("interrupts", source.as_str(), &line.name);
+			if !SENSOR_REGISTRY.is_visible(&id) {
+				continue;
+			}
+			let entry = SENSOR_REGISTRY.register(
+				id,
+				line.head().trim_start_matches("# "),
+				SensorKind::Other,
+				SensorUnit::PerSecond,
+			);
+			let stream = ViGraphArcSyncStream::with_len(len);
+			let (sender, recv) = async_channel::bounded(32);
+
+			let vimetr = ViMeter::new_visender(
+				app_config.clone(),
+				entry.head_or(&line.head()).as_str(),
+				width,
+				height,
+				stream.clone(),
+				Some(vigraph_surface.clone()),
+				1.0,
+			);
+			vimetr.set_visible_graph(true);
+			vimetr.set_visible_limit(true);
+			rvbox.pack_start(&*vimetr, false, false, 0);
+
+			let heat_values = Rc::new(RefCell::new(Vec::new()));
+			let viheatrow = heat_row.then(|| {
+				let viheatrow = ViHeatRow::new(6)
+					.connect_state_values(app_config.clone(), 1.0, &heat_values);
+				viheatrow.set_margin_bottom(6);
+				rvbox.pack_start(&viheatrow, false, false, 0);
+
+				viheatrow
+			});
+
+			glib::MainContext::default().spawn_local(async move {
+				let mut f64sbuff = F64SBuff::new();
+
+				let mut old_max = Default::default();
+				while let Ok(event) = recv.recv().await {
+					match event {
+						IrqEvents::QueueDraw(current, max, per_cpu) => {
+							vimetr.set_current_and_queue_draw(&f64sbuff.format_and_get(current.round()));
+							if max != old_max {
+								vimetr.set_limit_and_queue_draw(&f64sbuff.format_and_get(max.round()));
+								old_max = max;
+							}
+							vimetr.queue_draw();
+
+							if let Some(ref viheatrow) = viheatrow {
+								*RefCell::borrow_mut(&heat_values) = per_cpu;
+								viheatrow.queue_draw();
+							}
+						},
+					}
+				}
+			});
+
+			a_sensors.push((source, line, 1.0, entry, stream, sender));
+		}
+	}
+
+	std::thread::spawn(move || {
+		// the sources without meters are not read
+		let is_interrupts = a_sensors.iter().any(|a| a.0 == IrqSource::Interrupts);
+		let is_softirqs = a_sensors.iter().any(|a| a.0 == IrqSource::Softirqs);
+		let mut is_interrupts_error = false;
+		let mut is_softirqs_error = false;
+		let mut last_time = Instant::now();
+		loop {
+			std::thread::sleep(sampling_time);
+			let elapsed = last_time.elapsed();
+			last_time = Instant::now();
+
+			let interrupts = is_interrupts
+				.then(|| IrqSource::Interrupts.read(&mut is_interrupts_error))
+				.flatten()
+				.unwrap_or_default();
+			let softirqs = is_softirqs
+				.then(|| IrqSource::Softirqs.read(&mut is_softirqs_error))
+				.flatten()
+				.unwrap_or_default();
+			for (source, old_line, max, entry, stream, sender) in &mut a_sensors {
+				let new_line = match source {
+					IrqSource::Interrupts => &interrupts,
+					IrqSource::Softirqs => &softirqs,
+				}
+				.iter()
+				.find(|a| a.name == old_line.name);
+
+				let per_cpu = match new_line {
+					Some(new_line) => {
+						let rates = irq_rates(old_line, new_line, elapsed);
+						old_line.counts.clone_from(&new_line.counts);
+
+						rates
+					}
+					None => Vec::new(),
+				};
+				let current: f64 = per_cpu.iter().sum();
+				if current > *max {
+					*max = current;
+				}
+				let sample = entry.update(current, 0.0, *max);
+				stream.push_next(sample.fraction());
+
+				let per_cpu = normalize_by_max(per_cpu);
+
+				if sender
+					.send_blocking(IrqEvents::QueueDraw(sample.value, sample.max, per_cpu))
+					.is_err()
+				{
+					return;
+				}
+			}
+		}
+	});
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const INTERRUPTS: &str = "\
+           CPU0       CPU1       CPU2       CPU3
+  0:         44          0          0          0   IO-APIC   2-edge      timer
+  1:          0          0          0          9   IO-APIC   1-edge      i8042
+ 16:     123456          0       7890          0   IO-APIC  16-fasteoi   ehci_hcd:usb1, nvidia
+129:          0     987654          0          0  PCI-MSIX-0000:03:00.0   0-edge      nvme0q0
+130:          0          0          0          0  PCI-MSIX-0000:03:00.0   1-edge      nvme0q1
+NMI:         12         13         14         15   Non-maskable interrupts
+LOC:    5123456    4123456    3123456    2123456   Local timer interrupts
+ERR:          0
+MIS:          0
+";
+
+	const SOFTIRQS: &str = "\
+                    CPU0       CPU1       CPU2       CPU3
+          HI:          1          0          0          2
+       TIMER:     123456     234567     345678     456789
+      NET_TX:         10         20         30         40
+      NET_RX:       1000       2000       3000       4000
+       BLOCK:          0          0          0          0
+";
+
+	#[test]
+	fn proc_interrupts() {
+		let (num_cpus, lines) = parse_proc_interrupts(INTERRUPTS);
+		assert_eq!(num_cpus, 4);
+		assert_eq!(lines.len(), 9);
+
+		assert_eq!(
+			lines[2],
+			IrqLine {
+				name: "16".into(),
+				desc: "IO-APIC 16-fasteoi ehci_hcd:usb1, nvidia".into(),
+				counts: vec![123456, 0, 7890, 0],
+			}
+		);
+		assert!(lines[2].is_device());
+		assert_eq!(lines[2].total(), 131346);
+		assert_eq!(
+			lines[2].head(),
+			"# IRQ 16: IO-APIC 16-fasteoi ehci_hcd:usb1, nvidia"
+		);
+
+		let loc = lines.iter().find(|a| a.name == "LOC").unwrap();
+		assert!(!loc.is_device());
+		assert_eq!(loc.head(), "# LOC: Local timer interrupts");
+		// a single counter
+		let err = lines.iter().find(|a| a.name == "ERR").unwrap();
+		assert_eq!(err.counts, [0]);
+		assert_eq!(err.desc, "");
+	}
+
+	#[test]
+	fn proc_softirqs() {
+		let (num_cpus, lines) = parse_proc_interrupts(SOFTIRQS);
+		assert_eq!(num_cpus, 4);
+		assert_eq!(
+			lines.iter().map(|a| a.name.as_str()).collect::<Vec<_>>(),
+			["HI", "TIMER", "NET_TX", "NET_RX", "BLOCK"]
+		);
+		assert_eq!(lines[3].counts, [1000, 2000, 3000, 4000]);
+		assert_eq!(lines[3].head(), "# NET_RX");
+	}
+
+	#[test]
+	fn rates() {
+		let (_, old) = parse_proc_interrupts(SOFTIRQS);
+		let mut new = old[3].clone();
+		new.counts = vec![1500, 2000, 2900, 4100];
+		// a counter that went back (CPU hotplug) is 0
+		assert_eq!(
+			irq_rates(&old[3], &new, Duration::from_millis(500)),
+			[1000.0, 0.0, 0.0, 200.0]
+		);
+		assert_eq!(
+			irq_rates(&old[3], &new, Duration::ZERO),
+			[0.0, 0.0, 0.0, 0.0]
+		);
+
+		// a CPU missing from the old snapshot
+		new.counts.push(10);
+		assert_eq!(irq_rates(&old[3], &new, Duration::from_secs(1)).len(), 5);
+	}
+
+	#[test]
+	fn busiest() {
+		let (_, lines) = parse_proc_interrupts(INTERRUPTS);
+		let busiest = busiest_irq_lines(&lines, 2, IrqLine::is_device);
+		assert_eq!(
+			busiest.iter().map(|a| a.name.as_str()).collect::<Vec<_>>(),
+			["129", "16"]
+		);
+		// the idle lines are skipped
+		assert_eq!(busiest_irq_lines(&lines, 10, IrqLine::is_device).len(), 4);
+
+		let (_, lines) = parse_proc_interrupts(SOFTIRQS);
+		assert_eq!(
+			busiest_irq_lines(&lines, 3, |_| true)
+				.iter()
+				.map(|a| a.name.as_str())
+				.collect::<Vec<_>>(),
+			["TIMER", "NET_RX", "NET_TX"]
+		);
+	}
+}
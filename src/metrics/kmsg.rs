@@ -0,0 +1,377 @@
+use crate::app::config::AppConfig;
+use crate::widgets::notebook::ViNotebook;
+use crate::widgets::primitives::label::ViLabel;
+use enclose::enc;
+use gtk::Align;
+use gtk::Box;
+use gtk::pango::Weight;
+use gtk::pango::WrapMode;
+use gtk::traits::BoxExt;
+use gtk::traits::ContainerExt;
+use gtk::traits::WidgetExt;
+use log::error;
+use log::info;
+use log::trace;
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::fs::File;
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::ErrorKind;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::process::Command;
+use std::process::Stdio;
+use std::rc::Rc;
+use std::time::Duration;
+
+pub const DEV_KMSG_PATH: &str = "/dev/kmsg";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KernelEventKind {
+	ThermalThrottling,
+	OomKill,
+	GpuReset,
+	NvmeTimeout,
+	UsbDisconnect,
+}
+
+impl KernelEventKind {
+	#[inline]
+	pub const fn as_str(self) -> &'static str {
+		match self {
+			Self::ThermalThrottling => "Thermal throttling",
+			Self::OomKill => "OOM kill",
+			Self::GpuReset => "GPU reset/hang",
+			Self::NvmeTimeout => "NVMe timeout",
+			Self::UsbDisconnect => "USB disconnect",
+		}
+	}
+}
+
+impl Display for KernelEventKind {
+	#[inline]
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(self.as_str())
+	}
+}
+
+/// (kind, all of these substrings in the lowercase message)
+const KERNEL_EVENT_PATTERNS: &[(KernelEventKind, &[&str])] = &[
+	// `CPU3: Package temperature above threshold, cpu clock throttled (total events = 1)`
+	(KernelEventKind::ThermalThrottling, &["package temperature above threshold"]),
+	(KernelEventKind::ThermalThrottling, &["core temperature above threshold"]),
+	(KernelEventKind::ThermalThrottling, &["cpu clock throttled"]),
+	(KernelEventKind::OomKill, &["out of memory: kill"]),
+	(KernelEventKind::OomKill, &["invoked oom-killer"]),
+	(KernelEventKind::OomKill, &["oom-kill:"]),
+	(KernelEventKind::GpuReset, &["gpu reset"]),
+	(KernelEventKind::GpuReset, &["gpu hang"]),
+	(KernelEventKind::GpuReset, &["gpu recovery"]),
+	(KernelEventKind::GpuReset, &["amdgpu", "timeout"]),
+	(KernelEventKind::GpuReset, &["nvrm: xid"]),
+	(KernelEventKind::NvmeTimeout, &["nvme", "timeout"]),
+	(KernelEventKind::NvmeTimeout, &["nvme", "controller is down"]),
+	(KernelEventKind::NvmeTimeout, &["nvme", "resetting controller"]),
+	(KernelEventKind::UsbDisconnect, &["usb disconnect"]),
+];
+
+pub fn classify_kernel_message(message: &str) -> Option<KernelEventKind> {
+	let message = message.to_ascii_lowercase();
+
+	KERNEL_EVENT_PATTERNS
+		.iter()
+		.find(|(_, all)| all.iter().all(|a| message.contains(a)))
+		.map(|(kind, _)| *kind)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KernelRecord {
+	/// Time since boot (as in `dmesg`).
+	pub timestamp: Duration,
+	pub message: String,
+}
+
+/// Parses a `/dev/kmsg` record: `prio,seq,ts_usec,flags;message` followed by
+/// optional ` KEY=value` continuation lines.
+pub fn parse_kmsg_record(record: &str) -> Option<KernelRecord> {
+	let (head, message) = record.split_once(';')?;
+	let ts_usec: u64 = head.split(',').nth(2)?.parse().ok()?;
+	let message = message.lines().next().unwrap_or_default();
+
+	Some(KernelRecord {
+		timestamp: Duration::from_micros(ts_usec),
+		message: message.to_string(),
+	})
+}
+
+/// Parses a line of `journalctl --dmesg --output=short-monotonic`:
+/// `[  123.456789] host kernel: message`.
+pub fn parse_journal_kernel_line(line: &str) -> Option<KernelRecord> {
+	let (ts, rest) = line.trim_start().strip_prefix('[')?.split_once(']')?;
+	let ts: f64 = ts.trim().parse().ok()?;
+	let message = rest.split_once("kernel: ").map_or(rest, |(_, a)| a).trim();
+
+	Some(KernelRecord {
+		timestamp: Duration::from_secs_f64(ts),
+		message: message.to_string(),
+	})
+}
+
+/// Reads kernel messages as they appear, `/dev/kmsg` first, the `journalctl`
+/// kernel stream when `/dev/kmsg` is restricted. Blocks.
+pub fn follow_kernel_messages(mut next: impl FnMut(KernelRecord) -> bool) -> io::Result<()> {
+	match File::open(DEV_KMSG_PATH) {
+		Ok(mut file) => {
+			file.seek(SeekFrom::End(0))?;
+
+			let mut buf = vec![0; 8192];
+			loop {
+				match file.read(&mut buf) {
+					Ok(0) => return Ok(()),
+					Ok(len) => {
+						if let Some(record) = parse_kmsg_record(&String::from_utf8_lossy(&buf[..len]))
+							&& !next(record)
+						{
+							return Ok(());
+						}
+					}
+					// the record was overwritten in the ring buffer
+					Err(e) if e.kind() == ErrorKind::BrokenPipe => continue,
+					Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+					Err(e) => return Err(e),
+				}
+			}
+		}
+		Err(e) => {
+			info!(
+				"#[kmsg] I can't open {:?} ({}), fallback to journalctl.",
+				DEV_KMSG_PATH, e
+			);
+
+			let mut child = Command::new("journalctl")
+				.args(["--dmesg", "--follow", "--lines=0", "--output=short-monotonic"])
+				.stdin(Stdio::null())
+				.stdout(Stdio::piped())
+				.stderr(Stdio::null())
+				.spawn()?;
+			let stdout = child
+				.stdout
+				.take()
+				.ok_or_else(|| io::Error::other("journalctl without stdout"))?;
+
+			for line in BufReader::new(stdout).lines() {
+				if let Some(record) = parse_journal_kernel_line(&line?)
+					&& !next(record)
+				{
+					break;
+				}
+			}
+			let _e = child.kill();
+			let _e = child.wait();
+
+			Ok(())
+		}
+	}
+}
+
+enum KmsgEvents {
+	Event(KernelEventKind, KernelRecord),
+}
+
+pub fn vinotebook_append_page(app_config: &Rc<AppConfig>, max_events: usize, vinotebook: &ViNotebook) {
+	let rvbox = vinotebook.append_page(&**app_config, "events", None);
+	let empty_label = ViLabel::new(
+		"info_vitextmeter",
+		&**app_config,
+		"No kernel events yet (thermal throttling, OOM kills, GPU resets, NVMe timeouts, USB disconnects).",
+		(),
+	)
+	.set_margin_top(4)
+	.set_margin_start(4)
+	.set_margin_end(4)
+	.set_margin_bottom(4)
+	.set_wrap(true)
+	.set_wrap_mode(WrapMode::Word)
+	.set_max_width_chars(45)
+	.set_align(Align::Start);
+	rvbox.pack_start(&empty_label, false, false, 0);
+
+	let (sender, recv) = async_channel::bounded(64);
+	std::thread::spawn(move || {
+		let result = follow_kernel_messages(|record| {
+			match classify_kernel_message(&record.message) {
+				Some(kind) => {
+					trace!("#[kmsg] {}: {:?}", kind, record);
+
+					sender.send_blocking(KmsgEvents::Event(kind, record)).is_ok()
+				}
+				None => true,
+			}
+		});
+
+		if let Err(e) = result {
+			error!("#[kmsg] Kernel messages are not available: {}", e);
+		}
+	});
+
+	glib::MainContext::default().spawn_local(enc!((app_config) async move {
+		let mut rows: VecDeque<Box> = VecDeque::with_capacity(max_events);
+		while let Ok(event) = recv.recv().await {
+			match event {
+				KmsgEvents::Event(kind, record) => {
+					empty_label.set_visible2(false);
+
+					let row = Box::new(gtk::Orientation::Vertical, 0);
+					row.pack_start(
+						&ViLabel::new(
+							"info_vitextmeter",
+							&*app_config,
+							&format!(
+								"[{:>5}.{:06}] {}",
+								record.timestamp.as_secs(),
+								record.timestamp.subsec_micros(),
+								kind
+							),
+							Weight::Bold,
+						)
+						.set_margin_top(4)
+						.set_margin_start(4)
+						.set_align(Align::Start),
+						false,
+						false,
+						0,
+					);
+					row.pack_start(
+						&ViLabel::new("value", &*app_config, &record.message, ())
+							.set_margin_start(4)
+							.set_margin_end(4)
+							.set_margin_bottom(2)
+							.set_wrap(true)
+							.set_wrap_mode(WrapMode::WordChar)
+							.set_max_width_chars(45)
+							.set_align(Align::Start),
+						false,
+						false,
+						0,
+					);
+					row.set_visible(true);
+
+					// newest first
+					rvbox.pack_start(&row, false, false, 0);
+					rvbox.reorder_child(&row, 0);
+					rows.push_front(row);
+
+					while rows.len() > max_events {
+						if let Some(row) = rows.pop_back() {
+							rvbox.remove(&row);
+						}
+					}
+				}
+			}
+		}
+	}));
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn classify() {
+		for (message, kind) in [
+			(
+				"mce: CPU2: Package temperature above threshold, cpu clock throttled (total events = 3)",
+				Some(KernelEventKind::ThermalThrottling),
+			),
+			(
+				"CPU0: Core temperature above threshold, cpu clock throttled (total events = 1)",
+				Some(KernelEventKind::ThermalThrottling),
+			),
+			("CPU0: Core temperature/speed normal", None),
+			(
+				"thermal thermal_zone7: failed to read out thermal zone (-61)",
+				None,
+			),
+			(
+				"Out of memory: Killed process 4321 (chrome) total-vm:9876543kB, anon-rss:123456kB",
+				Some(KernelEventKind::OomKill),
+			),
+			(
+				"firefox invoked oom-killer: gfp_mask=0x140cca(GFP_HIGHUSER_MOVABLE|__GFP_COMP), order=0, oom_score_adj=0",
+				Some(KernelEventKind::OomKill),
+			),
+			(
+				"amdgpu 0000:03:00.0: amdgpu: GPU reset begin!",
+				Some(KernelEventKind::GpuReset),
+			),
+			(
+				"[drm:amdgpu_job_timedout [amdgpu]] *ERROR* ring gfx_0.0.0 timeout, signaled seq=1234, emitted seq=1236",
+				Some(KernelEventKind::GpuReset),
+			),
+			(
+				"NVRM: Xid (PCI:0000:01:00): 79, pid='<unknown>', name=<unknown>, GPU has fallen off the bus.",
+				Some(KernelEventKind::GpuReset),
+			),
+			(
+				"nvme nvme0: I/O tag 12 (100c) QID 4 timeout, aborting",
+				Some(KernelEventKind::NvmeTimeout),
+			),
+			(
+				"usb 1-2: USB disconnect, device number 5",
+				Some(KernelEventKind::UsbDisconnect),
+			),
+			(
+				"usb 1-2: new high-speed USB device number 6 using xhci_hcd",
+				None,
+			),
+		] {
+			assert_eq!(classify_kernel_message(message), kind, "{}", message);
+		}
+	}
+
+	#[test]
+	fn kmsg_record() {
+		assert_eq!(
+			parse_kmsg_record(
+				"4,1234,5678901234,-;mce: CPU2: Package temperature above threshold, cpu clock throttled (total events = 3)\n"
+			),
+			Some(KernelRecord {
+				timestamp: Duration::from_micros(5678901234),
+				message: "mce: CPU2: Package temperature above threshold, cpu clock throttled (total events = 3)".to_string(),
+			})
+		);
+		// continuation lines
+		assert_eq!(
+			parse_kmsg_record(
+				"6,1500,1000000,-,caller=T123;usb 1-2: USB disconnect, device number 5\n SUBSYSTEM=usb\n DEVICE=c189:4\n"
+			),
+			Some(KernelRecord {
+				timestamp: Duration::from_secs(1),
+				message: "usb 1-2: USB disconnect, device number 5".to_string(),
+			})
+		);
+		assert_eq!(parse_kmsg_record("no record"), None);
+		assert_eq!(parse_kmsg_record("6,1500,now,-;message"), None);
+	}
+
+	#[test]
+	fn journal_kernel_line() {
+		assert_eq!(
+			parse_journal_kernel_line(
+				"[ 1234.567890] myhost kernel: nvme nvme0: I/O tag 12 (100c) QID 4 timeout, aborting"
+			),
+			Some(KernelRecord {
+				timestamp: Duration::from_secs_f64(1234.56789),
+				message: "nvme nvme0: I/O tag 12 (100c) QID 4 timeout, aborting".to_string(),
+			})
+		);
+		assert_eq!(
+			parse_journal_kernel_line("-- Logs begin at Mon 2026-10-19 00:00:00 UTC. --"),
+			None
+		);
+		assert_eq!(parse_journal_kernel_line("[  abc] myhost kernel: x"), None);
+	}
+}
@@ -1,6 +1,12 @@
 use crate::app::config::AppConfig;
 use crate::core::f64sbuff::F64SBuff;
 use crate::core::maybe::Maybe;
+use crate::core::sensoroverride::SensorOverride;
+use crate::core::sensorregistry::SENSOR_REGISTRY;
+use crate::core::sensorregistry::SensorId;
+use crate::core::sensorregistry::SensorKind;
+use crate::core::sensorregistry::SensorUnit;
+use crate::core::threshold::ThresholdLevel;
 use crate::widgets::ViMeter;
 use crate::widgets::notebook::ViNotebook;
 use crate::widgets::primitives::graph::background::ViGraphBackgroundSurface;
@@ -19,6 +25,7 @@ use lm_sensors::Value;
 use lm_sensors::value::Unit;
 use log::error;
 use log::trace;
+use log::warn;
 use std::num::NonZeroUsize;
 use std::rc::Rc;
 use std::sync::Arc;
@@ -26,6 +33,56 @@ use std::sync::Barrier;
 use std::sync::OnceLock;
 use std::time::Duration;
 
+/// Readings outside of these bounds are errors of the chip.
+const LM_VALUE_MAX: f64 = 65261.0;
+const LM_VALUE_MIN: f64 = -273.0;
+/// The range without min/max/crit of the chip and of `[sensors.override]`.
+const LM_DEFAULT_MIN: f64 = 0.0;
+const LM_DEFAULT_MAX: f64 = 100.0;
+
+#[inline]
+fn is_sane_value(v: f64) -> bool {
+	v < LM_VALUE_MAX && v > LM_VALUE_MIN
+}
+
+/// Insane readings are chip errors, so is zero without a `[sensors.override]`
+/// range; with one, the readings far outside of it.
+#[inline]
+fn is_valid_input(v: f64, over: &SensorOverride) -> bool {
+	if !is_sane_value(v) {
+		return false;
+	}
+
+	match (over.get_min(), over.get_max()) {
+		(None, None) => v != 0.0,
+		_ => over.is_in_range(over.calibrate(v)),
+	}
+}
+
+/// The position of `v` in the graph, `0.0` for an empty range (min == max).
+#[inline]
+fn range_fraction(v: f64, min: f64, max: f64) -> f64 {
+	match max - min {
+		range if range > 0.0 => (v - min) / range,
+		_ => 0.0,
+	}
+}
+
+/// A calibrated min/max/crit of the chip.
+fn read_limit(
+	limit: &Option<(f64, Unit, SubFeatureRef<'_>)>,
+	is_zero_allowed: bool,
+	over: &SensorOverride,
+) -> Option<f64> {
+	let (_startv, _unit, sensor) = limit.as_ref()?;
+
+	sensor
+		.raw_value()
+		.ok()
+		.filter(|v| (is_zero_allowed || *v != 0.0) && is_sane_value(*v))
+		.map(|v| over.calibrate(v))
+}
+
 #[repr(transparent)]
 #[derive(Debug)]
 struct OnceWaitResult<T>(Arc<_WaitResult<T>>);
@@ -83,7 +140,7 @@ struct LmItem {
 }
 
 enum LmEvents {
-	QueueDraw(f64, f64),
+	QueueDraw(f64, f64, ThresholdLevel),
 }
 
 struct ChipInfo {
@@ -115,6 +172,7 @@ pub fn vinotebook_append_page(
 	vinotebook: &ViNotebook,
 ) {
 	let waitinitlist: OnceWaitResult<(Vec<LmItem>, Option<String>)> = OnceWaitResult::new();
+	let sensors_config = app_config.get_sensors_app_config().clone();
 	std::thread::spawn(enc!((waitinitlist) move || {
 		let mut exp_init_sensors = Vec::with_capacity(12);
 
@@ -125,13 +183,17 @@ pub fn vinotebook_append_page(
 				if let Ok(chip_name) = chip.name() {
 					trace!("{} (chip_name):", chip_name);
 					let mut chip_info = Some(ChipInfo {
-						name: chip_name,
+						name: chip_name.clone(),
 						bus: chip.bus().to_string(),
 					});
 
 					for feature in chip.feature_iter() {
 						if let Some(Ok(feature_name)) = feature.name() {
-							trace!("	{}(feature_name): {}(feature)", feature_name, feature);
+							let id = SensorId::new("lm_sensors", &chip_name, feature_name);
+							trace!("	{}(feature_name): {}(feature), {}(id)", feature_name, feature, id);
+							if !SENSOR_REGISTRY.is_visible(&id) {
+								continue;
+							}
 
 
 							#[derive(Debug, Clone, Default)]
@@ -288,20 +350,33 @@ pub fn vinotebook_append_page(
 							if c_value.input.is_some() && c_value.r#type != SensorType::Unknown {
 								let stream = ViGraphArcSyncStream::with_len(len);
 								let (sender, recv) = async_channel::bounded(32);
+								let (kind, unit) = match c_value.r#type {
+									SensorType::Temperature => (SensorKind::Temperature, SensorUnit::Celsius),
+									SensorType::Power => (SensorKind::Power, SensorUnit::Watt),
+									SensorType::Current => (SensorKind::Current, SensorUnit::Ampere),
+									SensorType::Unknown => (SensorKind::Other, SensorUnit::None),
+								};
+								let entry = SENSOR_REGISTRY.register(id, feature_name, kind, unit);
 								exp_init_sensors.push(LmItem {
 									chip_info: chip_info.take(),
-									feature_name: feature_name.to_string(),
+									feature_name: entry.name().to_string(),
 									stream: stream.clone(),
 									recv,
 								});
 
-								a_sensors.push((c_value, stream, sender));
+								a_sensors.push((c_value, entry, stream, sender));
 							}
 						}
 					}
 				}
 			}
 
+			for id in sensors_config.override_ids() {
+				if id.starts_with("lm_sensors/") && SENSOR_REGISTRY.get(id).is_none() {
+					warn!("#[lm_sensors] [sensors.override] {:?} was not found (or is hidden)", id);
+				}
+			}
+
 			if let Err(_exp_init_sensors) = waitinitlist.set_and_waitend((exp_init_sensors, lmsensors
 				.version()
 				.map(|a| format!("lm_sensors: {}", a)))) {
@@ -312,37 +387,24 @@ pub fn vinotebook_append_page(
 
 			if !a_sensors.is_empty() {
 				loop {
-					for (asensor, stream, sender) in &a_sensors {
-						let mut min = 0.0;
-						if let Some((_startv, _unit, sensor)) = asensor.min {
-							if let Ok(v) = sensor.raw_value() {
-								if v < 65261.0 && v > -273.0 {
-									min = v;
-								}
-							}
-						};
-						let mut max = 100.0;
-						if let Some((_startv, _unit, sensor)) = asensor.max {
-							if let Ok(v) = sensor.raw_value() {
-								if v != 0.0 && v < 65261.0 && v > -273.0 {
-									max = v;
-								}
-							}
-						}else if let Some((_startv, _unit, sensor)) = asensor.crit {
-							if let Ok(v) = sensor.raw_value() {
-								if v != 0.0 && v < 65261.0 && v > -273.0 {
-									max = v;
-								}
-							}
-						}
+					for (asensor, entry, stream, sender) in &a_sensors {
+						// AppConfig, then the chip, then the fallback
+						let over = entry.sensor_override();
+						let (min, max) = over.range(
+							read_limit(&asensor.min, true, over).unwrap_or(LM_DEFAULT_MIN),
+							read_limit(&asensor.max, false, over)
+								.or_else(|| over.get_crit())
+								.or_else(|| read_limit(&asensor.crit, false, over))
+								.unwrap_or(LM_DEFAULT_MAX),
+						);
 						if let Some((_startv, _unit, sensor)) = asensor.input {
 							let mut exp_elements = graph_count_elements_on_onestep.get();
-							let mut current = 0.0;
+							let mut last_raw = None;
 							loop {
 								if let Ok(v) = sensor.raw_value() {
-									if v != 0.0 && v < 65261.0 && v > -273.0 {
-										current = v;
-										let a = (v - min) / (max - min);
+									if is_valid_input(v, over) {
+										last_raw = Some(v);
+										let a = range_fraction(over.calibrate(v), min, max);
 
 										stream.write(|stream| {
 											stream.push_next(a);
@@ -357,7 +419,11 @@ pub fn vinotebook_append_page(
 								std::thread::sleep(graph_time_onestep);
 							}
 
-							let _e = sender.send_blocking(LmEvents::QueueDraw(current, max));
+							if let Some(v) = last_raw {
+								let sample = entry.update(v, min, max);
+								let level = entry.thresholds().level(sample.value, sample.min, sample.max);
+								let _e = sender.send_blocking(LmEvents::QueueDraw(sample.value, sample.max, level));
+							}
 						}
 					}
 
@@ -461,7 +527,7 @@ pub fn vinotebook_append_page(
 					let mut old_max = Default::default();
 					while let Ok(event) = item.recv().await {
 						match event {
-							LmEvents::QueueDraw(current, max) => {
+							LmEvents::QueueDraw(current, max, level) => {
 								if current != old_current {
 									vimetr.set_current_and_queue_draw(&f64sbuff.format_and_get(current));
 									old_current = current;
@@ -470,6 +536,7 @@ pub fn vinotebook_append_page(
 									vimetr.set_limit_and_queue_draw(&f64sbuff.format_and_get(max));
 									old_max = max;
 								}
+								vimetr.set_level_and_queue_draw(level);
 
 								vimetr.queue_draw();
 							},
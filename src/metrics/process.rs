@@ -0,0 +1,327 @@
+use crate::app::config::AppConfig;
+use crate::core::f64sbuff::F64SBuff;
+use crate::core::maybe::Maybe;
+use crate::core::sensorregistry::SENSOR_REGISTRY;
+use crate::core::sensorregistry::SensorId;
+use crate::core::sensorregistry::SensorKind;
+use crate::core::sensorregistry::SensorUnit;
+use crate::widgets::ViMeter;
+use crate::widgets::notebook::ViNotebook;
+use crate::widgets::primitives::graph::background::ViGraphBackgroundSurface;
+use crate::widgets::primitives::graph::stream::ViGraphArcSyncStream;
+use crate::widgets::primitives::graph::stream::ViGraphStream;
+use crate::widgets::primitives::label::ViLabel;
+use gtk::Align;
+use gtk::pango::Weight;
+use gtk::traits::BoxExt;
+use log::info;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+pub const PROC_PATH: &str = "/proc";
+pub const PROC_MEMINFO_PATH: &str = "/proc/meminfo";
+
+/// `utime`/`stime` are in clock ticks, `USER_HZ` is 100 on every Linux ABI.
+const USER_HZ: f64 = 100.0;
+
+/// The root of the process tree being monitored (`machinepmmeter run`).
+#[derive(Debug, Clone, Default)]
+pub struct ProcessTarget(Arc<Mutex<Option<(u32, String)>>>);
+
+impl ProcessTarget {
+	pub fn set(&self, pid: u32, exe: String) {
+		info!("#[process] Attach to {} ({})", exe, pid);
+		if let Ok(mut a) = self.0.lock() {
+			*a = Some((pid, exe));
+		}
+	}
+
+	/// Detaches only if `pid` is still the target.
+	pub fn clear(&self, pid: u32) {
+		if let Ok(mut a) = self.0.lock()
+			&& a.as_ref().is_some_and(|(a, _)| *a == pid)
+		{
+			info!("#[process] Detach from {}", pid);
+			*a = None;
+		}
+	}
+
+	pub fn get(&self) -> Option<(u32, String)> {
+		self.0.lock().ok()?.clone()
+	}
+}
+
+/// Fields of `/proc/<pid>/stat`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProcStat {
+	pub pid: u32,
+	pub ppid: u32,
+	/// `utime + stime` (clock ticks).
+	pub cpu_ticks: u64,
+	pub num_threads: u64,
+}
+
+pub fn parse_proc_stat(data: &str) -> Option<ProcStat> {
+	let (pid, rest) = data.split_once(" (")?;
+	// `comm` may contain spaces and parentheses
+	let (_comm, rest) = rest.rsplit_once(") ")?;
+	// from `state` (field 3)
+	let fields: Vec<&str> = rest.split_whitespace().collect();
+	let field = |n: usize| -> Option<u64> { fields.get(n - 3)?.parse().ok() };
+
+	Some(ProcStat {
+		pid: pid.trim().parse().ok()?,
+		ppid: field(4)? as u32,
+		cpu_ticks: field(14)? + field(15)?,
+		num_threads: field(20)?,
+	})
+}
+
+/// `pid` and all its descendants.
+pub fn process_tree(all: &[ProcStat], pid: u32) -> Vec<ProcStat> {
+	let mut children: HashMap<u32, Vec<&ProcStat>> = HashMap::new();
+	for a in all {
+		children.entry(a.ppid).or_default().push(a);
+	}
+
+	let mut result: Vec<ProcStat> = all.iter().filter(|a| a.pid == pid).copied().collect();
+	let mut i = 0;
+	while let Some(pid) = result.get(i).map(|a| a.pid) {
+		if let Some(children) = children.get(&pid) {
+			result.extend(children.iter().copied());
+		}
+		i += 1;
+	}
+
+	result
+}
+
+pub fn read_all_proc_stats(root: impl AsRef<Path>) -> Vec<ProcStat> {
+	let Ok(dir) = fs::read_dir(root.as_ref()) else {
+		return Vec::new();
+	};
+
+	dir.filter_map(|a| a.ok())
+		.filter(|a| a.file_name().to_str().is_some_and(|a| a.bytes().all(|a| a.is_ascii_digit())))
+		.filter_map(|a| fs::read_to_string(a.path().join("stat")).ok())
+		.filter_map(|a| parse_proc_stat(&a))
+		.collect()
+}
+
+/// `<key>: <value> kB` of `/proc/meminfo` or `/proc/<pid>/status` (bytes).
+fn read_kb_field(path: impl AsRef<Path>, key: &str) -> Option<u64> {
+	let data = fs::read_to_string(path).ok()?;
+	let kb: u64 = data
+		.lines()
+		.find_map(|a| a.strip_prefix(key)?.strip_prefix(':'))?
+		.split_whitespace()
+		.next()?
+		.parse()
+		.ok()?;
+
+	Some(kb * 1024)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProcessMeter {
+	Cpu,
+	Rss,
+	Processes,
+	Threads,
+}
+
+impl ProcessMeter {
+	#[inline]
+	const fn head(self) -> &'static str {
+		match self {
+			Self::Cpu => "# CPU (%)",
+			Self::Rss => "# Memory RSS (MiB)",
+			Self::Processes => "# Processes",
+			Self::Threads => "# Threads",
+		}
+	}
+
+	/// The feature of the sensor ID.
+	#[inline]
+	const fn sensor(self) -> (&'static str, SensorUnit) {
+		match self {
+			Self::Cpu => ("cpu", SensorUnit::Percent),
+			Self::Rss => ("rss", SensorUnit::Mebibyte),
+			Self::Processes => ("processes", SensorUnit::None),
+			Self::Threads => ("threads", SensorUnit::None),
+		}
+	}
+}
+
+enum ProcessEvents {
+	QueueDraw(f64, f64),
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn vinotebook_append_page(
+	app_config: &Rc<AppConfig>,
+	vigraph_surface: &ViGraphBackgroundSurface,
+	width: impl Maybe<i32> + Copy,
+	height: impl Maybe<i32> + Copy,
+	len: usize,
+	sampling_time: Duration,
+	target: &ProcessTarget,
+
+	vinotebook: &ViNotebook,
+) {
+	let rvbox = vinotebook.append_page(
+		&**app_config,
+		"process",
+		Some("Started with `machinepmmeter run -- <command>`."),
+	);
+	let target_label = ViLabel::new(
+		"info_vitextmeter",
+		&**app_config,
+		"No process attached.",
+		Weight::Bold,
+	)
+	.set_margin_top(4)
+	.set_margin_start(4)
+	.set_margin_bottom(2)
+	.set_align(Align::Start);
+	rvbox.pack_start(&target_label, false, false, 0);
+
+	let num_cpus = std::thread::available_parallelism().map_or(1, |a| a.get()) as f64;
+	let mem_total = read_kb_field(PROC_MEMINFO_PATH, "MemTotal").unwrap_or(1) as f64 / (1024.0 * 1024.0);
+	let (target_sender, target_recv) = async_channel::bounded(8);
+	let mut a_meters = Vec::with_capacity(4);
+	for meter in [
+		ProcessMeter::Cpu,
+		ProcessMeter::Rss,
+		ProcessMeter::Processes,
+		ProcessMeter::Threads,
+	] {
+		let (feature, unit) = meter.sensor();
+		let id = SensorId::new("process", "target", feature);
+		if !SENSOR_REGISTRY.is_visible(&id) {
+			continue;
+		}
+		let entry = SENSOR_REGISTRY.register(
+			id,
+			meter.head().trim_start_matches("# "),
+			SensorKind::Other,
+			unit,
+		);
+		let stream = ViGraphArcSyncStream::with_len(len);
+		let (sender, recv) = async_channel::bounded(32);
+
+		let vimetr = ViMeter::new_visender(
+			app_config.clone(),
+			&*entry.head_or(meter.head()),
+			width,
+			height,
+			stream.clone(),
+			Some(vigraph_surface.clone()),
+			1.0,
+		);
+		vimetr.set_visible_graph(matches!(meter, ProcessMeter::Cpu | ProcessMeter::Rss));
+		vimetr.set_visible_limit(true);
+		rvbox.pack_start(&*vimetr, false, false, 0);
+
+		glib::MainContext::default().spawn_local(async move {
+			let mut f64sbuff = F64SBuff::new();
+
+			let mut old_max = Default::default();
+			while let Ok(event) = recv.recv().await {
+				match event {
+					ProcessEvents::QueueDraw(current, max) => {
+						vimetr.set_current_and_queue_draw(&f64sbuff.format_and_get(current));
+						if max != old_max {
+							vimetr.set_limit_and_queue_draw(&f64sbuff.format_and_get(max));
+							old_max = max;
+						}
+						vimetr.queue_draw();
+					},
+				}
+			}
+		});
+
+		let max = match meter {
+			ProcessMeter::Cpu => num_cpus * 100.0,
+			ProcessMeter::Rss => mem_total.round(),
+			_ => 1.0,
+		};
+		a_meters.push((meter, max, entry, stream, sender));
+	}
+
+	glib::MainContext::default().spawn_local(async move {
+		while let Ok(head) = target_recv.recv().await {
+			target_label.set_text(head.as_deref().unwrap_or("No process attached."));
+		}
+	});
+
+	let target = target.clone();
+	std::thread::spawn(move || {
+		let mut last_target = None;
+		let mut last_ticks: Option<u64> = None;
+		let mut last_time = Instant::now();
+		loop {
+			std::thread::sleep(sampling_time);
+			let elapsed = last_time.elapsed();
+			last_time = Instant::now();
+
+			let current_target = target.get();
+			if current_target != last_target {
+				last_ticks = None;
+				let head: Option<String> = current_target.as_ref().map(|(pid, exe)| format!("{} ({})", exe, pid));
+				if target_sender.send_blocking(head).is_err() {
+					return;
+				}
+				last_target = current_target.clone();
+			}
+			let Some((pid, _)) = current_target else {
+				continue;
+			};
+
+			let tree = process_tree(&read_all_proc_stats(PROC_PATH), pid);
+			let ticks: u64 = tree.iter().map(|a| a.cpu_ticks).sum();
+			let cpu = match last_ticks {
+				// exited children take their ticks with them
+				Some(last) if ticks >= last => {
+					(ticks - last) as f64 / USER_HZ / elapsed.as_secs_f64() * 100.0
+				}
+				_ => 0.0,
+			};
+			last_ticks = Some(ticks);
+
+			for (meter, max, entry, stream, sender) in &mut a_meters {
+				let current = match meter {
+					ProcessMeter::Cpu => cpu.round(),
+					ProcessMeter::Rss => {
+						let rss: u64 = tree
+							.iter()
+							.filter_map(|a| read_kb_field(Path::new(PROC_PATH).join(a.pid.to_string()).join("status"), "VmRSS"))
+							.sum();
+
+						(rss as f64 / (1024.0 * 1024.0)).round()
+					}
+					ProcessMeter::Processes => tree.len() as f64,
+					ProcessMeter::Threads => tree.iter().map(|a| a.num_threads).sum::<u64>() as f64,
+				};
+				if current > *max {
+					*max = current;
+				}
+				let sample = entry.update(current, 0.0, *max);
+				stream.push_next(sample.fraction());
+
+				if sender
+					.send_blocking(ProcessEvents::QueueDraw(sample.value, sample.max))
+					.is_err()
+				{
+					return;
+				}
+			}
+		}
+	});
+}
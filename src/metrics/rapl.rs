@@ -0,0 +1,354 @@
+use crate::app::config::AppConfig;
+use crate::app::helper::connect_helper;
+use crate::core::f64sbuff::F64SBuff;
+use crate::core::helperproto::HelperClient;
+use crate::core::maybe::Maybe;
+use crate::core::sensorregistry::SENSOR_REGISTRY;
+use crate::core::sensorregistry::SensorId;
+use crate::core::sensorregistry::SensorKind;
+use crate::core::sensorregistry::SensorUnit;
+use crate::core::threshold::ThresholdLevel;
+use crate::widgets::ViMeter;
+use crate::widgets::notebook::ViNotebook;
+use crate::widgets::primitives::graph::background::ViGraphBackgroundSurface;
+use crate::widgets::primitives::graph::stream::ViGraphArcSyncStream;
+use crate::widgets::primitives::graph::stream::ViGraphStream;
+use crate::widgets::primitives::label::ViLabel;
+use gtk::Align;
+use gtk::traits::BoxExt;
+use log::error;
+use log::info;
+use log::trace;
+use log::warn;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+use std::time::Instant;
+
+pub const POWERCAP_PATH: &str = "/sys/class/powercap";
+pub const AMD_MSR_RAPL_POWER_UNIT: u32 = 0xc0010299;
+pub const AMD_MSR_PKG_ENERGY_STAT: u32 = 0xc001029b;
+/// Top of the power graph until a higher power is seen.
+pub const RAPL_DEFAULT_MAX_W: f64 = 15.0;
+/// `ryzenadj --info` is slow, it is read every n samples.
+const RYZENADJ_EVERY: usize = 5;
+
+/// A powercap zone (`intel-rapl:0` - package-0, `intel-rapl:0:0` - core, ...),
+/// also on AMD.
+#[derive(Debug, Clone)]
+pub struct RaplZone {
+	/// `intel-rapl:0:1`
+	pub zone: String,
+	pub name: String,
+	pub energy_path: PathBuf,
+	/// The counter wraps around at this value.
+	pub max_energy_range_uj: Option<u64>,
+}
+
+pub fn rapl_zones(root: impl AsRef<Path>) -> Vec<RaplZone> {
+	let Ok(dir) = fs::read_dir(root.as_ref()) else {
+		return Vec::new();
+	};
+
+	let mut result: Vec<RaplZone> = dir
+		.filter_map(|a| a.ok())
+		.filter_map(|a| {
+			let dir_name = a.file_name().to_str()?.to_string();
+			if !dir_name.starts_with("intel-rapl:") {
+				return None;
+			}
+			let path = a.path();
+			let name = fs::read_to_string(path.join("name")).ok()?;
+
+			Some(RaplZone {
+				zone: dir_name,
+				name: name.trim().to_string(),
+				energy_path: path.join("energy_uj"),
+				max_energy_range_uj: fs::read_to_string(path.join("max_energy_range_uj"))
+					.ok()
+					.and_then(|a| a.trim().parse().ok()),
+			})
+		})
+		.collect();
+	result.sort_by(|a, b| a.zone.cmp(&b.zone));
+
+	result
+}
+
+/// `energy_uj` is root-only (since Linux 5.10), the helper is asked then.
+fn read_energy_uj(zone: &RaplZone, helper: Option<&mut HelperClient>) -> io::Result<u64> {
+	let value = match fs::read_to_string(&zone.energy_path) {
+		Ok(a) => a,
+		Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+			match (helper, zone.energy_path.to_str()) {
+				(Some(helper), Some(path)) => helper.read(path)?,
+				_ => return Err(e),
+			}
+		}
+		Err(e) => return Err(e),
+	};
+
+	value
+		.trim()
+		.parse()
+		.map_err(|_| io::Error::new(io::ErrorKind::InvalidData, value))
+}
+
+/// Joules of the AMD package energy counter (MSR, without powercap) and the
+/// joules at which the 32-bit counter wraps around.
+fn read_amd_msr_energy_j(helper: &mut HelperClient) -> io::Result<(f64, f64)> {
+	let unit = helper.read_msr(0, AMD_MSR_RAPL_POWER_UNIT)?;
+	let energy = helper.read_msr(0, AMD_MSR_PKG_ENERGY_STAT)?;
+	// energy status units, bits 12:8
+	let esu = (unit >> 8) & 0x1f;
+
+	let unit_j = (1u64 << esu) as f64;
+
+	Ok((
+		(energy & 0xffff_ffff) as f64 / unit_j,
+		(1u64 << 32) as f64 / unit_j,
+	))
+}
+
+fn is_amd_cpu() -> bool {
+	fs::read_to_string("/proc/cpuinfo").is_ok_and(|a| a.contains("AuthenticAMD"))
+}
+
+/// `stapm-limit: 25.000`, limits and temperatures of `ryzenadj --info`.
+fn format_ryzenadj_info(pairs: &[(String, String)]) -> String {
+	pairs
+		.iter()
+		.filter(|(key, _)| key.ends_with("-limit") || key.ends_with("-temp"))
+		.map(|(key, value)| format!("{}: {}", key, value))
+		.collect::<Vec<_>>()
+		.join("\n")
+}
+
+enum RaplEvents {
+	QueueDraw(f64, f64, ThresholdLevel),
+}
+
+enum RaplSource {
+	Zones(Vec<RaplZone>),
+	AmdMsr,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn vinotebook_append_page(
+	app_config: &Rc<AppConfig>,
+	vigraph_surface: &ViGraphBackgroundSurface,
+	width: impl Maybe<i32> + Copy,
+	height: impl Maybe<i32> + Copy,
+	len: usize,
+	sampling_time: Duration,
+
+	vinotebook: &ViNotebook,
+) {
+	let zones = rapl_zones(POWERCAP_PATH);
+	// head, device and feature of the sensor ID
+	let (source, heads): (RaplSource, Vec<(String, String, String)>) = match zones.is_empty() {
+		false => {
+			let heads = zones
+				.iter()
+				.map(|a| (format!("# {} (W)", a.name), a.zone.clone(), a.name.clone()))
+				.collect();
+
+			(RaplSource::Zones(zones), heads)
+		}
+		true if is_amd_cpu() => (
+			RaplSource::AmdMsr,
+			vec![(
+				"# package (W, MSR)".to_string(),
+				"msr".to_string(),
+				"package".to_string(),
+			)],
+		),
+		true => {
+			error!(
+				"#[rapl] No RAPL zones were found in the system, there is nothing to do on this platform."
+			);
+
+			return;
+		}
+	};
+	let is_pkexec = app_config.get_helper_app_config().get_pkexec();
+
+	let rvbox = vinotebook.append_page(
+		&**app_config,
+		"rapl",
+		Some("Root-only readings come from machinepmmeter-helper."),
+	);
+	let mut a_meters = Vec::with_capacity(heads.len());
+	for (index, (head, device, feature)) in heads.into_iter().enumerate() {
+		let id = SensorId::new("rapl", &device, &feature);
+		if !SENSOR_REGISTRY.is_visible(&id) {
+			continue;
+		}
+		let entry = SENSOR_REGISTRY.register(id, feature, SensorKind::Power, SensorUnit::Watt);
+		let stream = ViGraphArcSyncStream::with_len(len);
+		let (sender, recv) = async_channel::bounded(32);
+
+		let vimetr = ViMeter::new_visender(
+			app_config.clone(),
+			&*entry.head_or(&head),
+			width,
+			height,
+			stream.clone(),
+			Some(vigraph_surface.clone()),
+			1.0,
+		);
+		vimetr.set_visible_graph(true);
+		vimetr.set_visible_limit(true);
+		rvbox.pack_start(&*vimetr, false, false, 0);
+
+		glib::MainContext::default().spawn_local(async move {
+			let mut f64sbuff = F64SBuff::new();
+
+			let mut old_max = Default::default();
+			while let Ok(event) = recv.recv().await {
+				match event {
+					RaplEvents::QueueDraw(current, max, level) => {
+						vimetr.set_current_and_queue_draw(&f64sbuff.format_and_get(current));
+						if max != old_max {
+							vimetr.set_limit_and_queue_draw(&f64sbuff.format_and_get(max));
+							old_max = max;
+						}
+						vimetr.set_level_and_queue_draw(level);
+						vimetr.queue_draw();
+					}
+				}
+			}
+		});
+
+		a_meters.push((
+			index,
+			RAPL_DEFAULT_MAX_W,
+			None::<f64>,
+			entry,
+			stream,
+			sender,
+		));
+	}
+	let ryzenadj_label = ViLabel::new("value", &**app_config, "", ())
+		.set_margin_start(4)
+		.set_margin_bottom(4)
+		.set_align(Align::Start)
+		.set_visible(false);
+	rvbox.pack_start(&ryzenadj_label, false, false, 0);
+
+	let (ryzenadj_sender, ryzenadj_recv) = async_channel::bounded::<String>(4);
+	glib::MainContext::default().spawn_local(async move {
+		while let Ok(info) = ryzenadj_recv.recv().await {
+			ryzenadj_label.set_text(&info);
+			ryzenadj_label.set_visible2(!info.is_empty());
+		}
+	});
+
+	std::thread::spawn(move || {
+		// only if the unprivileged readings are not enough
+		let is_helper_required = match source {
+			RaplSource::Zones(ref zones) => zones.iter().any(|a| {
+				fs::read_to_string(&a.energy_path)
+					.is_err_and(|e| e.kind() == io::ErrorKind::PermissionDenied)
+			}),
+			RaplSource::AmdMsr => true,
+		};
+		let mut helper = None;
+		if is_helper_required {
+			helper = connect_helper(is_pkexec);
+			match helper {
+				Some(_) => info!("#[rapl] Energy counters are read through the helper."),
+				None => {
+					warn!("#[rapl] Energy counters are root-only and the helper is not running.")
+				}
+			}
+		}
+		let mut is_ryzenadj = helper.is_some() && is_amd_cpu();
+
+		let mut time = Instant::now();
+		for i in 0usize.. {
+			let elapsed = std::mem::replace(&mut time, Instant::now()).elapsed();
+			for (index, max, last_energy, entry, stream, sender) in a_meters.iter_mut() {
+				let (energy_j, range_j) = match source {
+					RaplSource::Zones(ref zones) => {
+						let zone = &zones[*index];
+						let energy = read_energy_uj(zone, helper.as_mut())
+							.map(|a| a as f64 / 1_000_000.0)
+							.map_err(|e| trace!("#[rapl] {}: {}", zone.name, e));
+
+						(
+							energy,
+							zone.max_energy_range_uj
+								.map_or(f64::MAX, |a| a as f64 / 1_000_000.0),
+						)
+					}
+					RaplSource::AmdMsr => {
+						let energy = match helper {
+							Some(ref mut helper) => read_amd_msr_energy_j(helper)
+								.map_err(|e| trace!("#[rapl] MSR: {}", e)),
+							None => Err(()),
+						};
+
+						match energy {
+							Ok((energy, range)) => (Ok(energy), range),
+							Err(e) => (Err(e), f64::MAX),
+						}
+					}
+				};
+
+				let power = match (energy_j, *last_energy) {
+					(Ok(energy), Some(last)) if i > 0 => {
+						let delta = match energy >= last {
+							true => energy - last,
+							false => energy + range_j - last,
+						};
+
+						delta / elapsed.as_secs_f64()
+					}
+					_ => 0.0,
+				};
+				*last_energy = energy_j.ok();
+				let power = (power * 10.0).round() / 10.0;
+				if power > *max && power.is_finite() && power < 10_000.0 {
+					*max = power.ceil();
+				}
+				let sample = entry.update(power, 0.0, *max);
+				stream.push_next(sample.fraction());
+
+				let level = entry
+					.thresholds()
+					.level(sample.value, sample.min, sample.max);
+				if sender
+					.send_blocking(RaplEvents::QueueDraw(sample.value, sample.max, level))
+					.is_err()
+				{
+					return;
+				}
+			}
+
+			if is_ryzenadj && i % RYZENADJ_EVERY == 0 {
+				if let Some(ref mut helper) = helper {
+					match helper.ryzenadj_info() {
+						Ok(pairs) => {
+							if ryzenadj_sender
+								.send_blocking(format_ryzenadj_info(&pairs))
+								.is_err()
+							{
+								return;
+							}
+						}
+						Err(e) => {
+							warn!("#[rapl] ryzenadj: {}", e);
+							is_ryzenadj = false;
+						}
+					}
+				}
+			}
+
+			std::thread::sleep(sampling_time);
+		}
+	});
+}
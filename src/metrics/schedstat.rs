@@ -0,0 +1,414 @@
+use crate::app::config::AppConfig;
+use crate::core::f64sbuff::F64SBuff;
+use crate::core::maybe::Maybe;
+use crate::core::sensorregistry::SENSOR_REGISTRY;
+use crate::core::sensorregistry::SensorId;
+use crate::core::sensorregistry::SensorKind;
+use crate::core::sensorregistry::SensorUnit;
+use crate::widgets::ViMeter;
+use crate::widgets::notebook::ViNotebook;
+use crate::widgets::primitives::graph::background::ViGraphBackgroundSurface;
+use crate::widgets::primitives::graph::stream::ViGraphArcSyncStream;
+use crate::widgets::primitives::graph::stream::ViGraphStream;
+use crate::widgets::primitives::heatrow::ViHeatRow;
+use crate::widgets::primitives::heatrow::normalize_by_max;
+use gtk::traits::BoxExt;
+use gtk::traits::WidgetExt;
+use log::error;
+use log::trace;
+use std::cell::RefCell;
+use std::fs;
+use std::rc::Rc;
+use std::time::Duration;
+use std::time::Instant;
+
+pub const PROC_SCHEDSTAT_PATH: &str = "/proc/schedstat";
+pub const PROC_LOADAVG_PATH: &str = "/proc/loadavg";
+pub const PROC_STAT_PATH: &str = "/proc/stat";
+
+/// `cpuN` line of `/proc/schedstat`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SchedstatCpu {
+	pub cpu: u32,
+	/// Time spent running by tasks on this CPU (ns).
+	pub run_time: u64,
+	/// Time spent waiting to run by tasks on this CPU (ns).
+	pub run_delay: u64,
+	/// Number of timeslices run on this CPU.
+	pub timeslices: u64,
+}
+
+pub fn parse_schedstat(data: &str) -> Vec<SchedstatCpu> {
+	let mut result = Vec::new();
+	for line in data.lines() {
+		let mut fields = line.split_whitespace();
+		let Some(cpu) = fields
+			.next()
+			.and_then(|a| a.strip_prefix("cpu"))
+			.and_then(|a| a.parse().ok())
+		else {
+			continue;
+		};
+
+		// yld_count, legacy, sched_count, sched_goidle, ttwu_count, ttwu_local,
+		// rq_cpu_time, run_delay, pcount
+		let fields: Vec<u64> = fields.filter_map(|a| a.parse().ok()).collect();
+		if let [_, _, _, _, _, _, run_time, run_delay, timeslices, ..] = fields[..] {
+			result.push(SchedstatCpu {
+				cpu,
+				run_time,
+				run_delay,
+				timeslices,
+			});
+		}
+	}
+
+	result
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LoadAvg {
+	pub avg1: f64,
+	pub avg5: f64,
+	pub avg15: f64,
+}
+
+pub fn parse_loadavg(data: &str) -> Option<LoadAvg> {
+	let mut fields = data.split_whitespace();
+	let avg1 = fields.next()?.parse().ok()?;
+	let avg5 = fields.next()?.parse().ok()?;
+	let avg15 = fields.next()?.parse().ok()?;
+
+	Some(LoadAvg { avg1, avg5, avg15 })
+}
+
+/// `procs_running` and `procs_blocked` of `/proc/stat`.
+pub fn parse_stat_procs(data: &str) -> (Option<u64>, Option<u64>) {
+	let mut running = None;
+	let mut blocked = None;
+	for line in data.lines() {
+		if let Some(a) = line.strip_prefix("procs_running ") {
+			running = a.trim().parse().ok();
+		} else if let Some(a) = line.strip_prefix("procs_blocked ") {
+			blocked = a.trim().parse().ok();
+		}
+	}
+
+	(running, blocked)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SchedMeter {
+	RunQueueWait,
+	Timeslices,
+	LoadAvg,
+	Running,
+	Blocked,
+}
+
+impl SchedMeter {
+	#[inline]
+	const fn head(self) -> &'static str {
+		match self {
+			Self::RunQueueWait => "# Run-queue wait (ms/s)",
+			Self::Timeslices => "# Timeslices (/s)",
+			Self::LoadAvg => "# Load average (1 min)",
+			Self::Running => "# Running tasks",
+			Self::Blocked => "# Blocked tasks",
+		}
+	}
+
+	/// The feature of the sensor ID.
+	#[inline]
+	const fn sensor(self) -> (&'static str, SensorUnit) {
+		match self {
+			Self::RunQueueWait => ("run_queue_wait", SensorUnit::MillisecondPerSecond),
+			Self::Timeslices => ("timeslices", SensorUnit::PerSecond),
+			Self::LoadAvg => ("loadavg1", SensorUnit::None),
+			Self::Running => ("running", SensorUnit::None),
+			Self::Blocked => ("blocked", SensorUnit::None),
+		}
+	}
+
+	#[inline]
+	const fn is_per_cpu(self) -> bool {
+		matches!(self, Self::RunQueueWait | Self::Timeslices)
+	}
+}
+
+#[derive(Debug, Default)]
+struct SchedSnapshot {
+	schedstat: Vec<SchedstatCpu>,
+	loadavg: Option<LoadAvg>,
+	running: Option<u64>,
+	blocked: Option<u64>,
+}
+
+impl SchedSnapshot {
+	fn read() -> Self {
+		let (running, blocked) = fs::read_to_string(PROC_STAT_PATH)
+			.map(|a| parse_stat_procs(&a))
+			.unwrap_or_default();
+
+		Self {
+			schedstat: fs::read_to_string(PROC_SCHEDSTAT_PATH)
+				.map(|a| parse_schedstat(&a))
+				.unwrap_or_default(),
+			loadavg: fs::read_to_string(PROC_LOADAVG_PATH)
+				.ok()
+				.and_then(|a| parse_loadavg(&a)),
+			running,
+			blocked,
+		}
+	}
+
+	/// Per-CPU rates between two snapshots.
+	fn schedstat_rates(
+		&self,
+		old: &Self,
+		elapsed: Duration,
+		get: impl Fn(&SchedstatCpu) -> u64,
+	) -> Vec<f64> {
+		let elapsed = elapsed.as_secs_f64();
+		self.schedstat
+			.iter()
+			.map(|new| match old.schedstat.iter().find(|a| a.cpu == new.cpu) {
+				Some(old) if get(new) >= get(old) && elapsed > 0.0 => {
+					(get(new) - get(old)) as f64 / elapsed
+				}
+				_ => 0.0,
+			})
+			.collect()
+	}
+}
+
+enum SchedEvents {
+	QueueDraw(f64, f64, Vec<f64>),
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn vinotebook_append_page(
+	app_config: &Rc<AppConfig>,
+	vigraph_surface: &ViGraphBackgroundSurface,
+	width: impl Maybe<i32> + Copy,
+	height: impl Maybe<i32> + Copy,
+	len: usize,
+	sampling_time: Duration,
+	heat_row: bool,
+
+	vinotebook: &ViNotebook,
+) {
+	let snapshot = SchedSnapshot::read();
+	let num_cpus = std::thread::available_parallelism().map_or(1, |a| a.get()) as f64;
+
+	let mut meters = Vec::with_capacity(5);
+	if snapshot.schedstat.is_empty() {
+		error!("#[schedstat] {:?} is not available (CONFIG_SCHEDSTATS), run-queue meters are disabled.", PROC_SCHEDSTAT_PATH);
+	} else {
+		meters.extend([SchedMeter::RunQueueWait, SchedMeter::Timeslices]);
+	}
+	if snapshot.loadavg.is_some() {
+		meters.push(SchedMeter::LoadAvg);
+	}
+	if snapshot.running.is_some() {
+		meters.push(SchedMeter::Running);
+	}
+	if snapshot.blocked.is_some() {
+		meters.push(SchedMeter::Blocked);
+	}
+	if meters.is_empty() {
+		error!("#[schedstat] No scheduler statistics were found in the system, there is nothing to do on this platform.");
+
+		return;
+	}
+
+	let rvbox = vinotebook.append_page(&**app_config, "sched", None);
+	let mut a_meters = Vec::with_capacity(meters.len());
+	for meter in meters {
+		trace!("#[schedstat] {:?}", meter);
+		let (feature, unit) = meter.sensor();
+		let id = SensorId::new("schedstat", "system", feature);
+		if !SENSOR_REGISTRY.is_visible(&id) {
+			continue;
+		}
+		let entry = SENSOR_REGISTRY.register(
+			id,
+			meter.head().trim_start_matches("# "),
+			SensorKind::Other,
+			unit,
+		);
+		let stream = ViGraphArcSyncStream::with_len(len);
+		let (sender, recv) = async_channel::bounded(32);
+
+		let vimetr = ViMeter::new_visender(
+			app_config.clone(),
+			&*entry.head_or(meter.head()),
+			width,
+			height,
+			stream.clone(),
+			Some(vigraph_surface.clone()),
+			1.0,
+		);
+		vimetr.set_visible_graph(true);
+		vimetr.set_visible_limit(true);
+		rvbox.pack_start(&*vimetr, false, false, 0);
+
+		let heat_values = Rc::new(RefCell::new(Vec::new()));
+		let viheatrow = (heat_row && meter.is_per_cpu()).then(|| {
+			let viheatrow = ViHeatRow::new(6)
+				.connect_state_values(app_config.clone(), 1.0, &heat_values);
+			viheatrow.set_margin_bottom(6);
+			rvbox.pack_start(&viheatrow, false, false, 0);
+
+			viheatrow
+		});
+
+		glib::MainContext::default().spawn_local(async move {
+			let mut f64sbuff = F64SBuff::new();
+
+			let mut old_current = Default::default();
+			let mut old_max = Default::default();
+			while let Ok(event) = recv.recv().await {
+				match event {
+					SchedEvents::QueueDraw(current, max, per_cpu) => {
+						if current != old_current {
+							vimetr.set_current_and_queue_draw(&f64sbuff.format_and_get(current));
+							old_current = current;
+						}
+						if max != old_max {
+							vimetr.set_limit_and_queue_draw(&f64sbuff.format_and_get(max));
+							old_max = max;
+						}
+						vimetr.queue_draw();
+
+						if let Some(ref viheatrow) = viheatrow {
+							*RefCell::borrow_mut(&heat_values) = per_cpu;
+							viheatrow.queue_draw();
+						}
+					},
+				}
+			}
+		});
+
+		let max = match meter {
+			SchedMeter::LoadAvg | SchedMeter::Running => num_cpus,
+			_ => 1.0,
+		};
+		a_meters.push((meter, max, entry, stream, sender));
+	}
+
+	std::thread::spawn(move || {
+		let mut old_snapshot = snapshot;
+		let mut last_time = Instant::now();
+		loop {
+			std::thread::sleep(sampling_time);
+			let elapsed = last_time.elapsed();
+			last_time = Instant::now();
+
+			let snapshot = SchedSnapshot::read();
+			for (meter, max, entry, stream, sender) in &mut a_meters {
+				let (current, per_cpu) = match meter {
+					SchedMeter::RunQueueWait => {
+						let per_cpu: Vec<f64> = snapshot
+							.schedstat_rates(&old_snapshot, elapsed, |a| a.run_delay)
+							.into_iter()
+							.map(|a| a / 1_000_000.0) // ns/s -> ms/s
+							.collect();
+
+						(per_cpu.iter().sum::<f64>().round(), per_cpu)
+					}
+					SchedMeter::Timeslices => {
+						let per_cpu = snapshot.schedstat_rates(&old_snapshot, elapsed, |a| a.timeslices);
+
+						(per_cpu.iter().sum::<f64>().round(), per_cpu)
+					}
+					SchedMeter::LoadAvg => (snapshot.loadavg.map_or(0.0, |a| a.avg1), Vec::new()),
+					SchedMeter::Running => (snapshot.running.unwrap_or_default() as f64, Vec::new()),
+					SchedMeter::Blocked => (snapshot.blocked.unwrap_or_default() as f64, Vec::new()),
+				};
+
+				if current > *max {
+					*max = current;
+				}
+				let sample = entry.update(current, 0.0, *max);
+				stream.push_next(sample.fraction());
+
+				let per_cpu = normalize_by_max(per_cpu);
+
+				if sender
+					.send_blocking(SchedEvents::QueueDraw(sample.value, sample.max, per_cpu))
+					.is_err()
+				{
+					return;
+				}
+			}
+
+			old_snapshot = snapshot;
+		}
+	});
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn schedstat_v15() {
+		let data = "\
+version 15
+timestamp 4295212345
+cpu0 0 0 1234 567 890 123 98765432100 1234567890 456789
+domain0 00000003 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0
+cpu1 0 0 1000 500 800 100 87654321000 234567890 345678
+domain0 00000003 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0
+cpu2 0 0 1 2 3
+cpuX 0 0 0 0 0 0 1 2 3
+";
+		assert_eq!(
+			parse_schedstat(data),
+			[
+				SchedstatCpu {
+					cpu: 0,
+					run_time: 98765432100,
+					run_delay: 1234567890,
+					timeslices: 456789,
+				},
+				SchedstatCpu {
+					cpu: 1,
+					run_time: 87654321000,
+					run_delay: 234567890,
+					timeslices: 345678,
+				},
+			]
+		);
+		assert!(parse_schedstat("version 15\ntimestamp 1\n").is_empty());
+	}
+
+	#[test]
+	fn loadavg() {
+		assert_eq!(
+			parse_loadavg("0.54 0.23 0.14 2/71 14245\n"),
+			Some(LoadAvg {
+				avg1: 0.54,
+				avg5: 0.23,
+				avg15: 0.14,
+			})
+		);
+		assert_eq!(parse_loadavg("0.54 0.23"), None);
+		assert_eq!(parse_loadavg("a b c"), None);
+	}
+
+	#[test]
+	fn stat_procs() {
+		let data = "\
+cpu  10132153 290696 3084719 46828483 16683 0 25195 0 0 0
+ctxt 1990473
+btime 1062191376
+processes 2915
+procs_running 3
+procs_blocked 1
+";
+		assert_eq!(parse_stat_procs(data), (Some(3), Some(1)));
+		assert_eq!(parse_stat_procs("procs_running x\n"), (None, None));
+	}
+}
@@ -0,0 +1,288 @@
+use crate::app::config::AppConfig;
+use crate::core::f64sbuff::F64SBuff;
+use crate::core::maybe::Maybe;
+use crate::core::sensorregistry::SENSOR_REGISTRY;
+use crate::core::sensorregistry::SensorId;
+use crate::core::sensorregistry::SensorKind;
+use crate::core::sensorregistry::SensorUnit;
+use crate::widgets::ViMeter;
+use crate::widgets::notebook::ViNotebook;
+use crate::widgets::primitives::graph::background::ViGraphBackgroundSurface;
+use crate::widgets::primitives::graph::stream::ViGraphArcSyncStream;
+use crate::widgets::primitives::graph::stream::ViGraphStream;
+use enclose::enc;
+use gtk::traits::BoxExt;
+use log::error;
+use log::trace;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+use std::time::Instant;
+
+pub const CPU_SYSFS_PATH: &str = "/sys/devices/system/cpu";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThrottleCounter {
+	CoreThrottle,
+	PackageThrottle,
+	CorePowerLimit,
+	PackagePowerLimit,
+}
+
+impl ThrottleCounter {
+	pub const ALL: [Self; 4] = [
+		Self::CoreThrottle,
+		Self::PackageThrottle,
+		Self::CorePowerLimit,
+		Self::PackagePowerLimit,
+	];
+
+	#[inline]
+	pub const fn file_name(self) -> &'static str {
+		match self {
+			Self::CoreThrottle => "core_throttle_count",
+			Self::PackageThrottle => "package_throttle_count",
+			Self::CorePowerLimit => "core_power_limit_count",
+			Self::PackagePowerLimit => "package_power_limit_count",
+		}
+	}
+
+	#[inline]
+	pub const fn is_package(self) -> bool {
+		matches!(self, Self::PackageThrottle | Self::PackagePowerLimit)
+	}
+
+	#[inline]
+	pub const fn head(self) -> &'static str {
+		match self {
+			Self::CoreThrottle => "# Core thermal throttle (events/s)",
+			Self::PackageThrottle => "# Package thermal throttle (events/s)",
+			Self::CorePowerLimit => "# Core power limit (events/s)",
+			Self::PackagePowerLimit => "# Package power limit (events/s)",
+		}
+	}
+}
+
+#[derive(Debug)]
+struct ThrottleCpu {
+	thermal_throttle: PathBuf,
+	package_id: Option<u32>,
+}
+
+/// Cumulative `thermal_throttle` counters of all CPUs found under `root`
+/// (normally [`CPU_SYSFS_PATH`]).
+#[derive(Debug)]
+pub struct ThrottleCounters {
+	cpus: Vec<ThrottleCpu>,
+}
+
+impl ThrottleCounters {
+	pub fn new(root: impl AsRef<Path>) -> io::Result<Self> {
+		let mut cpus = Vec::new();
+		for entry in fs::read_dir(root)? {
+			let entry = entry?;
+			let name = entry.file_name();
+			let is_cpu = name.to_str().and_then(|a| a.strip_prefix("cpu")).is_some_and(|a| {
+				!a.is_empty() && a.bytes().all(|a| a.is_ascii_digit())
+			});
+			if !is_cpu {
+				continue;
+			}
+
+			let path = entry.path();
+			let thermal_throttle = path.join("thermal_throttle");
+			if !thermal_throttle.is_dir() {
+				continue;
+			}
+			let package_id = read_u64(path.join("topology/physical_package_id")).map(|a| a as u32);
+
+			cpus.push(ThrottleCpu {
+				thermal_throttle,
+				package_id,
+			});
+		}
+
+		Ok(Self { cpus })
+	}
+
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.cpus.is_empty()
+	}
+
+	pub fn available(&self) -> impl Iterator<Item = ThrottleCounter> + '_ {
+		ThrottleCounter::ALL.into_iter().filter(|counter| {
+			self.cpus
+				.iter()
+				.any(|cpu| cpu.thermal_throttle.join(counter.file_name()).is_file())
+		})
+	}
+
+	/// Sum of the counter over all CPUs, package counters are taken once per
+	/// physical package.
+	pub fn read(&self, counter: ThrottleCounter) -> Option<u64> {
+		let mut packages: HashMap<Option<u32>, u64> = HashMap::new();
+		let mut sum = None;
+		for cpu in &self.cpus {
+			if let Some(v) = read_u64(cpu.thermal_throttle.join(counter.file_name())) {
+				match counter.is_package() {
+					true => {
+						let package = packages.entry(cpu.package_id).or_default();
+						*package = (*package).max(v);
+					}
+					false => *sum.get_or_insert(0) += v,
+				}
+			}
+		}
+
+		match counter.is_package() {
+			true if !packages.is_empty() => Some(packages.values().sum()),
+			true => None,
+			false => sum,
+		}
+	}
+}
+
+fn read_u64(path: impl AsRef<Path>) -> Option<u64> {
+	fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+enum ThrottleEvents {
+	QueueDraw(f64, f64),
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn vinotebook_append_page(
+	app_config: &Rc<AppConfig>,
+	vigraph_surface: &ViGraphBackgroundSurface,
+	width: impl Maybe<i32> + Copy,
+	height: impl Maybe<i32> + Copy,
+	len: usize,
+	sampling_time: Duration,
+
+	vinotebook: &ViNotebook,
+) {
+	let counters = match ThrottleCounters::new(CPU_SYSFS_PATH) {
+		Ok(a) if !a.is_empty() => a,
+		Ok(_) => {
+			error!("#[throttle] No thermal_throttle counters were found in the system, there is nothing to do on this platform.");
+
+			return;
+		}
+		Err(e) => {
+			error!("#[throttle] I can't read {:?}: {}", CPU_SYSFS_PATH, e);
+
+			return;
+		}
+	};
+
+	let rvbox = vinotebook.append_page(&**app_config, "throttle", None);
+	let mut a_counters = Vec::with_capacity(ThrottleCounter::ALL.len());
+	for counter in counters.available() {
+		trace!("#[throttle] {:?}", counter);
+		let id = SensorId::new("throttle", "cpu", counter.file_name().trim_end_matches("_count"));
+		if !SENSOR_REGISTRY.is_visible(&id) {
+			continue;
+		}
+		let entry = SENSOR_REGISTRY.register(
+			id,
+			counter.head().trim_start_matches("# "),
+			SensorKind::Other,
+			SensorUnit::PerSecond,
+		);
+		let head = entry.head_or(counter.head());
+		let stream = ViGraphArcSyncStream::with_len(len);
+		let (sender, recv) = async_channel::bounded(32);
+
+		let vimetr = ViMeter::new_visender(
+			app_config.clone(),
+			&*head,
+			width,
+			height,
+			stream.clone(),
+			Some(vigraph_surface.clone()),
+			1.0,
+		);
+		vimetr.set_visible_graph(true);
+		vimetr.set_visible_limit(true);
+		rvbox.pack_start(&*vimetr, false, false, 0);
+
+		glib::MainContext::default().spawn_local(enc!((app_config) async move {
+			let mut f64sbuff = F64SBuff::new();
+			let color_config = app_config.get_color_app_config();
+
+			let mut old_current = Default::default();
+			let mut old_max = Default::default();
+			let mut is_throttling = false;
+			while let Ok(event) = recv.recv().await {
+				match event {
+					ThrottleEvents::QueueDraw(current, max) => {
+						if current != old_current {
+							vimetr.set_current_and_queue_draw(&f64sbuff.format_and_get(current));
+							old_current = current;
+						}
+						if max != old_max {
+							vimetr.set_limit_and_queue_draw(&f64sbuff.format_and_get(max));
+							old_max = max;
+						}
+						vimetr.queue_draw();
+
+						match (is_throttling, current > 0.0) {
+							(false, true) => {
+								is_throttling = true;
+
+								// throttling has started
+								vimetr.flash_and_queue_draw().await;
+							}
+							(true, false) => {
+								is_throttling = false;
+
+								vimetr.set_color_and_queue_draw(color_config.green());
+							}
+							_ => {}
+						}
+					},
+				}
+			}
+		}));
+
+		a_counters.push((counter, None, 1.0, entry, stream, sender));
+	}
+
+	std::thread::spawn(move || {
+		let mut last_time = Instant::now();
+		loop {
+			std::thread::sleep(sampling_time);
+			let elapsed = last_time.elapsed().as_secs_f64();
+			last_time = Instant::now();
+
+			for (counter, last_value, max, entry, stream, sender) in &mut a_counters {
+				let value = counters.read(*counter);
+				let current = match (value, *last_value) {
+					(Some(value), Some(last_value)) if value >= last_value => {
+						(value - last_value) as f64 / elapsed
+					}
+					_ => 0.0,
+				};
+				*last_value = value;
+
+				if current > *max {
+					*max = current;
+				}
+				let sample = entry.update(current, 0.0, *max);
+				stream.push_next(sample.fraction());
+
+				if sender
+					.send_blocking(ThrottleEvents::QueueDraw(sample.value, sample.max))
+					.is_err()
+				{
+					return;
+				}
+			}
+		}
+	});
+}
@@ -1,21 +1,39 @@
 use crate::app::config::AppConfig;
 use crate::core::f64sbuff::F64SBuff;
 use crate::core::maybe::Maybe;
+use crate::core::sensorregistry::SENSOR_REGISTRY;
+use crate::core::sensorregistry::SensorId;
+use crate::core::sensorregistry::SensorKind;
+use crate::core::sensorregistry::SensorUnit;
+use crate::core::smartselftest::SmartSelftest;
+use crate::core::smartselftest::SMART_SELFTEST_POLL_INTERVAL;
+use crate::core::smartselftest::SmartSelftestKind;
+use crate::core::smartselftest::SmartSelftestState;
+use crate::core::threshold::ThresholdLevel;
 use crate::widgets::ViMeter;
 use crate::widgets::notebook::ViNotebook;
 use crate::widgets::primitives::graph::background::ViGraphBackgroundSurface;
 use crate::widgets::primitives::graph::stream::ViGraphArcSyncStream;
 use crate::widgets::primitives::graph::stream::ViGraphStream;
+use crate::widgets::primitives::iconmenuitem::ViIconMenuItem;
 use crate::widgets::primitives::label::ViLabel;
 use async_channel::Receiver;
+use async_channel::Sender;
 use dbus_udisks2::Disks;
 use dbus_udisks2::UDisks2;
 use dbus_udisks2::smart::SmartValue;
 use enclose::enc;
 use gtk::Align;
 use gtk::Box;
+use gtk::EventBox;
+use gtk::Menu;
 use gtk::pango::Weight;
 use gtk::traits::BoxExt;
+use gtk::traits::ContainerExt;
+use gtk::traits::EventBoxExt;
+use gtk::traits::GtkMenuExt;
+use gtk::traits::GtkMenuItemExt;
+use gtk::traits::MenuShellExt;
 use gtk::traits::WidgetExt;
 use log::error;
 use log::trace;
@@ -25,6 +43,7 @@ use std::sync::Arc;
 use std::sync::Barrier;
 use std::sync::OnceLock;
 use std::time::Duration;
+use std::time::Instant;
 
 #[repr(transparent)]
 #[derive(Debug)]
@@ -79,10 +98,44 @@ struct U2Item {
 	model_info: ModelInfo,
 	stream: ViGraphArcSyncStream,
 	recv: Receiver<U2Events>,
+	/// `None` - the drive has no SMART self-tests (NVMe).
+	commands: Option<Sender<U2Commands>>,
 }
 
 enum U2Events {
-	QueueDraw(f64, f64),
+	QueueDraw(f64, f64, ThresholdLevel),
+	Selftest(SmartSelftestState),
+	SelftestError(String),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum U2Commands {
+	SelftestStart(SmartSelftestKind),
+	SelftestAbort,
+}
+
+/// The self-test state of a drive, polled only while a test runs.
+#[derive(Debug)]
+struct SelftestPoll {
+	commands: Receiver<U2Commands>,
+	last: Option<SmartSelftestState>,
+	next: Option<Instant>,
+}
+
+impl SelftestPoll {
+	fn new(commands: Receiver<U2Commands>) -> Self {
+		Self {
+			commands,
+			last: None,
+			// once at startup, the result of the last test
+			next: Some(Instant::now()),
+		}
+	}
+
+	#[inline]
+	fn is_time(&self) -> bool {
+		self.next.is_some_and(|a| a <= Instant::now())
+	}
 }
 
 struct ModelInfo {
@@ -120,6 +173,9 @@ pub fn vinotebook_append_page(
 		let mut a_sensors = Vec::with_capacity(12);
 		trace!("udisks2:");
 		if let Ok(udisks2) = UDisks2::new() {
+			let selftest = SmartSelftest::new_system().map_err(|e| {
+				error!("#[udisks2, selftest] SMART self-tests are not available: {}", e);
+			}).ok();
 			let disks = Disks::new(&udisks2);
 			for device in disks.devices {
 				trace!("	{}(model), {}(serial)", device.drive.model, device.drive.serial);
@@ -128,8 +184,18 @@ pub fn vinotebook_append_page(
 					let model = device.drive.model.clone();
 					let serial = device.drive.serial.clone();
 
+					let id = SensorId::new("udisks2", &serial, "temperature");
+					if !SENSOR_REGISTRY.is_visible(&id) {
+						continue;
+					}
+					let entry = SENSOR_REGISTRY.register(
+						id,
+						model,
+						SensorKind::Temperature,
+						SensorUnit::Celsius,
+					);
 					let model_info = ModelInfo {
-						name: model,
+						name: entry.name().to_string(),
 						serial,
 					};
 
@@ -150,13 +216,22 @@ pub fn vinotebook_append_page(
 
 					let stream = ViGraphArcSyncStream::with_len(len);
 					let (sender, recv) = async_channel::bounded(32);
+					let (commands, selftest_poll) = match selftest.as_ref().is_some_and(|a| a.is_supported(&device.drive.path)) {
+						true => {
+							let (commands, commands_recv) = async_channel::bounded(4);
+
+							(Some(commands), Some(SelftestPoll::new(commands_recv)))
+						},
+						false => (None, None),
+					};
 					exp_init_sensors.push(U2Item {
 						model_info,
 						stream: stream.clone(),
 						recv,
+						commands,
 					});
 
-					a_sensors.push((device.drive, stream, sender));
+					a_sensors.push((device.drive, entry, stream, sender, selftest_poll));
 				}
 			}
 
@@ -167,16 +242,52 @@ pub fn vinotebook_append_page(
 			}
 			if !a_sensors.is_empty() {
 				loop {
-					for (device, stream, sender) in &a_sensors {
-						let min = 0.0;
-						let max = 100.0;
+					for (device, entry, stream, sender, selftest_poll) in &mut a_sensors {
+						if let (Some(selftest), Some(poll)) = (&selftest, selftest_poll) {
+							while let Ok(command) = poll.commands.try_recv() {
+								trace!("#[udisks2, selftest] {}(serial): {:?}", device.serial, command);
+								let result = match command {
+									U2Commands::SelftestStart(kind) => selftest.start(&device.path, kind),
+									U2Commands::SelftestAbort => selftest.abort(&device.path),
+								};
+
+								match result {
+									Ok(()) => {
+										poll.last = None;
+										poll.next = Some(Instant::now());
+									},
+									Err(e) => {
+										error!("#[udisks2, selftest] {}(serial): {:?}, err: {}", device.serial, command, e);
+										let _e = sender.send_blocking(U2Events::SelftestError(
+											e.message().unwrap_or("request failed").to_string()
+										));
+									},
+								}
+							}
+
+							if poll.is_time() {
+								poll.next = None;
+								if let Ok(state) = selftest.state(&device.path) {
+									if poll.last != Some(state) {
+										let _e = sender.send_blocking(U2Events::Selftest(state));
+										poll.last = Some(state);
+									}
+									if state.is_in_progress() {
+										poll.next = Some(Instant::now() + SMART_SELFTEST_POLL_INTERVAL);
+									}
+								}
+							}
+						}
+
+						let over = entry.sensor_override();
+						let (min, max) = over.range(0.0, 100.0);
 						let mut exp_elements = graph_count_elements_on_onestep.get();
 						let mut current = 0.0;
 						loop {
-							if let Ok(SmartValue::Enabled(smart_data)) = udisks2.smart_attributes(device, true) {
+							if let Ok(SmartValue::Enabled(smart_data)) = udisks2.smart_attributes(&*device, true) {
 								current = smart_data.temperature - 273.15;
 
-								let a = (current - min) / (max - min);
+								let a = (over.calibrate(current) - min) / (max - min);
 								stream.write(|stream| {
 									stream.push_next(a);
 								});
@@ -189,7 +300,9 @@ pub fn vinotebook_append_page(
 							std::thread::sleep(graph_time_onestep);
 						}
 
-						let _e = sender.send_blocking(U2Events::QueueDraw(current, max));
+						let sample = entry.update(current, min, max);
+						let level = entry.thresholds().level(sample.value, sample.min, sample.max);
+						let _e = sender.send_blocking(U2Events::QueueDraw(sample.value, sample.max, level));
 					}
 
 					std::thread::sleep(complete_redraw_step_time);
@@ -249,10 +362,24 @@ pub fn vinotebook_append_page(
 				0,
 			);
 
-			rvbox.pack_start(&vbox, false,
+			let drive_vbox = Box::new(gtk::Orientation::Vertical, 0);
+			drive_vbox.set_visible(true);
+			drive_vbox.pack_start(&vbox, false,
 				false,
 				0,);
 
+			let selftest_label = ViLabel::new(
+				"info_vitextmeter",
+				&**app_config,
+				"",
+				Weight::Normal,
+			)
+			.set_margin_start(4)
+			.set_margin_bottom(2)
+			.set_align(Align::Start)
+			.set_visible(false);
+			drive_vbox.pack_start(&selftest_label, false, false, 0);
+
 			let vimetr = ViMeter::new_visender(
 				app_config.clone(),
 				item.model_info.serial.as_str(),
@@ -263,7 +390,27 @@ pub fn vinotebook_append_page(
 				1.0,
 			);
 			vimetr.set_visible_graph(true);
-			rvbox.pack_start(&*vimetr, false, false, 0);
+			drive_vbox.pack_start(&*vimetr, false, false, 0);
+
+			// context menu: SMART self-tests
+			let evbox = EventBox::new();
+			evbox.set_visible_window(false);
+			evbox.add(&drive_vbox);
+			evbox.set_visible(true);
+
+			if let Some(ref commands) = item.commands {
+				let menu = selftest_menu(commands);
+				evbox.connect_button_press_event(move |_, event| {
+					if event.button() == 3 {
+						menu.popup_at_pointer(Some(&**event));
+
+						return true.into();
+					}
+
+					false.into()
+				});
+			}
+			rvbox.pack_start(&evbox, false, false, 0);
 
 			glib::MainContext::default().spawn_local(
 				enc!((item.recv => item) async move {
@@ -273,7 +420,7 @@ pub fn vinotebook_append_page(
 					let mut old_max = Default::default();
 					while let Ok(event) = item.recv().await {
 						match event {
-							U2Events::QueueDraw(current, max) => {
+							U2Events::QueueDraw(current, max, level) => {
 								if current != old_current {
 									vimetr.set_current_and_queue_draw(&f64sbuff.format_and_get(current));
 									old_current = current;
@@ -282,9 +429,25 @@ pub fn vinotebook_append_page(
 									vimetr.set_limit_and_queue_draw(&f64sbuff.format_and_get(max));
 									old_max = max;
 								}
+								vimetr.set_level_and_queue_draw(level);
 
 								vimetr.queue_draw();
 							},
+							U2Events::Selftest(state) => {
+								selftest_label.set_text(&match state.is_in_progress() {
+									true => format!(
+										"SMART self-test: {}, {}% remaining",
+										state.status_as_str(),
+										state.percent_remaining
+									),
+									false => format!("SMART self-test: {}", state.status_as_str()),
+								});
+								selftest_label.set_visible2(true);
+							},
+							U2Events::SelftestError(e) => {
+								selftest_label.set_text(&format!("SMART self-test: {}", e));
+								selftest_label.set_visible2(true);
+							},
 						}
 					}
 				}
@@ -296,3 +459,34 @@ pub fn vinotebook_append_page(
 		error!("#[lm_sensors, recv] Feedback is broken, i can't continue initialization.");
 	}
 }
+
+fn selftest_menu(commands: &Sender<U2Commands>) -> Menu {
+	let menu = Menu::new();
+	for (icon, value, command) in [
+		(
+			"media-playback-start-symbolic",
+			"Start short SMART self-test",
+			U2Commands::SelftestStart(SmartSelftestKind::Short),
+		),
+		(
+			"media-playback-start-symbolic",
+			"Start extended SMART self-test",
+			U2Commands::SelftestStart(SmartSelftestKind::Extended),
+		),
+		(
+			"media-playback-stop-symbolic",
+			"Abort SMART self-test",
+			U2Commands::SelftestAbort,
+		),
+	] {
+		let menu_item = ViIconMenuItem::new(icon, value);
+		menu_item.connect_activate(enc!((commands) move |_| {
+			if let Err(e) = commands.try_send(command) {
+				error!("#[udisks2, selftest] I can't send command: {:?}, err: {:?}", command, e);
+			}
+		}));
+		menu.append(&*menu_item);
+	}
+
+	menu
+}
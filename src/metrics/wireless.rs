@@ -0,0 +1,433 @@
+use crate::app::config::AppConfig;
+use crate::core::f64sbuff::F64SBuff;
+use crate::core::maybe::Maybe;
+use crate::core::sensorregistry::SENSOR_REGISTRY;
+use crate::core::sensorregistry::SensorId;
+use crate::core::sensorregistry::SensorKind;
+use crate::core::sensorregistry::SensorUnit;
+use crate::core::threshold::ThresholdLevel;
+use crate::widgets::ViMeter;
+use crate::widgets::notebook::ViNotebook;
+use crate::widgets::primitives::graph::background::ViGraphBackgroundSurface;
+use crate::widgets::primitives::graph::stream::ViGraphArcSyncStream;
+use crate::widgets::primitives::graph::stream::ViGraphStream;
+use crate::widgets::primitives::label::ViLabel;
+use gtk::Align;
+use gtk::pango::Weight;
+use gtk::traits::BoxExt;
+use log::error;
+use log::trace;
+use log::warn;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+use std::process::Stdio;
+use std::rc::Rc;
+use std::time::Duration;
+use std::time::Instant;
+
+pub const PROC_NET_WIRELESS_PATH: &str = "/proc/net/wireless";
+pub const SYS_CLASS_NET_PATH: &str = "/sys/class/net";
+
+/// cfg80211 reports the link quality out of 70.
+pub const WIRELESS_MAX_QUALITY: f64 = 70.0;
+/// Signal level range of the meter (dBm).
+pub const WIRELESS_MIN_LEVEL: f64 = -100.0;
+pub const WIRELESS_MAX_LEVEL: f64 = -30.0;
+/// `iw` is a process per sample, the bitrate is read much less often than
+/// the signal level (it changes with the rate control, not per sample).
+pub const IW_BITRATE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// One interface line of `/proc/net/wireless`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WirelessStatus {
+	pub iface: String,
+	/// Link quality (0 - [`WIRELESS_MAX_QUALITY`]).
+	pub link: f64,
+	/// Signal level (dBm).
+	pub level: f64,
+	/// Noise level (dBm).
+	pub noise: f64,
+}
+
+pub fn parse_proc_net_wireless(data: &str) -> Vec<WirelessStatus> {
+	let mut result = Vec::new();
+	// two header lines
+	for line in data.lines().skip(2) {
+		let Some((iface, rest)) = line.split_once(':') else {
+			continue;
+		};
+
+		// status, link, level, noise (`70.`, `-40.`)
+		let mut fields = rest
+			.split_whitespace()
+			.skip(1)
+			.map(|a| a.trim_end_matches('.').parse::<f64>());
+		if let (Some(Ok(link)), Some(Ok(level)), Some(Ok(noise))) =
+			(fields.next(), fields.next(), fields.next())
+		{
+			result.push(WirelessStatus {
+				iface: iface.trim().to_string(),
+				link,
+				level,
+				noise,
+			});
+		}
+	}
+
+	result
+}
+
+/// The `tx bitrate` (Mbit/s) of `iw dev <iface> link`, `None` when not
+/// connected.
+pub fn parse_iw_link(data: &str) -> Option<f64> {
+	data.lines()
+		.find_map(|a| a.trim().strip_prefix("tx bitrate:"))
+		.and_then(|a| a.split_whitespace().next())
+		.and_then(|a| a.parse().ok())
+}
+
+/// Wi-Fi interfaces found under `root` (normally [`SYS_CLASS_NET_PATH`]).
+pub fn wireless_interfaces(root: impl AsRef<Path>) -> io::Result<Vec<String>> {
+	let mut result = Vec::new();
+	for entry in fs::read_dir(root)? {
+		let entry = entry?;
+		let path = entry.path();
+		// `wireless` only with wireless extensions, `phy80211` for any cfg80211 device
+		if (path.join("wireless").is_dir() || path.join("phy80211").exists())
+			&& let Some(name) = entry.file_name().to_str()
+		{
+			result.push(name.to_string());
+		}
+	}
+	result.sort();
+
+	Ok(result)
+}
+
+fn read_iface_is_up(iface: &str) -> bool {
+	fs::read_to_string(Path::new(SYS_CLASS_NET_PATH).join(iface).join("operstate"))
+		.is_ok_and(|a| a.trim() == "up")
+}
+
+fn read_iw_bitrate(iface: &str) -> Option<f64> {
+	let output = Command::new("iw")
+		.args(["dev", iface, "link"])
+		.stdin(Stdio::null())
+		.stderr(Stdio::null())
+		.output()
+		.ok()?;
+
+	parse_iw_link(&String::from_utf8_lossy(&output.stdout))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WirelessMeter {
+	Level,
+	Quality,
+	Bitrate,
+}
+
+impl WirelessMeter {
+	#[inline]
+	const fn head(self) -> &'static str {
+		match self {
+			Self::Level => "# Signal level (dBm)",
+			Self::Quality => "# Link quality (%)",
+			Self::Bitrate => "# Bitrate (Mbit/s)",
+		}
+	}
+
+	/// The feature of the sensor ID.
+	#[inline]
+	const fn sensor(self) -> (&'static str, SensorKind, SensorUnit) {
+		match self {
+			Self::Level => ("signal", SensorKind::Signal, SensorUnit::Dbm),
+			Self::Quality => ("quality", SensorKind::Signal, SensorUnit::Percent),
+			Self::Bitrate => ("bitrate", SensorKind::Bitrate, SensorUnit::Mbps),
+		}
+	}
+}
+
+enum WirelessEvents {
+	QueueDraw(f64, f64, ThresholdLevel),
+	Link(bool),
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn vinotebook_append_page(
+	app_config: &Rc<AppConfig>,
+	vigraph_surface: &ViGraphBackgroundSurface,
+	width: impl Maybe<i32> + Copy,
+	height: impl Maybe<i32> + Copy,
+	len: usize,
+	sampling_time: Duration,
+
+	vinotebook: &ViNotebook,
+) {
+	let ifaces = match wireless_interfaces(SYS_CLASS_NET_PATH) {
+		Ok(a) if !a.is_empty() => a,
+		Ok(_) => {
+			error!("#[wireless] No Wi-Fi interfaces were found in the system, there is nothing to do on this platform.");
+
+			return;
+		}
+		Err(e) => {
+			error!("#[wireless] I can't read {:?}: {}", SYS_CLASS_NET_PATH, e);
+
+			return;
+		}
+	};
+	let is_iw = Command::new("iw")
+		.arg("--version")
+		.stdout(Stdio::null())
+		.stderr(Stdio::null())
+		.status()
+		.is_ok_and(|a| a.success());
+	if !is_iw {
+		warn!("#[wireless] `iw` is not available, bitrate meters are disabled.");
+	}
+
+	let rvbox = vinotebook.append_page(
+		&**app_config,
+		"wifi",
+		Some("A dropped link is highlighted."),
+	);
+	let mut a_ifaces = Vec::with_capacity(ifaces.len());
+	for iface in ifaces {
+		trace!("#[wireless] {:?}", iface);
+		rvbox.pack_start(
+			&ViLabel::new("info_vitextmeter", &**app_config, &iface, Weight::Bold)
+				.set_margin_top(4)
+				.set_margin_start(4)
+				.set_margin_bottom(2)
+				.set_align(Align::Start),
+			false,
+			false,
+			0,
+		);
+
+		let mut a_meters = Vec::with_capacity(3);
+		for meter in [
+			WirelessMeter::Level,
+			WirelessMeter::Quality,
+			WirelessMeter::Bitrate,
+		] {
+			if meter == WirelessMeter::Bitrate && !is_iw {
+				continue;
+			}
+			let (feature, kind, unit) = meter.sensor();
+			let id = SensorId::new("wireless", &iface, feature);
+			if !SENSOR_REGISTRY.is_visible(&id) {
+				continue;
+			}
+			let entry = SENSOR_REGISTRY.register(
+				id,
+				format!("{} {}", iface, feature),
+				kind,
+				unit,
+			);
+			let head = entry.head_or(meter.head());
+			let stream = ViGraphArcSyncStream::with_len(len);
+			let (sender, recv) = async_channel::bounded(32);
+
+			let vimetr = ViMeter::new_visender(
+				app_config.clone(),
+				&*head,
+				width,
+				height,
+				stream.clone(),
+				Some(vigraph_surface.clone()),
+				1.0,
+			);
+			vimetr.set_visible_graph(true);
+			vimetr.set_visible_limit(meter == WirelessMeter::Bitrate);
+			rvbox.pack_start(&*vimetr, false, false, 0);
+
+			glib::MainContext::default().spawn_local(async move {
+				let mut f64sbuff = F64SBuff::new();
+
+				let mut old_max = Default::default();
+				let mut old_level = ThresholdLevel::Normal;
+				let mut is_up = true;
+				while let Ok(event) = recv.recv().await {
+					match event {
+						WirelessEvents::QueueDraw(current, max, level) => {
+							vimetr.set_current_and_queue_draw(&f64sbuff.format_and_get(current));
+							if max != old_max {
+								vimetr.set_limit_and_queue_draw(&f64sbuff.format_and_get(max));
+								old_max = max;
+							}
+							// a dropped link stays red
+							if is_up {
+								vimetr.set_level_and_queue_draw(level);
+							}
+							old_level = level;
+							vimetr.queue_draw();
+						},
+						WirelessEvents::Link(new_is_up) if new_is_up != is_up => {
+							is_up = new_is_up;
+							match is_up {
+								true => vimetr.set_level_and_queue_draw(old_level),
+								false => vimetr.flash_and_queue_draw().await,
+							}
+						},
+						WirelessEvents::Link(_) => {},
+					}
+				}
+			});
+
+			a_meters.push((meter, 1.0, entry, stream, sender));
+		}
+
+		// the last `iw` bitrate and its time
+		let last_bitrate: Option<(Instant, Option<f64>)> = None;
+		a_ifaces.push((iface, true, last_bitrate, a_meters));
+	}
+
+	std::thread::spawn(move || {
+		loop {
+			let statuses = fs::read_to_string(PROC_NET_WIRELESS_PATH)
+				.map(|a| parse_proc_net_wireless(&a))
+				.unwrap_or_default();
+
+			for (iface, last_is_up, last_bitrate, a_meters) in &mut a_ifaces {
+				let status = statuses.iter().find(|a| &a.iface == iface);
+				let bitrate = match (status, is_iw) {
+					(Some(_), true) => match last_bitrate {
+						Some((time, bitrate)) if time.elapsed() < IW_BITRATE_INTERVAL => *bitrate,
+						_ => {
+							let bitrate = read_iw_bitrate(iface);
+							*last_bitrate = Some((Instant::now(), bitrate));
+
+							bitrate
+						}
+					},
+					_ => {
+						*last_bitrate = None;
+
+						None
+					}
+				};
+				let is_up = status.is_some_and(|a| a.link > 0.0) && read_iface_is_up(iface);
+				if is_up != *last_is_up {
+					match is_up {
+						true => trace!("#[wireless] {}: link is up", iface),
+						false => warn!("#[wireless] {}: link is down", iface),
+					}
+					*last_is_up = is_up;
+				}
+
+				for (meter, max, entry, stream, sender) in a_meters.iter_mut() {
+					let current = match meter {
+						WirelessMeter::Level => status.map_or(WIRELESS_MIN_LEVEL, |a| a.level),
+						WirelessMeter::Quality => {
+							let quality = status.map_or(0.0, |a| a.link) / WIRELESS_MAX_QUALITY;
+
+							(quality * 100.0).round()
+						}
+						WirelessMeter::Bitrate => {
+							let bitrate = bitrate.unwrap_or_default();
+							if bitrate > *max {
+								*max = bitrate;
+							}
+
+							bitrate
+						}
+					};
+					let (min, range_max) = match meter {
+						WirelessMeter::Level => (WIRELESS_MIN_LEVEL, WIRELESS_MAX_LEVEL),
+						WirelessMeter::Quality => (0.0, 100.0),
+						WirelessMeter::Bitrate => (0.0, *max),
+					};
+					let sample = entry.update(current, min, range_max);
+					stream.push_next(sample.fraction());
+					let level = entry.thresholds().level(sample.value, sample.min, sample.max);
+
+					if sender
+						.send_blocking(WirelessEvents::QueueDraw(sample.value, sample.max, level))
+						.is_err()
+						|| sender.send_blocking(WirelessEvents::Link(is_up)).is_err()
+					{
+						return;
+					}
+				}
+			}
+
+			std::thread::sleep(sampling_time);
+		}
+	});
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn proc_net_wireless() {
+		let data = "\
+Inter-| sta-|   Quality        |   Discarded packets               | Missed | WE
+ face | tus | link level noise |  nwid  crypt   frag  retry   misc | beacon | 22
+wlp2s0: 0000   54.  -56.  -256        0      0      0      0      0        0
+  wlan1: 0000    0     0     0        0      0      0      0      0        0
+";
+		assert_eq!(
+			parse_proc_net_wireless(data),
+			[
+				WirelessStatus {
+					iface: "wlp2s0".into(),
+					link: 54.0,
+					level: -56.0,
+					noise: -256.0,
+				},
+				WirelessStatus {
+					iface: "wlan1".into(),
+					link: 0.0,
+					level: 0.0,
+					noise: 0.0,
+				},
+			]
+		);
+		assert!(parse_proc_net_wireless(&data[..data.find("wlp2s0").unwrap()]).is_empty());
+	}
+
+	#[test]
+	fn iw_link() {
+		let connected = "\
+Connected to aa:bb:cc:dd:ee:ff (on wlp2s0)
+	SSID: home
+	freq: 5180
+	RX: 123456 bytes (789 packets)
+	TX: 23456 bytes (123 packets)
+	signal: -56 dBm
+	rx bitrate: 866.7 MBit/s VHT-MCS 9 80MHz short GI VHT-NSS 2
+	tx bitrate: 780.0 MBit/s VHT-MCS 8 80MHz short GI VHT-NSS 2
+
+	bss flags:	short-slot-time
+	dtim period:	1
+	beacon int:	100
+";
+		assert_eq!(parse_iw_link(connected), Some(780.0));
+		assert_eq!(parse_iw_link("\ttx bitrate: 6.0 MBit/s\n"), Some(6.0));
+		assert_eq!(parse_iw_link("Not connected.\n"), None);
+		assert_eq!(parse_iw_link(""), None);
+	}
+
+	#[test]
+	fn interfaces() {
+		let root = std::env::temp_dir().join(format!("wireless-{}", std::process::id()));
+		let _e = fs::remove_dir_all(&root);
+		// wireless extensions, cfg80211 only, wired
+		fs::create_dir_all(root.join("wlan0/wireless")).unwrap();
+		fs::create_dir_all(root.join("wlp2s0")).unwrap();
+		fs::create_dir_all(root.join("ieee80211/phy0")).unwrap();
+		std::os::unix::fs::symlink("../ieee80211/phy0", root.join("wlp2s0/phy80211")).unwrap();
+		fs::create_dir_all(root.join("eth0")).unwrap();
+
+		assert_eq!(wireless_interfaces(&root).unwrap(), ["wlan0", "wlp2s0"]);
+		assert!(wireless_interfaces(root.join("missing")).is_err());
+
+		let _e = fs::remove_dir_all(&root);
+	}
+}
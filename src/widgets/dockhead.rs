@@ -147,4 +147,11 @@ impl ViDockHead {
 
 		Self(head)
 	}
+
+	/// Packs an additional label (e.g. the current game) after the name.
+	pub fn pack_label(self, label: &ViLabel) -> Self {
+		self.0.pack_start(label, false, true, 0);
+
+		self
+	}
 }
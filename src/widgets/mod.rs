@@ -1,6 +1,8 @@
 use crate::__gen_transparent_gtk_type;
 use crate::app::config::AppConfig;
+use crate::core::eightbitcolor::EightBitColor;
 use crate::core::maybe::Maybe;
+use crate::core::threshold::ThresholdLevel;
 use crate::maybe;
 use crate::widgets::primitives::graph::background::ViGraphBackgroundSurface;
 use crate::widgets::primitives::graph::stream::ViGraphStream;
@@ -14,11 +16,14 @@ use gtk::Box;
 use gtk::ffi::GtkBox;
 use gtk::traits::BoxExt;
 use gtk::traits::WidgetExt;
+use std::cell::Cell;
 use std::ops::Deref;
 use std::rc::Rc;
+use std::time::Duration;
 
 pub mod primitives {
 	pub mod colorblock;
+	pub mod heatrow;
 	pub mod hotkeyitem;
 	pub mod iconmenuitem;
 	pub mod label;
@@ -102,6 +107,7 @@ impl ViMeter {
 
 		ViMeterSender {
 			app_config,
+			level: Cell::new(None),
 			color_and_text: textmeter_sender,
 			graph: graphsender,
 			meter: Self(vbox),
@@ -115,6 +121,8 @@ where
 	S: ViGraphStream,
 {
 	app_config: Rc<AppConfig>,
+	/// The applied level, `None` after a manual color.
+	level: Cell<Option<ThresholdLevel>>,
 	color_and_text: ViTextMeterSender,
 	graph: ViGraphSender<S>,
 	meter: ViMeter,
@@ -162,13 +170,45 @@ where
 		self.color_and_text.set_limit_and_queue_draw(v);
 	}
 
+	#[inline]
+	pub fn set_color_and_queue_draw(&self, color: EightBitColor) {
+		self.level.set(None);
+		self.graph.reset_level();
+		let (red, green, blue) = color.into_rgb();
+
+		self.color_and_text
+			.set_current_color(None);
+		self.color_and_text
+			.set_color_and_queue_draw(red, green, blue);
+	}
+
+	/// The color block, the current value and the graph line by the
+	/// thresholds of the meter.
+	pub fn set_level_and_queue_draw(&self, level: ThresholdLevel) {
+		if self.level.get() == Some(level) {
+			return;
+		}
+		let color_config = self.app_config.get_color_app_config();
+		let (color, current_color) = match level {
+			ThresholdLevel::Normal => (color_config.green(), None),
+			ThresholdLevel::Warning => (color_config.orange(), Some(color_config.orange())),
+			ThresholdLevel::Critical => (color_config.red(), Some(color_config.red())),
+		};
+		let (red, green, blue) = color.into_rgb();
+		self.color_and_text
+			.set_color_and_queue_draw(red, green, blue);
+		self.color_and_text
+			.set_current_color(current_color.map(EightBitColor::into_rgb));
+		self.graph.set_level(level);
+		self.level.set(Some(level));
+	}
+
 	pub fn push_next_and_queue_draw(
 		&self,
 		current: f64,
 		graph_v: impl Maybe<f64>,
 		limit: impl Maybe<f64>,
-		l_red: f64,
-		l_orange: f64,
+		level: ThresholdLevel,
 	) {
 		maybe!((graph_v) {
 			self.set_visible_graph(true);
@@ -177,19 +217,7 @@ where
 			self.set_visible_graph(false);
 		});
 
-		{
-			let color = self.app_config.get_color_app_config();
-			let (red, green, blue) = (if current >= l_red {
-				color.red()
-			} else if current >= l_orange {
-				color.orange()
-			} else {
-				color.green()
-			})
-			.into_rgb();
-			self.color_and_text
-				.set_color_and_queue_draw(red, green, blue);
-		}
+		self.set_level_and_queue_draw(level);
 
 		self.color_and_text
 			.set_current_and_queue_draw(&current.to_string()); // TODO REFACTOING ME
@@ -207,6 +235,19 @@ where
 		ViMeterSender::queue_draw(self);
 	}
 
+	/// Briefly blinks the color block (red/orange) and leaves it red.
+	pub async fn flash_and_queue_draw(&self) {
+		let color_config = self.app_config.get_color_app_config();
+		for i in 0..8 {
+			self.set_color_and_queue_draw(match i % 2 {
+				0 => color_config.red(),
+				_ => color_config.orange(),
+			});
+			glib::timeout_future(Duration::from_millis(120)).await;
+		}
+		self.set_color_and_queue_draw(color_config.red());
+	}
+
 	#[inline]
 	pub fn queue_draw(&self) {
 		self.graph.queue_draw();
@@ -1,6 +1,8 @@
 use crate::__gen_transparent_gtk_type;
 use crate::app::config::AppConfig;
 use crate::core::maybe::Maybe;
+use crate::core::threshold::ThresholdLevel;
+use crate::core::threshold::Thresholds;
 use crate::maybe;
 use crate::widgets::primitives::graph::background::ViGraphBackgroundSurface;
 use crate::widgets::primitives::graph::stream::ViGraphStream;
@@ -12,6 +14,7 @@ use gtk::cairo::Context;
 use gtk::cairo::ImageSurface;
 use gtk::ffi::GtkDrawingArea;
 use gtk::traits::WidgetExt;
+use std::cell::Cell;
 use std::cell::RefCell;
 use std::ops::Deref;
 use std::rc::Rc;
@@ -49,6 +52,8 @@ impl ViGraph {
 		S: ViGraphStream,
 	{
 		let cache_surface = Rc::new(RefCell::new(ViGraphCachedSurface::empty()));
+		// `None` - by the default thresholds of the normalized value
+		let level: Rc<Cell<Option<ThresholdLevel>>> = Default::default();
 
 		let graph_area = DrawingArea::new();
 		graph_area.set_size_request(
@@ -72,7 +77,7 @@ impl ViGraph {
 		}
 
 		graph_area.connect_draw(
-			enc!((stream, background_surface, app_config, cache_surface) move |da, in_cr| {
+			enc!((stream, background_surface, app_config, cache_surface, level) move |da, in_cr| {
 				let (width, height) = {
 					let allocation = da.allocation();
 
@@ -114,17 +119,13 @@ impl ViGraph {
 								let (r, g, b, transparent) = {
 									let color_config = app_config.get_color_app_config();
 
-									match data.back() {
-										Some(aback) => {
-											if aback >= 0.85 {
-												color_config.red().into_rgba(transparent)
-											} else if aback >= 0.75 {
-												color_config.orange().into_rgba(transparent)
-											} else {
-												color_config.green().into_rgba(transparent)
-											}
-										}
-										None => color_config.green().into_rgba(transparent)
+									let level = level.get().or_else(|| {
+										data.back().map(|a| Thresholds::default().level_normalized(a))
+									});
+									match level {
+										Some(ThresholdLevel::Critical) => color_config.red().into_rgba(transparent),
+										Some(ThresholdLevel::Warning) => color_config.orange().into_rgba(transparent),
+										Some(ThresholdLevel::Normal) | None => color_config.green().into_rgba(transparent),
 									}
 								};
 
@@ -182,6 +183,7 @@ impl ViGraph {
 		ViGraphSender {
 			stream,
 			cache_surface,
+			level,
 			vi: Self(graph_area),
 		}
 	}
@@ -193,6 +195,7 @@ where
 {
 	stream: S,
 	cache_surface: Rc<RefCell<ViGraphCachedSurface>>,
+	level: Rc<Cell<Option<ThresholdLevel>>>,
 	vi: ViGraph,
 }
 
@@ -222,6 +225,19 @@ where
 		self.stream.push_next(v);
 	}
 
+	/// The color of the line, instead of the default thresholds of the
+	/// normalized value.
+	#[inline]
+	pub fn set_level(&self, level: ThresholdLevel) {
+		self.level.set(Some(level));
+	}
+
+	/// Back to the default thresholds of the normalized value.
+	#[inline]
+	pub fn reset_level(&self) {
+		self.level.set(None);
+	}
+
 	#[inline]
 	pub fn queue_draw(&self) {
 		{
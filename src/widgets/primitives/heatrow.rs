@@ -0,0 +1,100 @@
+use crate::__gen_transparent_gtk_type;
+use crate::app::config::AppConfig;
+use enclose::enc;
+use gtk::{
+	DrawingArea,
+	ffi::GtkDrawingArea,
+	traits::{StyleContextExt, WidgetExt},
+};
+use std::{cell::RefCell, rc::Rc};
+
+/// Scales the values of the cells by the largest one, so the busiest cell is
+/// 1.0 (all 0.0 if there is no load).
+pub fn normalize_by_max(mut values: Vec<f64>) -> Vec<f64> {
+	let max = values.iter().copied().fold(0.0, f64::max);
+	for a in &mut values {
+		*a = match max > 0.0 {
+			true => *a / max,
+			false => 0.0,
+		};
+	}
+
+	values
+}
+
+/// A row of cells (e.g. one per CPU core), each cell is colored by its
+/// normalized value (0.0 - green, 0.75 - orange, 0.85 - red).
+#[repr(transparent)]
+#[derive(Debug)]
+pub struct ViHeatRow(DrawingArea);
+
+__gen_transparent_gtk_type! {
+	#[sys(GtkDrawingArea)]
+	ViHeatRow(
+		new |a: DrawingArea| {
+			Self(a)
+		},
+		ref |sself| {
+			&sself.0
+		},
+		into |sself| {
+			sself.0
+		},
+	)
+}
+
+impl ViHeatRow {
+	pub fn new(height: i32) -> Self {
+		let drawing_area = DrawingArea::new();
+		drawing_area.style_context().add_class("viheatrow");
+		drawing_area.set_size_request(-1, height);
+		drawing_area.set_visible(true);
+
+		Self(drawing_area)
+	}
+
+	pub fn connect_state_values(
+		self,
+		app_config: Rc<AppConfig>,
+		transparent: f64,
+		rcptr: &Rc<RefCell<Vec<f64>>>,
+	) -> Self {
+		self.0.connect_draw(enc!((rcptr) move |da, cr| {
+			let allocation = da.allocation();
+			let (width, height): (f64, f64) = (allocation.width().into(), allocation.height().into());
+			let color_config = app_config.get_color_app_config();
+
+			let values = RefCell::borrow(&rcptr);
+			if values.is_empty() {
+				return true.into();
+			}
+
+			let x_step = width / values.len() as f64;
+			for (i, a) in values.iter().copied().enumerate() {
+				let (r, g, b) = if a >= 0.85 {
+					color_config.red().into_rgb()
+				} else if a >= 0.75 {
+					color_config.orange().into_rgb()
+				} else {
+					// background -> green
+					let (gr, gg, gb) = color_config.green().into_rgb();
+					let a = a.max(0.0) / 0.75;
+
+					(
+						0.220 + (gr - 0.220) * a,
+						0.220 + (gg - 0.220) * a,
+						0.220 + (gb - 0.220) * a,
+					)
+				};
+
+				cr.set_source_rgba(r, g, b, transparent);
+				cr.rectangle(i as f64 * x_step, 0.0, (x_step - 1.0).max(1.0), height);
+				let _e = cr.fill();
+			}
+
+			true.into()
+		}));
+
+		self
+	}
+}
@@ -5,7 +5,7 @@ use gtk::pango;
 use gtk::{
 	Align, Label,
 	ffi::GtkLabel,
-	pango::{AttrFontDesc, AttrList, FontDescription},
+	pango::{AttrColor, AttrFontDesc, AttrList, AttrType, FontDescription},
 	traits::{LabelExt, StyleContextExt, WidgetExt},
 };
 pub use pango::Weight;
@@ -149,6 +149,25 @@ impl ViLabel {
 		self
 	}
 
+	/// The text color over the CSS one, `None` - the CSS one.
+	pub fn set_foreground(&self, rgb: Option<(f64, f64, f64)>) {
+		let attrs = self
+			.0
+			.attributes()
+			.and_then(|a| a.copy())
+			.unwrap_or_else(AttrList::new);
+		let _e = attrs.filter(|a| a.type_() == AttrType::Foreground);
+		if let Some((red, green, blue)) = rgb {
+			let into_u16 = |a: f64| (a.clamp(0.0, 1.0) * u16::MAX as f64) as u16;
+			attrs.insert(AttrColor::new_foreground(
+				into_u16(red),
+				into_u16(green),
+				into_u16(blue),
+			));
+		}
+		self.0.set_attributes(Some(&attrs));
+	}
+
 	#[inline]
 	pub fn set_visible2(&self, v: bool) {
 		self.0.set_visible(v);
@@ -172,6 +172,12 @@ impl ViTextMeterSender {
 		self.color.1.queue_draw();
 	}
 
+	/// The color of the current value, `None` - the default one.
+	#[inline]
+	pub fn set_current_color(&self, rgb: Option<(f64, f64, f64)>) {
+		self.current.set_foreground(rgb);
+	}
+
 	pub fn set_current_and_queue_draw(&self, v: &str) {
 		let v = v.get(..6).map_or(v, |v| v);
 